@@ -1,6 +1,12 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
-use crate::{CodecOptions, SteganoEncoder, SteganoError};
+use ed25519_dalek::SigningKey;
+
+use crate::media::payload::PaddingBuckets;
+use crate::{CodecOptions, Metadata, SteganoEncoder, SteganoError, TimestampToken};
 
 use super::Password;
 
@@ -16,6 +22,13 @@ pub struct HideApi {
     image: Option<PathBuf>,
     output: Option<PathBuf>,
     password: Password,
+    threshold_passwords: Vec<Password>,
+    threshold: u8,
+    padding: Option<PaddingBuckets>,
+    metadata: Option<Metadata>,
+    comments: HashMap<String, String>,
+    signing_key: Option<SigningKey>,
+    timestamp: Option<TimestampToken>,
     options: CodecOptions,
 }
 
@@ -84,6 +97,57 @@ impl HideApi {
         self
     }
 
+    /// Set multiple passwords of which any `threshold` are required for unveiling
+    /// Note: this takes precedence over a single password set by `using_password`
+    pub fn using_threshold_passwords<P: Into<Password>>(
+        mut self,
+        passwords: impl IntoIterator<Item = P>,
+        threshold: u8,
+    ) -> Self {
+        self.threshold_passwords = passwords.into_iter().map(Into::into).collect();
+        self.threshold = threshold;
+        self
+    }
+
+    /// Pad the hidden data up to the given size buckets, so that its exact size cannot be inferred
+    pub fn with_padding(mut self, buckets: PaddingBuckets) -> Self {
+        self.padding = Some(buckets);
+        self
+    }
+
+    /// Hide the given metadata record (author, creation time, app version) along with the data
+    pub fn with_metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Hides the given RFC 3161 timestamp token along with the files
+    pub fn with_timestamp_token(mut self, token: TimestampToken) -> Self {
+        self.timestamp = Some(token);
+        self
+    }
+
+    /// Hides a manifest of all files, signed with the given Ed25519 secret key
+    pub fn signed_with(mut self, secret_key: &[u8; 32]) -> Self {
+        self.signing_key = Some(SigningKey::from_bytes(secret_key));
+        self
+    }
+
+    /// Attach a comment to the hidden file, the file is matched by its file name
+    pub fn with_file_comment<A: AsRef<Path>, S: Into<String>>(
+        mut self,
+        file: A,
+        comment: S,
+    ) -> Self {
+        let name = file
+            .as_ref()
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.comments.insert(name, comment.into());
+        self
+    }
+
     /// Execute the hiding process and blocks until it is finished
     pub fn execute(self) -> Result<(), SteganoError> {
         self.validate()?;
@@ -101,14 +165,43 @@ impl HideApi {
             s.with_encryption(password);
         }
 
-        if let Some(message) = self.message {
-            s.add_message(message.as_str())?;
+        if !self.threshold_passwords.is_empty() {
+            let passwords = self
+                .threshold_passwords
+                .iter()
+                .filter_map(|p| p.as_ref().clone());
+            s.with_threshold_encryption(passwords, self.threshold);
         }
 
+        if let Some(buckets) = self.padding {
+            s.with_padding(buckets);
+        }
+
+        if let Some(metadata) = self.metadata {
+            s.with_metadata(metadata);
+        }
+
+        if let Some(token) = self.timestamp {
+            s.with_timestamp_token(token);
+        }
+
+        if let Some(key) = self.signing_key.as_ref() {
+            s.with_signing_key(&key.to_bytes());
+        }
+
+        for (name, comment) in self.comments {
+            s.with_file_comment(&name, comment);
+        }
+
+        // `add_files` starts over with the files, so the text has to be added after them
         if let Some(files) = self.files {
             s.add_files(&files)?;
         }
 
+        if let Some(message) = self.message {
+            s.add_message(message.as_str())?;
+        }
+
         s.hide_and_save()?;
 
         Ok(())
@@ -175,6 +268,27 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn should_hide_padded_and_encrypted() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let secret_image = temp_dir.path().join("image-with-secret.png");
+        crate::api::hide::prepare()
+            .with_message("Hello, World!")
+            .with_image("tests/images/plain/carrier-image.png")
+            .using_password("SuperSecret42")
+            .with_padding(crate::media::payload::PaddingBuckets::PowersOfTwo)
+            .with_output(&secret_image)
+            .execute()
+            .expect("Failed to hide message in image");
+
+        let files = crate::api::unveil::prepare()
+            .from_secret_file(&secret_image)
+            .using_password("SuperSecret42")
+            .execute_to_memory()
+            .expect("Failed to unveil padded message");
+        assert_eq!(files[0].1, b"Hello, World!");
+    }
+
     // create some tests for the files methods
     #[test]
     fn test_adding_files() {
@@ -0,0 +1,248 @@
+use std::path::Path;
+
+use regex_lite::Regex;
+
+use crate::{media::Media, CodecOptions, SteganoError};
+
+use super::{unveil::UnveilApi, Password};
+
+pub use crate::message::{FileEntry, FilePreview, SpecialEntryPolicy, TableOfContents};
+
+/// Prepares the inspect API, that tells what is hidden without handing out the file contents
+pub fn prepare() -> InspectApi {
+    InspectApi::default()
+}
+
+#[derive(Default, Debug)]
+pub struct InspectApi {
+    unveil: UnveilApi,
+}
+
+impl InspectApi {
+    /// Use the given codec options
+    pub fn with_options(mut self, options: CodecOptions) -> Self {
+        self.unveil = self.unveil.with_options(options);
+        self
+    }
+
+    /// This is the secret image that contains the data to be inspected
+    pub fn from_secret_file(mut self, secret_image: impl AsRef<Path>) -> Self {
+        self.unveil = self.unveil.from_secret_file(secret_image);
+        self
+    }
+
+    /// This is the secret media (image or audio) to be inspected
+    pub fn from_media(mut self, media: Media) -> Self {
+        self.unveil = self.unveil.from_media(media);
+        self
+    }
+
+    /// Set the password used for decrypting the data
+    /// If `None` is passed, the data is expected to be not encrypted
+    pub fn using_password<P: Into<Password>>(mut self, password: P) -> Self {
+        self.unveil = self.unveil.using_password(password);
+        self
+    }
+
+    /// Set the passwords used for data that was hidden with a threshold of passwords
+    pub fn using_threshold_passwords<P: Into<Password>>(
+        mut self,
+        passwords: impl IntoIterator<Item = P>,
+    ) -> Self {
+        self.unveil = self.unveil.using_threshold_passwords(passwords);
+        self
+    }
+
+    /// Set how symlinks and special files are listed, the same way they would be unveiled
+    pub fn with_special_entry_policy(mut self, policy: SpecialEntryPolicy) -> Self {
+        self.unveil = self.unveil.with_special_entry_policy(policy);
+        self
+    }
+
+    /// Execute the inspection and blocks until it is finished
+    pub fn execute(self) -> Result<TableOfContents, SteganoError> {
+        self.unveil.execute_to_toc()
+    }
+
+    /// Reads only the first `max_bytes_per_file` bytes of each hidden file, e.g. for thumbnails or text snippets,
+    /// the rest of the files is never decompressed
+    pub fn previews(self, max_bytes_per_file: usize) -> Result<Vec<FilePreview>, SteganoError> {
+        self.unveil.execute_to_previews(max_bytes_per_file)
+    }
+
+    /// Finds the names of all hidden files that match the pattern, nothing gets extracted.
+    /// The pattern is a glob like `*.pdf` or `docs/**`, or a regex when wrapped in slashes like `/^report-\d+/`.
+    pub fn find_files(self, pattern: &str) -> Result<Vec<String>, SteganoError> {
+        let pattern = compile_pattern(pattern)?;
+
+        Ok(self
+            .execute()?
+            .files
+            .into_iter()
+            .filter(|file| pattern.is_match(&file.name))
+            .map(|file| file.name)
+            .collect())
+    }
+}
+
+/// turns a glob or a `/regex/` into a regex, a glob has to match the whole name
+/// and its `*` and `?` do not cross directories, only `**` does.
+fn compile_pattern(pattern: &str) -> Result<Regex, SteganoError> {
+    let regex = match pattern.strip_prefix('/').and_then(|p| p.strip_suffix('/')) {
+        Some(regex) if !regex.is_empty() => regex.to_string(),
+        _ => {
+            let mut regex = String::from("^");
+            let mut chars = pattern.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '*' if chars.peek() == Some(&'*') => {
+                        chars.next();
+                        regex.push_str(".*");
+                    }
+                    '*' => regex.push_str("[^/]*"),
+                    '?' => regex.push_str("[^/]"),
+                    c => regex.push_str(&regex_lite::escape(&c.to_string())),
+                }
+            }
+            regex.push('$');
+            regex
+        }
+    };
+
+    Regex::new(&regex).map_err(|e| SteganoError::InvalidPattern(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::compile_pattern;
+    use crate::timestamp::tests::token_for;
+    use crate::{Metadata, TimestampToken};
+
+    #[test]
+    fn should_expose_the_encrypted_metadata() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let secret_image = temp_dir.path().join("image-with-secret.png");
+        let metadata = Metadata {
+            author: Some("Jane Doe".to_string()),
+            created_at: Some(1_700_000_000),
+            app_version: Some("0.1.0".to_string()),
+        };
+
+        crate::api::hide::prepare()
+            .with_message("Hello, World!")
+            .with_image("tests/images/plain/carrier-image.png")
+            .using_password("SuperSecret42")
+            .with_metadata(metadata.clone())
+            .with_output(&secret_image)
+            .execute()
+            .expect("Failed to hide message in image");
+
+        let inspection = crate::api::inspect::prepare()
+            .from_secret_file(&secret_image)
+            .using_password("SuperSecret42")
+            .execute()
+            .expect("Failed to inspect image");
+        assert_eq!(inspection.metadata, Some(metadata));
+        assert_eq!(inspection.files.len(), 1);
+        assert_eq!(inspection.files[0].name, "secret-message.txt");
+        assert_eq!(inspection.files[0].size, 13);
+
+        let files = crate::api::unveil::prepare()
+            .from_secret_file(&secret_image)
+            .using_password("SuperSecret42")
+            .execute_to_memory()
+            .expect("Failed to unveil image");
+        assert_eq!(files.len(), 1, "Metadata should not show up as a file");
+    }
+
+    #[test]
+    fn should_expose_the_timestamp_token() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let secret_image = temp_dir.path().join("image-with-secret.png");
+        let document = std::fs::read("Cargo.toml").unwrap();
+        let token = TimestampToken::from_der(token_for(&document)).unwrap();
+
+        crate::api::hide::prepare()
+            .with_file("Cargo.toml")
+            .with_image("tests/images/plain/carrier-image.png")
+            .with_timestamp_token(token.clone())
+            .with_output(&secret_image)
+            .execute()
+            .expect("Failed to hide files in image");
+
+        let inspection = crate::api::inspect::prepare()
+            .from_secret_file(&secret_image)
+            .execute()
+            .expect("Failed to inspect image");
+        assert_eq!(inspection.timestamp, Some(token));
+        assert_eq!(inspection.files.len(), 1, "the token is not a file");
+        assert!(inspection.timestamp.unwrap().info().covers(&document));
+    }
+
+    #[test]
+    fn should_find_files_without_extracting() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let secret_image = temp_dir.path().join("image-with-secret.png");
+
+        crate::api::hide::prepare()
+            .with_file("Cargo.toml")
+            .with_file("LICENSE")
+            .with_file("README.md")
+            .with_image("tests/images/plain/carrier-image.png")
+            .with_output(&secret_image)
+            .execute()
+            .expect("Failed to hide files in image");
+
+        let found = crate::api::inspect::prepare()
+            .from_secret_file(&secret_image)
+            .find_files("*.md")
+            .expect("Failed to find files");
+        assert_eq!(found, vec!["README.md"]);
+
+        let found = crate::api::inspect::prepare()
+            .from_secret_file(&secret_image)
+            .find_files("/^[A-Z]+$/")
+            .expect("Failed to find files");
+        assert_eq!(found, vec!["LICENSE"]);
+    }
+
+    #[test]
+    fn should_preview_only_the_head_of_each_file() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let secret_image = temp_dir.path().join("image-with-secret.png");
+
+        crate::api::hide::prepare()
+            .with_message("Hello, World!")
+            .with_file("Cargo.toml")
+            .with_image("tests/images/plain/carrier-image.png")
+            .with_output(&secret_image)
+            .execute()
+            .expect("Failed to hide files in image");
+
+        let previews = crate::api::inspect::prepare()
+            .from_secret_file(&secret_image)
+            .previews(5)
+            .expect("Failed to preview files");
+        assert_eq!(previews.len(), 2);
+        assert_eq!(previews[0].entry.name, "Cargo.toml");
+        assert_eq!(previews[0].head, b"[pack");
+        assert!(previews[0].entry.size > 5);
+        assert_eq!(previews[1].entry.name, "secret-message.txt");
+        assert_eq!(previews[1].head, b"Hello");
+    }
+
+    #[test]
+    fn should_match_globs_like_paths() {
+        let glob = compile_pattern("docs/*.pdf").unwrap();
+        assert!(glob.is_match("docs/report.pdf"));
+        assert!(!glob.is_match("docs/2024/report.pdf"));
+        assert!(!glob.is_match("docs/report.pdf.bak"));
+
+        let glob = compile_pattern("docs/**.pdf").unwrap();
+        assert!(glob.is_match("docs/2024/report.pdf"));
+
+        assert!(compile_pattern("/[/").is_err());
+    }
+}
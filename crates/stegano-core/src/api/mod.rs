@@ -1,4 +1,6 @@
 pub mod hide;
+pub mod inspect;
+pub mod rekey;
 pub mod unveil;
 pub mod unveil_raw;
 
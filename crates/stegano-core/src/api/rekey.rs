@@ -0,0 +1,223 @@
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use byteorder::ReadBytesExt;
+
+use crate::{
+    media::{
+        audio, image,
+        payload::{
+            FabA, FabS, PayloadCodecFactory, PayloadCodecFeatures, AES_CRYPTO, CHA_CRYPTO,
+            THRESHOLD_CRYPTO,
+        },
+        Media, Persist,
+    },
+    CodecOptions, SteganoError,
+};
+
+use super::Password;
+
+/// the feature bits that describe the encryption of a payload
+const CRYPTO_FEATURES: u8 = AES_CRYPTO | CHA_CRYPTO | THRESHOLD_CRYPTO;
+
+/// Prepares the rekey API, that swaps the password of already hidden data
+pub fn prepare() -> RekeyApi {
+    RekeyApi::default()
+}
+
+#[derive(Default, Debug)]
+pub struct RekeyApi {
+    secret_media: Option<PathBuf>,
+    secret_buffer: Option<Media>,
+    output: Option<PathBuf>,
+    password: Password,
+    new_password: Password,
+    options: CodecOptions,
+}
+
+impl RekeyApi {
+    /// Use the given codec options
+    pub fn with_options(mut self, options: CodecOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// This is the secret image that contains the data to be rekeyed
+    pub fn from_secret_file(mut self, secret_image: impl AsRef<Path>) -> Self {
+        self.secret_media = Some(secret_image.as_ref().to_path_buf());
+        self
+    }
+
+    /// This is the secret media (image or audio) to be rekeyed
+    pub fn from_media(mut self, media: Media) -> Self {
+        self.secret_buffer = Some(media);
+        self
+    }
+
+    /// This is the output image/audio
+    pub fn with_output<A: AsRef<Path>>(mut self, output: A) -> Self {
+        self.output = Some(output.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the password the data is currently encrypted with
+    /// If `None` is passed, the data is expected to be not encrypted
+    pub fn using_password<P: Into<Password>>(mut self, password: P) -> Self {
+        self.password = password.into();
+        self
+    }
+
+    /// Set the password the data will be encrypted with afterwards
+    /// If `None` is passed, the data will be stored without encryption
+    pub fn using_new_password<P: Into<Password>>(mut self, password: P) -> Self {
+        self.new_password = password.into();
+        self
+    }
+
+    /// Execute the rekey process and blocks until it is finished
+    pub fn execute(mut self) -> Result<(), SteganoError> {
+        let Some(output) = self.output.take() else {
+            return Err(SteganoError::TargetNotSet);
+        };
+
+        self.execute_to_media()?.save_as(&output)
+    }
+
+    /// Execute the rekey process and returns the media with the re-encrypted data.
+    /// Only the region of the media that holds the payload gets rewritten.
+    /// Data encrypted for a threshold of passwords fails with `ThresholdRekeyUnsupported`.
+    pub fn execute_to_media(mut self) -> Result<Media, SteganoError> {
+        let mut media = match self.secret_buffer.take() {
+            Some(media) => media,
+            None => {
+                let Some(secret_media) = self.secret_media.as_ref() else {
+                    return Err(SteganoError::CarrierNotSet);
+                };
+                Media::from_file(secret_media)?
+            }
+        };
+
+        let (version, content) = match &media {
+            Media::Image(image) => {
+                let mut decoder = image::LsbCodec::decoder(image, &self.options);
+                self.decode(&mut decoder)?
+            }
+            Media::Audio(audio) => {
+                let mut decoder = audio::LsbCodec::decoder(&audio.1);
+                self.decode(&mut decoder)?
+            }
+        };
+
+        let features = PayloadCodecFeatures::MixedFeatures(version & !CRYPTO_FEATURES);
+        let data = factory(&self.new_password)
+            .create_codec(features)?
+            .encode(&mut Cursor::new(content))?;
+        media.hide_data(data, &self.options)?;
+
+        Ok(media)
+    }
+
+    fn decode(&self, decoder: &mut dyn std::io::Read) -> Result<(u8, Vec<u8>), SteganoError> {
+        let version = decoder.read_u8()?;
+        if version & THRESHOLD_CRYPTO != 0 {
+            return Err(SteganoError::ThresholdRekeyUnsupported);
+        }
+        let content = factory(&self.password)
+            .create_codec(PayloadCodecFeatures::MixedFeatures(version))?
+            .decode(decoder)?;
+
+        Ok((version, content))
+    }
+}
+
+fn factory(password: &Password) -> Box<dyn PayloadCodecFactory> {
+    if let Some(password) = password.as_ref() {
+        Box::new(FabS::new(password))
+    } else {
+        Box::new(FabA)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    #[test]
+    fn should_swap_the_password() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let rekeyed_image = temp_dir.path().join("rekeyed.png");
+
+        crate::api::rekey::prepare()
+            .from_secret_file("tests/images/encrypted/hello_world.png")
+            .using_password("Secret42")
+            .using_new_password("Secret43")
+            .with_output(&rekeyed_image)
+            .execute()
+            .expect("Failed to rekey image");
+
+        let files = crate::api::unveil::prepare()
+            .from_secret_file(&rekeyed_image)
+            .using_password("Secret43")
+            .execute_to_memory()
+            .expect("Failed to unveil with the new password");
+        assert_eq!(files[0].1, b"Hello World");
+
+        let result = crate::api::unveil::prepare()
+            .from_secret_file(&rekeyed_image)
+            .using_password("Secret42")
+            .execute_to_memory();
+        assert!(result.is_err(), "Old password should not work anymore");
+    }
+
+    #[test]
+    fn should_refuse_threshold_encrypted_data() {
+        let carrier = image::open("tests/images/plain/carrier-image.png")
+            .unwrap()
+            .to_rgba8();
+        let mut encoder = crate::SteganoEncoder::new();
+        encoder
+            .with_threshold_encryption(["Secret42", "Secret43"], 1)
+            .use_media_from_media(crate::media::Media::from_image(carrier));
+        encoder.add_message("Hello World").unwrap();
+        let media = encoder.hide_to_media().unwrap();
+
+        let result = crate::api::rekey::prepare()
+            .from_media(media)
+            .using_password("Secret42")
+            .using_new_password("Secret44")
+            .execute_to_media();
+        assert!(matches!(
+            result,
+            Err(crate::SteganoError::ThresholdRekeyUnsupported)
+        ));
+    }
+
+    #[test]
+    fn should_only_touch_the_payload_region() {
+        let original = image::open("tests/images/with_text/hello_world.png")
+            .unwrap()
+            .to_rgba8();
+
+        let media = crate::api::rekey::prepare()
+            .from_media(crate::media::Media::from_image(original.clone()))
+            .using_new_password("Secret42")
+            .execute_to_media()
+            .expect("Failed to rekey image");
+        let crate::media::Media::Image(rekeyed) = media else {
+            panic!("Rekeyed media should be an image");
+        };
+
+        let changed_pixels = original
+            .pixels()
+            .zip(rekeyed.pixels())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert!(changed_pixels > 0, "Payload was not rewritten");
+        assert!(
+            changed_pixels < (original.width() * original.height()) as usize / 10,
+            "Rekey should leave most of the carrier untouched"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use std::fmt::{self, Debug, Formatter};
 
+use zeroize::Zeroize;
+
 #[derive(Default)]
 pub struct Password(Option<String>);
 
@@ -13,12 +15,24 @@ impl Debug for Password {
     }
 }
 
+impl Drop for Password {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 impl From<Option<String>> for Password {
     fn from(password: Option<String>) -> Self {
         Self(password)
     }
 }
 
+impl From<String> for Password {
+    fn from(password: String) -> Self {
+        Self(Some(password))
+    }
+}
+
 impl From<&str> for Password {
     fn from(password: &str) -> Self {
         Self(Some(password.to_string()))
@@ -1,31 +1,123 @@
 use std::{
+    collections::HashSet,
     fs::File,
-    io::Write,
+    io::{Read, Write},
     path::{Path, PathBuf},
 };
 
+use zeroize::Zeroize;
+
 use crate::{
+    digests::{sha256, to_hex},
     media::{
         audio, image,
-        payload::{FabA, FabS, PayloadCodecFactory},
+        payload::{legacy::FabLegacy, FabA, FabS, FabT, PayloadCodecFactory},
         Media,
     },
-    CodecOptions, Message, SteganoError,
+    message::{display_path, FilePreview, TableOfContents},
+    CodecOptions, ManifestVerification, Message, SteganoError,
 };
 
+pub use crate::media::payload::legacy::LegacyFormats;
+pub use crate::message::SpecialEntryPolicy;
+
+/// The order of the unveiled files
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum FileOrder {
+    /// the order the files were hidden in, the hidden text comes last
+    #[default]
+    Embedded,
+    /// by name, compared byte by byte along the whole path
+    Name,
+    /// by size, the smallest first, files of the same size stay in embed order
+    Size,
+}
+
+/// What to do when a container holds the same file name more than once,
+/// the hidden text counts as `secret-message.txt`
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum CollisionPolicy {
+    /// the later files get a suffix like `report (1).pdf`
+    #[default]
+    Rename,
+    /// only the first file of a name is unveiled
+    KeepFirst,
+    /// unveiling fails with `SteganoError::DuplicateFileName`
+    Error,
+}
+
 use super::Password;
 
 pub fn prepare() -> UnveilApi {
     UnveilApi::default()
 }
 
+/// A file unveiled into memory, together with its attributes
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnveiledFile {
+    /// the position in the order the files were hidden in, the hidden text comes last
+    pub index: usize,
+    /// the path inside of the container, in NFC
+    pub name: String,
+    pub data: Vec<u8>,
+    /// the comment given at hide time, if any
+    pub comment: Option<String>,
+    /// where a symlink pointed to, see `SpecialEntryPolicy::LinkTargetAsMetadata`
+    pub link_target: Option<String>,
+    /// the SHA-256 of `data`, taken while the file was unveiled
+    pub sha256: [u8; 32],
+}
+
+/// All files unveiled into memory, together with what happened to special entries
+#[derive(Debug, Default)]
+pub struct UnveilReport {
+    pub files: Vec<UnveiledFile>,
+    /// one warning per symlink or special file that was not unveiled as it is
+    pub warnings: Vec<String>,
+}
+
+impl UnveiledFile {
+    /// the directories and the file name of the path inside of the container
+    pub fn path_components(&self) -> Vec<&str> {
+        self.name.split('/').collect()
+    }
+
+    /// The path as it gets unveiled on disk, `name` stays the one stored in the container.
+    /// Characters that some platform does not allow become `_`, e.g. `a:b?.txt` becomes `a_b_.txt`
+    pub fn display_name(&self) -> String {
+        display_path(&self.name)
+    }
+
+    /// the last component of the path
+    pub fn file_name(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or_default()
+    }
+
+    /// the SHA-256 as lowercase hex, the way `sha256sum` prints it
+    pub fn sha256_hex(&self) -> String {
+        to_hex(&self.sha256)
+    }
+}
+
+impl Drop for UnveiledFile {
+    fn drop(&mut self) {
+        self.data.zeroize();
+        self.comment.zeroize();
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct UnveilApi {
     secret_media: Option<PathBuf>,
     secret_buffer: Option<Media>,
     output_folder: Option<PathBuf>,
     password: Password,
+    threshold_passwords: Vec<Password>,
     options: CodecOptions,
+    special_entries: SpecialEntryPolicy,
+    legacy_formats: LegacyFormats,
+    collisions: CollisionPolicy,
+    order: FileOrder,
 }
 
 impl UnveilApi {
@@ -66,6 +158,42 @@ impl UnveilApi {
         self
     }
 
+    /// Set the passwords used for unveiling data that was hidden with a threshold of passwords
+    /// Note: this takes precedence over a single password set by `using_password`
+    pub fn using_threshold_passwords<P: Into<Password>>(
+        mut self,
+        passwords: impl IntoIterator<Item = P>,
+    ) -> Self {
+        self.threshold_passwords = passwords.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set what happens to symlinks and other special files found in the hidden container,
+    /// by default they are skipped
+    pub fn with_special_entry_policy(mut self, policy: SpecialEntryPolicy) -> Self {
+        self.special_entries = policy;
+        self
+    }
+
+    /// Set which containers of older stegano-rs versions are read, by default they are detected
+    pub fn with_legacy_formats(mut self, formats: LegacyFormats) -> Self {
+        self.legacy_formats = formats;
+        self
+    }
+
+    /// Set what happens to files with the same name, by default they get renamed
+    pub fn with_collision_policy(mut self, policy: CollisionPolicy) -> Self {
+        self.collisions = policy;
+        self
+    }
+
+    /// Set the order of the unveiled files in `execute_to_report` and `execute_to_files`,
+    /// by default the order they were hidden in
+    pub fn with_file_order(mut self, order: FileOrder) -> Self {
+        self.order = order;
+        self
+    }
+
     /// Execute the unveil process and blocks until it is finished
     pub fn execute(self) -> Result<(), SteganoError> {
         let Some(secret_media) = self.secret_media.as_ref() else {
@@ -82,12 +210,13 @@ impl UnveilApi {
             return Err(SteganoError::NoSecretData);
         }
 
-        for (file_name, buf) in files.iter().map(|(file_name, buf)| {
-            let file = Path::new(file_name).file_name().unwrap().to_str().unwrap();
-
-            (file, buf)
-        }) {
-            let target_file = output_folder.join(file_name);
+        for (file_name, buf, ..) in files.iter() {
+            // the path is relative and free of `..`, so it stays inside of the output folder
+            let target_file = output_folder.join(display_path(file_name));
+            if let Some(parent) = target_file.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|source| SteganoError::WriteError { source })?;
+            }
             let mut target_file =
                 File::create(target_file).map_err(|source| SteganoError::WriteError { source })?;
 
@@ -99,52 +228,451 @@ impl UnveilApi {
         Ok(())
     }
 
-    pub fn execute_to_memory(mut self) -> Result<Vec<(String, Vec<u8>)>, SteganoError> {
+    /// Unveils all files into memory in the order they were hidden in, the hidden text comes last
+    pub fn execute_to_memory(self) -> Result<Vec<(String, Vec<u8>)>, SteganoError> {
+        let collisions = self.collisions;
+        let mut msg = self.execute_to_message()?;
+        let files = take_files(&mut msg, collisions)?;
+        for warning in msg.warnings.iter() {
+            log::warn!("{warning}");
+        }
+
+        Ok(files
+            .into_iter()
+            .map(|(name, data, ..)| (name, data))
+            .collect())
+    }
+
+    /// Unveils all files into memory, including their comments
+    pub fn execute_to_files(self) -> Result<Vec<UnveiledFile>, SteganoError> {
+        let report = self.execute_to_report()?;
+        for warning in report.warnings.iter() {
+            log::warn!("{warning}");
+        }
+
+        Ok(report.files)
+    }
+
+    /// Unveils all files into memory, and reports what happened to special entries.
+    /// The files come in the order set by `with_file_order`
+    pub fn execute_to_report(self) -> Result<UnveilReport, SteganoError> {
+        let (collisions, order) = (self.collisions, self.order);
+        let mut msg = self.execute_to_message()?;
+        let files = take_files(&mut msg, collisions)?;
+        // the names may have been changed for duplicates, the entries tell the files apart
+        let mut comments = std::mem::take(&mut msg.entry_comments);
+        let mut link_targets = std::mem::take(&mut msg.link_targets);
+        let warnings = std::mem::take(&mut msg.warnings);
+
+        let mut files: Vec<_> = files
+            .into_iter()
+            .map(|(name, data, sha256, entry)| UnveiledFile {
+                index: entry,
+                sha256,
+                comment: comments.remove(&entry),
+                link_target: link_targets.remove(&entry),
+                name,
+                data,
+            })
+            .collect();
+        match order {
+            FileOrder::Embedded => {}
+            FileOrder::Name => files.sort_by(|a, b| a.name.cmp(&b.name)),
+            FileOrder::Size => files.sort_by_key(|file| file.data.len()),
+        }
+
+        Ok(UnveilReport { files, warnings })
+    }
+
+    /// Unveils the files and checks them against the signed manifest that was hidden along with them,
+    /// the signature is checked with the given Ed25519 public key. No file data is kept
+    pub fn execute_to_verification(
+        self,
+        public_key: &[u8; 32],
+    ) -> Result<ManifestVerification, SteganoError> {
+        let collisions = self.collisions;
+        let mut msg = self.execute_to_message()?;
+        let manifest = msg.manifest.take().ok_or(SteganoError::NoManifest)?;
+        let mut files = take_files(&mut msg, collisions)?;
+        files.iter_mut().for_each(|(_, data, ..)| data.zeroize());
+
+        let unveiled: Vec<_> = files
+            .iter()
+            .map(|(name, _, digest, _)| (name.as_str(), *digest))
+            .collect();
+        Ok(manifest.verify(public_key, &unveiled))
+    }
+
+    /// Unveils the whole message, including the metadata
+    pub(crate) fn execute_to_message(mut self) -> Result<Message, SteganoError> {
+        let media = self.take_media()?;
+        let policy = self.special_entries;
+        self.decode_with(media, |dec, fab| {
+            Message::from_raw_data_with_policy(dec, fab, policy)
+        })
+    }
+
+    /// Reads only the table of contents, no file gets extracted
+    pub(crate) fn execute_to_toc(mut self) -> Result<TableOfContents, SteganoError> {
+        let media = self.take_media()?;
+        let policy = self.special_entries;
+        self.decode_with(media, |dec, fab| {
+            TableOfContents::from_raw_data_with_policy(dec, fab, policy)
+        })
+    }
+
+    /// Reads the table of contents with the first `max_head` bytes of each file
+    pub(crate) fn execute_to_previews(
+        mut self,
+        max_head: usize,
+    ) -> Result<Vec<FilePreview>, SteganoError> {
+        let media = self.take_media()?;
+        let policy = self.special_entries;
+        self.decode_with(media, |dec, fab| {
+            TableOfContents::previews_from_raw_data(dec, fab, max_head, policy)
+        })
+    }
+
+    fn take_media(&mut self) -> Result<Media, SteganoError> {
         if let Some(media) = self.secret_buffer.take() {
-            return self.unveil_files(media);
+            return Ok(media);
         }
 
         let Some(secret_media) = self.secret_media.as_ref() else {
             return Err(SteganoError::CarrierNotSet);
         };
 
-        let media = Media::from_file(secret_media)?;
-        self.unveil_files(media)
+        Media::from_file(secret_media)
+    }
+
+    fn unveil_files(&self, media: Media) -> Result<Vec<TakenFile>, SteganoError> {
+        let policy = self.special_entries;
+        let mut msg = self.decode_with(media, |dec, fab| {
+            Message::from_raw_data_with_policy(dec, fab, policy)
+        })?;
+        let files = take_files(&mut msg, self.collisions)?;
+        for warning in msg.warnings.iter() {
+            log::warn!("{warning}");
+        }
+
+        Ok(files)
     }
 
-    fn unveil_files(&self, media: Media) -> Result<Vec<(String, Vec<u8>)>, SteganoError> {
-        let fab: Box<dyn PayloadCodecFactory> = if let Some(password) = self.password.as_ref() {
+    fn decode_with<T>(
+        &self,
+        media: Media,
+        decode: impl FnOnce(&mut dyn Read, &dyn PayloadCodecFactory) -> Result<T, SteganoError>,
+    ) -> Result<T, SteganoError> {
+        let fab: Box<dyn PayloadCodecFactory> = if !self.threshold_passwords.is_empty() {
+            let passwords = self
+                .threshold_passwords
+                .iter()
+                .filter_map(|p| p.as_ref().clone());
+            Box::new(FabT::new(passwords, 0))
+        } else if let Some(password) = self.password.as_ref() {
             Box::new(FabS::new(password))
         } else {
             Box::new(FabA)
         };
+        let fab = FabLegacy {
+            formats: self.legacy_formats,
+            inner: &*fab,
+        };
 
-        let msg = match media {
+        match media {
             Media::Image(image) => {
                 let mut decoder = image::LsbCodec::decoder(&image, &self.options);
-                Message::from_raw_data(&mut decoder, &*fab)?
+                decode(&mut decoder, &fab)
             }
             Media::Audio(audio) => {
                 let mut decoder = audio::LsbCodec::decoder(&audio.1);
-                Message::from_raw_data(&mut decoder, &*fab)?
+                decode(&mut decoder, &fab)
             }
-        };
-
-        let mut files = msg.files;
-        if let Some(text) = msg.text {
-            files.push(("secret-message.txt".to_owned(), text.as_bytes().to_vec()));
         }
+    }
+}
 
-        Ok(files)
+/// A file taken out of a message, with its SHA-256 and the index of its entry in the message
+type TakenFile = (String, Vec<u8>, [u8; 32], usize);
+
+/// moves the files out of the message, the text becomes a file as well.
+/// Duplicate names are handled by `collisions`, what happened is added to the warnings.
+fn take_files(
+    msg: &mut Message,
+    collisions: CollisionPolicy,
+) -> Result<Vec<TakenFile>, SteganoError> {
+    let digests = std::mem::take(&mut msg.digests);
+    let mut files: Vec<TakenFile> = std::mem::take(&mut msg.files)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, data))| {
+            // the digests are taken while reading, only the hidden text is hashed here
+            let digest = digests.get(i).copied().unwrap_or_else(|| sha256(&data));
+            (name, data, digest, i)
+        })
+        .collect();
+    if let Some(text) = msg.text.take() {
+        let data = text.into_bytes();
+        let digest = sha256(&data);
+        files.push(("secret-message.txt".to_owned(), data, digest, files.len()));
+    }
+    // names are compared as they get unveiled, `a:b.txt` and `a_b.txt` would end up in the same file
+    let mut names = HashSet::new();
+    let duplicate = files
+        .iter()
+        .find(|(name, ..)| !names.insert(display_path(name)))
+        .map(|(name, ..)| name.clone());
+    let Some(duplicate) = duplicate else {
+        return Ok(files);
+    };
+    if collisions == CollisionPolicy::Error {
+        files.iter_mut().for_each(|(_, data, ..)| data.zeroize());
+        return Err(SteganoError::DuplicateFileName(duplicate));
+    }
+
+    let mut names = HashSet::new();
+    let mut unique = Vec::with_capacity(files.len());
+    for (name, mut data, digest, entry) in files {
+        if names.insert(display_path(&name)) {
+            unique.push((name, data, digest, entry));
+        } else if collisions == CollisionPolicy::KeepFirst {
+            msg.warnings.push(format!(
+                "Skipped the file `{name}`, it is hidden more than once"
+            ));
+            data.zeroize();
+        } else {
+            let renamed = free_name(&name, &names);
+            msg.warnings.push(format!(
+                "Renamed the duplicate file `{name}` to `{renamed}`"
+            ));
+            names.insert(display_path(&renamed));
+            unique.push((renamed, data, digest, entry));
+        }
     }
+
+    Ok(unique)
+}
+
+/// the first name like `report (1).pdf` whose display path is not taken yet
+fn free_name(name: &str, taken: &HashSet<String>) -> String {
+    let file_name_start = name.rfind('/').map_or(0, |i| i + 1);
+    let (stem, extension) = match name[file_name_start..].rfind('.') {
+        Some(dot) if dot > 0 => name.split_at(file_name_start + dot),
+        _ => (name, ""),
+    };
+
+    (1..)
+        .map(|n| format!("{stem} ({n}){extension}"))
+        .find(|candidate| !taken.contains(&display_path(candidate)))
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
     use std::io::read_to_string;
 
+    use super::{free_name, take_files, CollisionPolicy, FileOrder};
+    use crate::media::payload::FabA;
+    use crate::media::Media;
+    use crate::{CodecOptions, Message, SteganoError};
+    use image::RgbaImage;
+
     use tempfile::tempdir;
 
+    #[test]
+    fn should_rename_duplicate_file_names() {
+        let mut taken = HashSet::new();
+        taken.insert("docs/report.pdf".to_string());
+        taken.insert("docs/report (1).pdf".to_string());
+        assert_eq!(free_name("docs/report.pdf", &taken), "docs/report (2).pdf");
+        assert_eq!(free_name("v1.0/README", &taken), "v1.0/README (1)");
+        assert_eq!(free_name(".env", &taken), ".env (1)");
+
+        let mut msg = Message::empty();
+        msg.files = vec![
+            ("a.txt".to_string(), b"first".to_vec()),
+            ("a.txt".to_string(), b"second".to_vec()),
+        ];
+        msg.text = Some("Hello".to_string());
+        let files = take_files(&mut msg, CollisionPolicy::Rename).unwrap();
+        let names: Vec<_> = files.iter().map(|(name, ..)| name.as_str()).collect();
+        assert_eq!(names, ["a.txt", "a (1).txt", "secret-message.txt"]);
+        assert_eq!(files[1].1, b"second");
+        assert_eq!(files[1].2, crate::digests::sha256(b"second"));
+        assert_eq!(msg.warnings.len(), 1);
+    }
+
+    #[test]
+    fn should_keep_the_first_or_fail_on_duplicate_file_names() {
+        let message = || {
+            let mut msg = Message::empty();
+            msg.files = vec![
+                ("secret-message.txt".to_string(), b"a file".to_vec()),
+                ("b.txt".to_string(), b"b".to_vec()),
+            ];
+            msg.text = Some("Hello".to_string());
+            msg
+        };
+
+        let files = take_files(&mut message(), CollisionPolicy::KeepFirst).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].1, b"a file");
+
+        let result = take_files(&mut message(), CollisionPolicy::Error);
+        assert!(matches!(
+            result,
+            Err(SteganoError::DuplicateFileName(name)) if name == "secret-message.txt"
+        ));
+    }
+
+    #[test]
+    fn should_detect_names_that_unveil_to_the_same_path() {
+        let message = || {
+            let mut msg = Message::empty();
+            msg.files = vec![
+                ("a:b.txt".to_string(), b"first".to_vec()),
+                ("a_b.txt".to_string(), b"second".to_vec()),
+            ];
+            msg
+        };
+
+        let files = take_files(&mut message(), CollisionPolicy::Rename).unwrap();
+        let names: Vec<_> = files.iter().map(|(name, ..)| name.as_str()).collect();
+        assert_eq!(names, ["a:b.txt", "a_b (1).txt"]);
+
+        let result = take_files(&mut message(), CollisionPolicy::Error);
+        assert!(matches!(
+            result,
+            Err(SteganoError::DuplicateFileName(name)) if name == "a_b.txt"
+        ));
+    }
+
+    #[test]
+    fn should_keep_the_comment_of_a_renamed_duplicate() {
+        let mut msg = Message::empty();
+        msg.files = vec![
+            ("a:b.txt".to_string(), b"first".to_vec()),
+            ("a_b.txt".to_string(), b"second".to_vec()),
+        ];
+        msg.comments
+            .insert("a_b.txt".to_string(), "the second".to_string());
+        let mut media = Media::from_image(RgbaImage::new(128, 128));
+        media
+            .hide_data(msg.to_raw_data(&FabA).unwrap(), &CodecOptions::default())
+            .unwrap();
+
+        let files = super::prepare()
+            .from_media(media)
+            .with_collision_policy(CollisionPolicy::Rename)
+            .execute_to_files()
+            .unwrap();
+        assert_eq!(files[0].name, "a:b.txt");
+        assert_eq!(files[0].comment, None);
+        assert_eq!(files[1].name, "a_b (1).txt");
+        assert_eq!(files[1].comment.as_deref(), Some("the second"));
+    }
+
+    #[test]
+    fn should_unveil_in_embed_order_or_sorted() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let secret_image = temp_dir.path().join("image-with-secret.png");
+
+        crate::api::hide::prepare()
+            .with_message("Hello")
+            .with_file("README.md")
+            .with_file("Cargo.toml")
+            .with_image("tests/images/plain/carrier-image.png")
+            .with_output(&secret_image)
+            .execute()
+            .expect("Failed to hide files in image");
+        let unveil = |order| {
+            crate::api::unveil::prepare()
+                .from_secret_file(&secret_image)
+                .with_file_order(order)
+                .execute_to_files()
+                .expect("Failed to unveil files")
+                .iter()
+                .map(|file| (file.index, file.name.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let embedded = unveil(FileOrder::Embedded);
+        assert_eq!(
+            embedded,
+            [
+                (0, "README.md".to_string()),
+                (1, "Cargo.toml".to_string()),
+                (2, "secret-message.txt".to_string())
+            ]
+        );
+        let by_name = unveil(FileOrder::Name);
+        assert_eq!(by_name[0], (1, "Cargo.toml".to_string()));
+        assert_eq!(by_name[1], (0, "README.md".to_string()));
+        let by_size = unveil(FileOrder::Size);
+        assert_eq!(by_size[0], (2, "secret-message.txt".to_string()));
+    }
+
+    #[test]
+    fn should_tell_the_sha256_of_each_file() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let secret_image = temp_dir.path().join("image-with-secret.png");
+
+        crate::api::hide::prepare()
+            .with_message("abc")
+            .with_file("Cargo.toml")
+            .with_image("tests/images/plain/carrier-image.png")
+            .with_output(&secret_image)
+            .execute()
+            .expect("Failed to hide files in image");
+
+        let files = crate::api::unveil::prepare()
+            .from_secret_file(&secret_image)
+            .execute_to_files()
+            .expect("Failed to unveil files");
+        let cargo_toml = std::fs::read("Cargo.toml").unwrap();
+        assert_eq!(files[0].sha256, crate::digests::sha256(&cargo_toml));
+        assert_eq!(
+            files[1].sha256_hex(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn should_verify_the_signed_manifest() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let secret_image = temp_dir.path().join("image-with-secret.png");
+        let key = ed25519_dalek::SigningKey::from_bytes(&[42; 32]);
+
+        crate::api::hide::prepare()
+            .with_message("abc")
+            .with_file("Cargo.toml")
+            .with_image("tests/images/plain/carrier-image.png")
+            .with_output(&secret_image)
+            .signed_with(&key.to_bytes())
+            .execute()
+            .expect("Failed to hide files in image");
+
+        let verification = crate::api::unveil::prepare()
+            .from_secret_file(&secret_image)
+            .execute_to_verification(&key.verifying_key().to_bytes())
+            .expect("Failed to verify the manifest");
+        assert!(verification.is_authentic());
+        assert_eq!(verification.files.len(), 2);
+
+        let files = crate::api::unveil::prepare()
+            .from_secret_file(&secret_image)
+            .execute_to_files()
+            .expect("Failed to unveil files");
+        assert_eq!(files.len(), 2, "the manifest is not unveiled as a file");
+
+        let result = crate::api::unveil::prepare()
+            .from_secret_file("tests/images/encrypted/hello_world.png")
+            .using_password("Secret42")
+            .execute_to_verification(&key.verifying_key().to_bytes());
+        assert!(matches!(result, Err(SteganoError::NoManifest)));
+    }
+
     #[test]
     fn illustrate_api_usage() {
         let temp_dir = tempdir().expect("Failed to create temporary directory");
@@ -164,4 +692,58 @@ mod tests {
         .expect("Failed to read file");
         assert_eq!(secret_message, "Hello World");
     }
+
+    #[test]
+    fn should_unveil_with_enough_threshold_passwords() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let secret_image = temp_dir.path().join("image-with-secret.png");
+
+        crate::api::hide::prepare()
+            .with_message("Hello, Custodians!")
+            .with_image("tests/images/plain/carrier-image.png")
+            .using_threshold_passwords(["alice", "bob", "carol"], 2)
+            .with_output(&secret_image)
+            .execute()
+            .expect("Failed to hide message in image");
+
+        let files = crate::api::unveil::prepare()
+            .from_secret_file(&secret_image)
+            .using_threshold_passwords(["carol", "bob"])
+            .execute_to_memory()
+            .expect("Failed to unveil message from image");
+        assert_eq!(files[0].1, b"Hello, Custodians!");
+
+        let result = crate::api::unveil::prepare()
+            .from_secret_file(&secret_image)
+            .using_threshold_passwords(["carol"])
+            .execute_to_memory();
+        assert!(matches!(
+            result.unwrap_err(),
+            crate::SteganoError::DecryptionError(_)
+        ));
+    }
+
+    #[test]
+    fn should_unveil_the_comments_of_files() {
+        let temp_dir = tempdir().expect("Failed to create temporary directory");
+        let secret_image = temp_dir.path().join("image-with-secret.png");
+
+        crate::api::hide::prepare()
+            .with_file("Cargo.toml")
+            .with_file("LICENSE")
+            .with_file_comment("Cargo.toml", "the manifest")
+            .with_image("tests/images/plain/carrier-image.png")
+            .with_output(&secret_image)
+            .execute()
+            .expect("Failed to hide files in image");
+
+        let files = crate::api::unveil::prepare()
+            .from_secret_file(&secret_image)
+            .execute_to_files()
+            .expect("Failed to unveil files from image");
+        assert_eq!(files[0].name, "Cargo.toml");
+        assert_eq!(files[0].comment.as_deref(), Some("the manifest"));
+        assert_eq!(files[1].name, "LICENSE");
+        assert_eq!(files[1].comment, None);
+    }
 }
@@ -0,0 +1,154 @@
+//! # Block checksums
+//! Every hidden file carries a CRC32 per block of its content, so that corruption is found
+//! while the file is read, and reported with the offset of the first corrupt block.
+
+use std::io::Read;
+
+use subtle::ConstantTimeEq;
+
+use crate::result::Result;
+use crate::SteganoError;
+
+/// the id of the zip extra field that holds the block checksums, it reads `sk` for stegano checksums
+pub(crate) const CHECKSUMS_EXTRA_FIELD_ID: u16 = 0x6b73;
+
+/// the smallest block that gets its own checksum
+const MIN_BLOCK_SIZE: usize = 64 * 1024;
+
+/// zip extra fields are limited to 64 KiB in total, this leaves room for a comment
+const MAX_BLOCKS: usize = 8 * 1024;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct BlockChecksums {
+    block_size: usize,
+    checksums: Vec<u32>,
+}
+
+impl BlockChecksums {
+    /// calculates the checksums of all blocks of `data`
+    pub(crate) fn for_data(data: &[u8]) -> Self {
+        let block_size = block_size_for(data.len());
+
+        Self {
+            block_size,
+            checksums: data.chunks(block_size).map(crc32fast::hash).collect(),
+        }
+    }
+
+    /// the length of `to_bytes` for data of `len` bytes, without calculating anything
+    pub(crate) fn bytes_len_for(len: usize) -> usize {
+        4 + len.div_ceil(block_size_for(len)) * 4
+    }
+
+    /// the content of the zip extra field: the block size, followed by one checksum per block
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(4 + self.checksums.len() * 4);
+        bytes.extend_from_slice(&(self.block_size as u32).to_be_bytes());
+        for checksum in self.checksums.iter() {
+            bytes.extend_from_slice(&checksum.to_be_bytes());
+        }
+
+        bytes
+    }
+
+    /// parses the content of the zip extra field, `None` if it is malformed
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (block_size, checksums) = bytes.split_first_chunk::<4>()?;
+        let block_size = u32::from_be_bytes(*block_size) as usize;
+        if block_size == 0 || checksums.len() % 4 != 0 {
+            return None;
+        }
+
+        Some(Self {
+            block_size,
+            checksums: checksums
+                .chunks_exact(4)
+                .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+                .collect(),
+        })
+    }
+
+    /// reads all of `r` into `out`, and stops at the first block that does not match its checksum
+    pub(crate) fn read_verified(
+        &self,
+        r: &mut dyn Read,
+        name: &str,
+        out: &mut Vec<u8>,
+    ) -> Result<()> {
+        let mut block = vec![0; self.block_size];
+        for (i, checksum) in self.checksums.iter().enumerate() {
+            let offset = (i * self.block_size) as u64;
+            let len = read_block(r, &mut block)?;
+            if len == 0 || !bool::from(crc32fast::hash(&block[..len]).ct_eq(checksum)) {
+                return Err(SteganoError::CorruptBlock {
+                    name: name.to_string(),
+                    offset,
+                });
+            }
+            out.extend_from_slice(&block[..len]);
+        }
+
+        if read_block(r, &mut block)? > 0 {
+            return Err(SteganoError::CorruptBlock {
+                name: name.to_string(),
+                offset: out.len() as u64,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// fills `block` as far as `r` has data
+fn read_block(r: &mut dyn Read, block: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    while len < block.len() {
+        match r.read(&mut block[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+
+    Ok(len)
+}
+
+fn block_size_for(len: usize) -> usize {
+    MIN_BLOCK_SIZE.max(len.div_ceil(MAX_BLOCKS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_verify_intact_data() {
+        let data = vec![42u8; MIN_BLOCK_SIZE * 2 + 7];
+        let checksums = BlockChecksums::for_data(&data);
+        let checksums = BlockChecksums::from_bytes(&checksums.to_bytes()).unwrap();
+
+        let mut out = Vec::new();
+        checksums
+            .read_verified(&mut data.as_slice(), "a.txt", &mut out)
+            .unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn should_report_the_offset_of_the_first_corrupt_block() {
+        let mut data = vec![42u8; MIN_BLOCK_SIZE * 3];
+        let checksums = BlockChecksums::for_data(&data);
+        data[MIN_BLOCK_SIZE + 1] = 0;
+
+        let mut out = Vec::new();
+        let r = checksums.read_verified(&mut data.as_slice(), "a.txt", &mut out);
+        assert!(matches!(
+            r,
+            Err(SteganoError::CorruptBlock { offset, .. }) if offset == MIN_BLOCK_SIZE as u64
+        ));
+        assert_eq!(
+            out.len(),
+            MIN_BLOCK_SIZE,
+            "it should stop right at the corrupt block"
+        );
+    }
+}
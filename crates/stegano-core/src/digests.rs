@@ -0,0 +1,64 @@
+//! # File digests
+//! The SHA-256 of every unveiled file is taken while the file is read from the container,
+//! so that recipients can compare it with a hash they got on another way, without hashing again.
+
+use std::io::Read;
+
+use sha2::{Digest, Sha256};
+
+/// hashes everything that is read through it
+pub(crate) struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// the SHA-256 of all the bytes read so far
+    pub(crate) fn finish(self) -> [u8; 32] {
+        self.hasher.finalize().into()
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.hasher.update(&buf[..len]);
+        Ok(len)
+    }
+}
+
+/// the SHA-256 of data that is in memory already
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// the digest as lowercase hex, the way `sha256sum` prints it
+pub fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_hash_while_reading() {
+        let mut reader = HashingReader::new(&b"abc"[..]);
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).unwrap();
+
+        assert_eq!(data, b"abc");
+        assert_eq!(
+            to_hex(&reader.finish()),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(sha256(b"abc"), sha256(&data));
+    }
+}
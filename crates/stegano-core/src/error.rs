@@ -4,6 +4,8 @@ use zip::result::ZipError;
 
 pub use stegano_seasmoke::SeasmokeError;
 
+use crate::media::image::lsb_codec::AlphaPolicy;
+
 #[derive(Error, Debug)]
 pub enum SteganoError {
     /// Represents an unsupported carrier media. For example, a Movie file is not supported
@@ -26,6 +28,22 @@ pub enum SteganoError {
     #[error("Invalid text data found inside a message")]
     InvalidTextData(#[from] FromUtf8Error),
 
+    /// Represents a padded message whose padding header does not match its content
+    #[error("Invalid padding found inside a message")]
+    InvalidPadding,
+
+    /// Represents a hidden file whose content does not match its block checksums
+    #[error("The hidden file `{name}` is corrupt from byte {offset} on")]
+    CorruptBlock { name: String, offset: u64 },
+
+    /// Represents a length inside of a message that does not fit into memory on this platform
+    #[error("Payload of {0} bytes is too large for this platform")]
+    PayloadTooLarge(u64),
+
+    /// Represents a payload that ends before its length header says, e.g. in a truncated carrier
+    #[error("Payload of {expected} bytes ends after {found} bytes")]
+    TruncatedPayload { expected: u64, found: u64 },
+
     /// Represents an unveil of no secret data. For example when a media did not contain any secrets
     #[error("No secret data found")]
     NoSecretData,
@@ -70,6 +88,67 @@ pub enum SteganoError {
     #[error("Decryption error")]
     DecryptionError(SeasmokeError),
 
+    /// Represents an error when generating random noise, for example for sanitizing a carrier
+    #[error("Noise generation error")]
+    NoiseError(SeasmokeError),
+
+    /// Represents an invalid glob or regex pattern for searching files
+    #[error("Invalid file pattern: {0}")]
+    InvalidPattern(String),
+
+    /// Represents a progress token that could not be parsed
+    #[error("Invalid progress token")]
+    InvalidProgressToken,
+
+    /// Represents a progress token that was created for a different payload
+    #[error("The progress token belongs to a different payload")]
+    ProgressMismatch,
+
+    /// Represents a timestamp token that does not have the structure of RFC 3161
+    #[error("Invalid timestamp token: {0}")]
+    InvalidTimestampToken(&'static str),
+
+    /// Represents a container without a signed manifest that was asked to be verified
+    #[error("No signed manifest was hidden")]
+    NoManifest,
+
+    /// Represents a container with the same file name more than once, see `CollisionPolicy::Error`.
+    /// Names that only differ in characters that are illegal on some platform count as the same
+    #[error("The file name {0} is hidden more than once")]
+    DuplicateFileName(String),
+
+    /// Represents a hidden file whose name is not valid UTF-8, the name is shown with the invalid bytes replaced
+    #[error("The file name {0} is not valid UTF-8")]
+    NonUtf8FileName(String),
+
+    /// Represents an operation that cannot follow the given alpha policy
+    #[error("The alpha policy {0:?} is not supported here")]
+    UnsupportedAlphaPolicy(AlphaPolicy),
+
+    /// Represents an operation that cannot follow the given stealth level
+    #[error("The stealth level {0} is not supported here")]
+    UnsupportedStealthLevel(u8),
+
+    /// Represents an operation that cannot reduce the channels in smooth areas
+    #[error("Banding aware embedding is not supported here")]
+    UnsupportedBandingAware,
+
+    /// Represents an operation that cannot hide the payload more than once
+    #[error("A redundancy of {0} is not supported here")]
+    UnsupportedRedundancy(u8),
+
+    /// Represents a file comment that does not fit into the zip extra fields next to the block checksums,
+    /// all extra fields of a file share 64 KiB
+    #[error("The comment of {name} is too long, at most {max} bytes fit")]
+    CommentTooLong { name: String, max: usize },
+
+    /// Represents a rekey of data that is encrypted for a threshold of passwords,
+    /// its shares cannot be swapped one by one
+    #[error(
+        "Data encrypted for a threshold of passwords cannot be rekeyed, unveil and hide it again"
+    )]
+    ThresholdRekeyUnsupported,
+
     #[error("No carrier media set")]
     CarrierNotSet,
 
@@ -70,10 +70,15 @@
 #[cfg(feature = "benchmarks")]
 extern crate test;
 
+mod checksums;
+mod digests;
 mod error;
+mod manifest;
 mod message;
+mod metadata;
 mod raw_message;
 mod result;
+mod timestamp;
 mod universal_decoder;
 mod universal_encoder;
 
@@ -82,17 +87,21 @@ pub mod media;
 pub mod api;
 
 pub use crate::error::SteganoError;
-pub use crate::media::image::CodecOptions;
+pub use crate::manifest::{FileVerification, Manifest, ManifestVerification};
+pub use crate::media::image::{AlphaPolicy, CodecOptions};
+pub use crate::metadata::Metadata;
 pub use crate::result::Result;
+pub use crate::timestamp::{TimestampInfo, TimestampToken};
 
 use std::default::Default;
 use std::fs::File;
 use std::path::{Path, PathBuf};
 
-use crate::media::payload::{FabA, FabS, PayloadCodecFactory};
+use crate::media::payload::{FabA, FabS, FabT, PaddingBuckets, PayloadCodecFactory};
 use crate::media::{Media, Persist};
 use crate::message::Message;
 use crate::raw_message::RawMessage;
+use ed25519_dalek::SigningKey;
 use image::ImageFormat;
 
 pub struct SteganoEncoder {
@@ -157,6 +166,40 @@ impl SteganoEncoder {
         self
     }
 
+    /// Encrypts the data so that any `threshold` of the given passwords can unveil it
+    pub fn with_threshold_encryption<S: Into<String>>(
+        &mut self,
+        passwords: impl IntoIterator<Item = S>,
+        threshold: u8,
+    ) -> &mut Self {
+        self.codec_factory = Box::new(FabT::new(passwords, threshold));
+        self
+    }
+
+    /// Pads the hidden data up to the given size buckets, this hides the exact size of the data
+    pub fn with_padding(&mut self, buckets: PaddingBuckets) -> &mut Self {
+        self.message.padding = Some(buckets);
+        self
+    }
+
+    /// Hides the given metadata record along with the files
+    pub fn with_metadata(&mut self, metadata: Metadata) -> &mut Self {
+        self.message.metadata = Some(metadata);
+        self
+    }
+
+    /// Hides the given RFC 3161 timestamp token along with the files
+    pub fn with_timestamp_token(&mut self, token: TimestampToken) -> &mut Self {
+        self.message.timestamp = Some(token);
+        self
+    }
+
+    /// Hides a manifest of all files with their SHA-256, signed with the given Ed25519 secret key
+    pub fn with_signing_key(&mut self, secret_key: &[u8; 32]) -> &mut Self {
+        self.message.signing_key = Some(SigningKey::from_bytes(secret_key));
+        self
+    }
+
     pub fn add_message(&mut self, msg: &str) -> Result<&mut Self> {
         self.message
             .add_file_data("secret-message.txt", msg.as_bytes().to_vec())?;
@@ -183,10 +226,31 @@ impl SteganoEncoder {
     }
 
     pub fn add_file_from_memory(&mut self, name: &str, data: &[u8]) -> Result<&mut Self> {
-        self.message.add_file_data(name, data.to_vec())?;
+        self.message.add_file_data_at(name, data.to_vec())?;
         Ok(self)
     }
 
+    /// Attaches a comment to the hidden file with the given name, e.g. to label what it is
+    pub fn with_file_comment<S: Into<String>>(&mut self, name: &str, comment: S) -> &mut Self {
+        self.message
+            .comments
+            .insert(name.to_string(), comment.into());
+        self
+    }
+
+    /// Encodes the message into the payload, as it gets hidden in the carrier.
+    /// Keep it together with the `HideProgress` to resume an interrupted `Media::hide_step`.
+    pub fn encode_payload(&self) -> Result<Vec<u8>> {
+        self.message.to_raw_data(&*self.codec_factory)
+    }
+
+    /// An upper bound of the length of `encode_payload`, once each file grows by the bytes of `growths`, in the order
+    /// the files were added, that cannot be compressed. Unlike `encode_payload` nothing gets encrypted,
+    /// so that it takes no time to derive a key, see `Message::raw_len_with`
+    pub fn estimate_payload_len(&self, growths: &[usize]) -> Result<usize> {
+        self.message.raw_len_with(growths, &*self.codec_factory)
+    }
+
     pub fn hide_and_save(&mut self) -> Result<&mut Self> {
         {
             // TODO this hack needs to be implemented as well :(
@@ -217,6 +281,15 @@ impl SteganoEncoder {
         Ok(self)
     }
 
+    /// Hides the message and hands out the carrier, e.g. to encode it in a format the `image` crate lacks
+    pub fn hide_to_media(&mut self) -> Result<Media> {
+        let mut media = self.carrier.take().ok_or(SteganoError::CarrierNotSet)?;
+        let data = self.message.to_raw_data(&*self.codec_factory)?;
+        media.hide_data(data, &self.options)?;
+
+        Ok(media)
+    }
+
     pub fn hide_to_vec(&mut self) -> Result<Vec<u8>> {
         if self.carrier.is_none() {
             return Err(SteganoError::CarrierNotSet);
@@ -360,6 +433,76 @@ mod e2e_tests {
         Ok(())
     }
 
+    #[test]
+    fn should_hide_and_unveil_with_a_sanitized_lsb_plane() -> Result<()> {
+        let out_dir = TempDir::new()?;
+        let image_with_secret_path = out_dir.path().join("secret.png");
+
+        SteganoEncoder::with_options(CodecOptions {
+            sanitize_lsb_plane: true,
+            ..CodecOptions::default()
+        })
+        .add_file("Cargo.toml")?
+        .use_media("tests/images/with_text/hello_world.png")?
+        .save_as(&image_with_secret_path)
+        .hide_and_save()?;
+
+        let original = image::open("tests/images/with_text/hello_world.png")
+            .unwrap()
+            .to_rgba8();
+        let sanitized = image::open(&image_with_secret_path).unwrap().to_rgba8();
+        let (w, h) = original.dimensions();
+        // the last row and column is never part of the payload, so only the noise changes it
+        let changed = original
+            .enumerate_pixels()
+            .filter(|(x, y, _)| *x == w - 1 || *y == h - 1)
+            .filter(|(x, y, p)| sanitized.get_pixel(*x, *y) != *p)
+            .count();
+        assert!(changed > 0, "Unused LSBs were not sanitized");
+        assert!(
+            original
+                .pixels()
+                .zip(sanitized.pixels())
+                .all(|(a, b)| a[3] == b[3]),
+            "Alpha channel should be untouched"
+        );
+
+        unveil::prepare()
+            .from_secret_file(&image_with_secret_path)
+            .into_output_folder(&out_dir)
+            .execute()?;
+
+        assert_eq_file_content(
+            &out_dir.path().join("Cargo.toml"),
+            "Cargo.toml".as_ref(),
+            "Unveiled data did not match expected",
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_unveil_nested_paths_into_folders() -> Result<()> {
+        let out_dir = TempDir::new()?;
+        let image_with_secret_path = out_dir.path().join("secret.png");
+
+        SteganoEncoder::new()
+            .add_file_from_memory("docs/2024/report.txt", b"quarterly numbers")?
+            .use_media("tests/images/with_text/hello_world.png")?
+            .save_as(&image_with_secret_path)
+            .hide_and_save()?;
+
+        unveil::prepare()
+            .from_secret_file(&image_with_secret_path)
+            .into_output_folder(&out_dir)
+            .execute()?;
+
+        let unveiled = fs::read(out_dir.path().join("docs").join("2024").join("report.txt"))?;
+        assert_eq!(unveiled, b"quarterly numbers");
+
+        Ok(())
+    }
+
     #[test]
     fn should_raw_unveil_a_message() -> Result<()> {
         let out_dir = TempDir::new()?;
@@ -517,6 +660,36 @@ mod e2e_tests {
         Ok(())
     }
 
+    #[test]
+    fn should_estimate_an_upper_bound_of_the_payload() -> Result<()> {
+        let mut secret = vec![0; 100_000];
+        stegano_seasmoke::fill_noise(&mut secret).map_err(SteganoError::NoiseError)?;
+
+        let encoder = |data: &[u8]| -> Result<SteganoEncoder> {
+            let mut encoder = SteganoEncoder::new();
+            encoder
+                .with_encryption("Secret42")
+                .with_padding(PaddingBuckets::MultipleOf(4096))
+                .add_file_from_memory("secret.bin", data)?;
+            Ok(encoder)
+        };
+        let estimate = encoder(&[])?.estimate_payload_len(&[secret.len()])?;
+        let payload = encoder(&secret)?.encode_payload()?;
+
+        assert!(
+            payload.len() <= estimate,
+            "{} > {}",
+            payload.len(),
+            estimate
+        );
+        assert!(
+            estimate - payload.len() <= 4096,
+            "at most a padding bucket too much"
+        );
+
+        Ok(())
+    }
+
     // TODO test for hide_message
 
     fn assert_eq_file_content(file1: &Path, file2: &Path, msg: &str) {
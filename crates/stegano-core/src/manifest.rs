@@ -0,0 +1,210 @@
+//! # Signed manifest
+//! An optional list of the hidden files with their SHA-256, signed with the Ed25519 key of the sender.
+//! It is hidden along with the files, so that a recipient who knows the public key
+//! can authenticate the archive as a whole, including that no file was added or left out.
+
+use std::io::{BufRead, Write};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use subtle::ConstantTimeEq;
+
+use crate::digests::{sha256, to_hex};
+use crate::metadata::{escape, unescape};
+use crate::result::Result;
+
+/// the name of the zip entry that holds the manifest
+pub(crate) const MANIFEST_ENTRY: &str = ".stegano/manifest";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// the name and the SHA-256 of each hidden file, in the order they were hidden in
+    pub files: Vec<(String, [u8; 32])>,
+    /// the Ed25519 signature over all lines of `files`
+    pub signature: [u8; 64],
+}
+
+/// The outcome of checking one file against the manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileVerification {
+    /// listed with the same SHA-256
+    Verified,
+    /// listed, but with a different SHA-256
+    Modified,
+    /// listed, but it was not unveiled
+    Missing,
+    /// unveiled, but not listed
+    Unlisted,
+}
+
+/// The outcome of checking the unveiled files against the manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestVerification {
+    /// if the manifest was signed by the given public key, without it no status can be trusted
+    pub signature_valid: bool,
+    /// the status of each file, the listed ones first
+    pub files: Vec<(String, FileVerification)>,
+}
+
+impl ManifestVerification {
+    /// the signature is valid and every file is verified
+    pub fn is_authentic(&self) -> bool {
+        self.signature_valid
+            && self
+                .files
+                .iter()
+                .all(|(_, status)| *status == FileVerification::Verified)
+    }
+}
+
+impl Manifest {
+    /// lists the files and signs the list
+    pub(crate) fn sign<'f>(
+        files: impl IntoIterator<Item = (&'f str, &'f [u8])>,
+        key: &SigningKey,
+    ) -> Self {
+        let files: Vec<_> = files
+            .into_iter()
+            .map(|(name, data)| (name.to_string(), sha256(data)))
+            .collect();
+        let signature = key.sign(&signed_bytes(&files)).to_bytes();
+
+        Self { files, signature }
+    }
+
+    /// Checks the signature with `public_key` and the unveiled files against the list,
+    /// the files are given by name and SHA-256
+    pub fn verify(
+        &self,
+        public_key: &[u8; 32],
+        unveiled: &[(&str, [u8; 32])],
+    ) -> ManifestVerification {
+        let signature_valid = VerifyingKey::from_bytes(public_key).is_ok_and(|key| {
+            key.verify(
+                &signed_bytes(&self.files),
+                &Signature::from_bytes(&self.signature),
+            )
+            .is_ok()
+        });
+
+        let mut files: Vec<_> = self
+            .files
+            .iter()
+            .map(|(name, digest)| {
+                let status = match unveiled.iter().find(|(n, _)| n == name) {
+                    Some((_, d)) if bool::from(d[..].ct_eq(&digest[..])) => {
+                        FileVerification::Verified
+                    }
+                    Some(_) => FileVerification::Modified,
+                    None => FileVerification::Missing,
+                };
+                (name.clone(), status)
+            })
+            .collect();
+        files.extend(
+            unveiled
+                .iter()
+                .filter(|(name, _)| !self.files.iter().any(|(n, _)| n == name))
+                .map(|(name, _)| (name.to_string(), FileVerification::Unlisted)),
+        );
+
+        ManifestVerification {
+            signature_valid,
+            files,
+        }
+    }
+
+    /// serializes the manifest as `file=<sha256> <name>` lines, followed by the signature
+    pub(crate) fn write_to(&self, w: &mut dyn Write) -> Result<()> {
+        w.write_all(&signed_bytes(&self.files))?;
+        writeln!(w, "signature={}", to_hex(&self.signature))?;
+
+        Ok(())
+    }
+
+    /// parses a manifest written by `write_to`, `None` if it is malformed
+    pub(crate) fn read_from(r: &mut dyn BufRead) -> Result<Option<Self>> {
+        let mut files = Vec::new();
+        let mut signature = None;
+        for line in r.lines() {
+            let line = line?;
+            if let Some(file) = line.strip_prefix("file=") {
+                let Some((digest, name)) = file.split_once(' ') else {
+                    return Ok(None);
+                };
+                let Some(digest) = from_hex(digest) else {
+                    return Ok(None);
+                };
+                files.push((unescape(name), digest));
+            } else if let Some(hex) = line.strip_prefix("signature=") {
+                signature = from_hex(hex);
+            }
+        }
+
+        Ok(signature.map(|signature| Self { files, signature }))
+    }
+}
+
+/// the lines the signature is made over
+fn signed_bytes(files: &[(String, [u8; 32])]) -> Vec<u8> {
+    files
+        .iter()
+        .map(|(name, digest)| format!("file={} {}\n", to_hex(digest), escape(name)))
+        .collect::<String>()
+        .into_bytes()
+}
+
+fn from_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_verify_what_was_signed() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = key.verifying_key().to_bytes();
+        let manifest = Manifest::sign([("a.txt", &b"a"[..]), ("b.txt", &b"b"[..])], &key);
+
+        let mut buf = Vec::new();
+        manifest.write_to(&mut buf).unwrap();
+        let manifest = Manifest::read_from(&mut buf.as_slice()).unwrap().unwrap();
+
+        let verification = manifest.verify(
+            &public_key,
+            &[("a.txt", sha256(b"a")), ("b.txt", sha256(b"b"))],
+        );
+        assert!(verification.is_authentic());
+
+        let verification = manifest.verify(
+            &public_key,
+            &[("a.txt", sha256(b"changed")), ("c.txt", sha256(b"c"))],
+        );
+        assert!(verification.signature_valid);
+        assert_eq!(
+            verification.files,
+            [
+                ("a.txt".to_string(), FileVerification::Modified),
+                ("b.txt".to_string(), FileVerification::Missing),
+                ("c.txt".to_string(), FileVerification::Unlisted),
+            ]
+        );
+
+        let other_key = SigningKey::from_bytes(&[8; 32]).verifying_key().to_bytes();
+        let verification = manifest.verify(
+            &other_key,
+            &[("a.txt", sha256(b"a")), ("b.txt", sha256(b"b"))],
+        );
+        assert!(!verification.signature_valid);
+        assert!(!verification.is_authentic());
+    }
+}
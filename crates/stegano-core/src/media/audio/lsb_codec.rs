@@ -22,8 +22,13 @@ impl LsbCodec {
 
     /// builds a LSB Audio Encoder that implements Write
     pub fn encoder<'i>(input: &'i mut [i16]) -> Box<dyn Write + 'i> {
+        Self::encoder_at(input, 0)
+    }
+
+    /// builds a LSB Audio Encoder that starts writing at the byte `offset` of the payload
+    pub fn encoder_at<'i>(input: &'i mut [i16], offset: usize) -> Box<dyn Write + 'i> {
         Box::new(UniversalEncoder::new(
-            AudioWavIterMut::new(input.iter_mut()),
+            AudioWavIterMut::new(input.iter_mut()).skip(offset << 3),
             OneBitHide,
         ))
     }
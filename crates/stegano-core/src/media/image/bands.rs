@@ -0,0 +1,175 @@
+use super::lsb_codec::{AlphaPolicy, CodecOptions, Concealer};
+use crate::error::SteganoError;
+use crate::media::types::noise_bits;
+use crate::media::MediaPrimitiveMut;
+use crate::result::Result;
+use crate::universal_encoder::{
+    HideAlgorithm, HideAlgorithms, OneBitHide, OneBitInLowFrequencyHide,
+};
+
+/// Hides a payload in an RGBA image that is processed in bands of rows, top to bottom,
+/// so that the whole image never needs to be decoded at once.
+///
+/// The result is the same as `LsbCodec::encoder` on the full image would produce.
+/// Neither alpha policies other than `AlphaPolicy::AllPixels` nor stealth levels are supported,
+/// with them the pixels in use depend on the whole image. Redundancy is not supported either.
+pub struct BandEncoder<'p> {
+    payload: &'p [u8],
+    width: u32,
+    used_width: u32,
+    used_height: u32,
+    channels: usize,
+    steps: usize,
+    sanitize_lsb_plane: bool,
+    algorithm: HideAlgorithms,
+}
+
+impl<'p> BandEncoder<'p> {
+    /// Fails with `ImageCapacityError` if an image of `width`x`height` cannot hold the payload
+    pub fn new(payload: &'p [u8], width: u32, height: u32, opts: &CodecOptions) -> Result<Self> {
+        if opts.alpha_policy != AlphaPolicy::AllPixels {
+            return Err(SteganoError::UnsupportedAlphaPolicy(opts.alpha_policy));
+        }
+        if opts.stealth_level != 0 {
+            return Err(SteganoError::UnsupportedStealthLevel(opts.stealth_level));
+        }
+        if opts.banding_aware {
+            return Err(SteganoError::UnsupportedBandingAware);
+        }
+        if opts.copies() > 1 {
+            return Err(SteganoError::UnsupportedRedundancy(opts.redundancy));
+        }
+        let (used_width, used_height) = if opts.skip_last_row_and_column {
+            (width.saturating_sub(1), height.saturating_sub(1))
+        } else {
+            (width, height)
+        };
+        let channels = if opts.skip_alpha_channel { 3 } else { 4 };
+        let steps = opts.color_channel_step_increment.max(1);
+
+        let positions = used_width as usize * used_height as usize * channels;
+        let needed = payload.len() * 8;
+        if positions.div_ceil(steps) < needed {
+            let scale = (needed * steps) as f64 / positions.max(1) as f64;
+            return Err(SteganoError::ImageCapacityError(
+                width as _,
+                height as _,
+                (scale * width as f64) as _,
+                (scale * height as f64) as _,
+            ));
+        }
+
+        Ok(Self {
+            payload,
+            width,
+            used_width,
+            used_height,
+            channels,
+            steps,
+            sanitize_lsb_plane: opts.sanitize_lsb_plane,
+            algorithm: match opts.concealer {
+                Concealer::LeastSignificantBit => OneBitHide.into(),
+                Concealer::LowFrequencies => OneBitInLowFrequencyHide.into(),
+            },
+        })
+    }
+
+    /// Hides the part of the payload that falls into `band`, the RGBA rows that start at row `first_row`
+    pub fn hide_in_band(&self, first_row: u32, band: &mut [u8]) -> Result<()> {
+        if self.sanitize_lsb_plane {
+            let skip_alpha = self.channels == 3;
+            let len = if skip_alpha {
+                band.len() / 4 * 3
+            } else {
+                band.len()
+            };
+            let channels = band
+                .iter_mut()
+                .enumerate()
+                .filter(|(idx, _)| !skip_alpha || idx % 4 != 3)
+                .map(|(_, c)| c);
+            for (channel, bit) in channels.zip(noise_bits(len)?) {
+                *channel = (*channel & !1) | bit;
+            }
+        }
+
+        let row_len = self.width as usize * 4;
+        let bits = self.payload.len() * 8;
+        for (row_idx, row) in band.chunks_exact_mut(row_len).enumerate() {
+            let y = first_row as usize + row_idx;
+            if y >= self.used_height as usize {
+                break;
+            }
+            for x in 0..self.used_width as usize {
+                // the colors are iterated column by column, see `TransposeMut`
+                let first_position = (x * self.used_height as usize + y) * self.channels;
+                if first_position >= bits * self.steps {
+                    break;
+                }
+                for c in 0..self.channels {
+                    let position = first_position + c;
+                    if !position.is_multiple_of(self.steps) {
+                        continue;
+                    }
+                    let bit = position / self.steps;
+                    if bit >= bits {
+                        break;
+                    }
+                    let information = Ok((self.payload[bit / 8] >> (bit % 8)) & 1 == 1);
+                    self.algorithm.encode(
+                        MediaPrimitiveMut::ImageColorChannel(&mut row[x * 4 + c]),
+                        &information,
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::Media;
+
+    use image::RgbaImage;
+
+    #[test]
+    fn should_hide_band_by_band_like_at_once() {
+        let payload: Vec<u8> = (0..128).collect();
+        let carrier = RgbaImage::from_fn(40, 37, |x, y| image::Rgba([x as u8, y as u8, 77, 255]));
+
+        for opts in [
+            CodecOptions::default(),
+            CodecOptions {
+                color_channel_step_increment: 3,
+                skip_alpha_channel: false,
+                ..CodecOptions::default()
+            },
+        ] {
+            let mut at_once = Media::from_image(carrier.clone());
+            at_once.hide_data(payload.clone(), &opts).unwrap();
+
+            let mut in_bands = carrier.clone().into_raw();
+            let encoder = BandEncoder::new(&payload, 40, 37, &opts).unwrap();
+            for (i, band) in in_bands.chunks_mut(40 * 4 * 8).enumerate() {
+                encoder.hide_in_band(i as u32 * 8, band).unwrap();
+            }
+
+            match at_once {
+                Media::Image(i) => assert_eq!(i.into_raw(), in_bands),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn should_refuse_a_too_small_image() {
+        let r = BandEncoder::new(&[0; 100], 10, 10, &CodecOptions::default());
+        assert!(matches!(
+            r,
+            Err(SteganoError::ImageCapacityError(10, 10, _, _))
+        ));
+    }
+}
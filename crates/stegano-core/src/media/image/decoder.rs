@@ -1,14 +1,18 @@
-use super::iterators::{ColorIter, Transpose};
+use super::iterators::{ColorIter, Masked, MinAlpha, Transpose};
 use super::lsb_codec::CodecOptions;
+use super::perceptual::pixel_plan;
 use crate::media::MediaPrimitive;
 
 use image::{Rgba, RgbaImage};
 
+/// the color channels of the pixels that carry data, column by column
+type Colors<'i> = ColorIter<'i, Rgba<u8>, MinAlpha<Masked<Transpose<'i, Rgba<u8>>>>>;
+
 /// stegano source for image files, based on `RgbaImage` by `image` crate
 pub struct ImageRgbaColor<'i> {
     i: usize,
     steps: usize,
-    pixel: ColorIter<'i, Rgba<u8>>,
+    pixel: Colors<'i>,
 }
 
 impl<'i> ImageRgbaColor<'i> {
@@ -24,8 +28,14 @@ impl<'i> ImageRgbaColor<'i> {
             i: 0,
             steps: options.get_color_channel_step_increment(),
             pixel: ColorIter::from_transpose(
-                Transpose::from_rows(input.rows(), w, true),
-                options.skip_alpha_channel,
+                MinAlpha::new(
+                    Masked::new(
+                        Transpose::from_rows(input.rows(), w, true),
+                        pixel_plan(input, options, true),
+                    ),
+                    options.alpha_policy.min_alpha(),
+                ),
+                options.alpha_channel_skipped(),
             ),
         }
     }
@@ -1,14 +1,18 @@
 use image::{Rgba, RgbaImage};
 
-use super::iterators::{ColorIterMut, TransposeMut};
+use super::iterators::{ColorIterMut, Masked, MinAlpha, TransposeMut};
 use super::lsb_codec::CodecOptions;
+use super::perceptual::pixel_plan;
 use crate::media::MediaPrimitiveMut;
 
+/// the color channels of the pixels that carry data, column by column
+type ColorsMut<'a> = ColorIterMut<'a, Rgba<u8>, MinAlpha<Masked<TransposeMut<'a, Rgba<u8>>>>>;
+
 /// stegano source for image files, based on `RgbaImage` by `image` crate
 pub struct ImageRgbaColorMut<'a> {
     i: usize,
     steps: usize,
-    pixel: ColorIterMut<'a, Rgba<u8>>,
+    pixel: ColorsMut<'a>,
 }
 
 impl<'a> ImageRgbaColorMut<'a> {
@@ -19,16 +23,43 @@ impl<'a> ImageRgbaColorMut<'a> {
     }
 
     pub fn new_with_options(input: &'a mut RgbaImage, options: &CodecOptions) -> Self {
+        Self::new_at(input, options, 0)
+    }
+
+    /// continues right where the iterator with the given `cursor` stopped, see `cursor`
+    pub fn new_at(input: &'a mut RgbaImage, options: &CodecOptions, cursor: usize) -> Self {
         let w = input.width();
+        let plan = pixel_plan(input, options, options.skip_last_row_and_column);
+        let (start, first_channel) = (cursor / 4, cursor % 4);
         Self {
             i: 0,
             steps: options.color_channel_step_increment,
-            pixel: ColorIterMut::from_transpose(
-                TransposeMut::from_rows_mut(input.rows_mut(), w, options.skip_last_row_and_column),
-                options.skip_alpha_channel,
+            pixel: ColorIterMut::from_transpose_at(
+                MinAlpha::new(
+                    Masked::new_at(
+                        TransposeMut::from_pixels_mut_at(
+                            input,
+                            w,
+                            options.skip_last_row_and_column,
+                            start,
+                        ),
+                        plan,
+                        start,
+                    ),
+                    options.alpha_policy.min_alpha(),
+                ),
+                options.alpha_channel_skipped(),
+                start,
+                first_channel,
             ),
         }
     }
+
+    /// the position of the next color channel, an iterator from `new_at` with it continues from there
+    /// without going over the channels before
+    pub fn cursor(&self) -> usize {
+        self.pixel.cursor()
+    }
 }
 
 impl<'i> Iterator for ImageRgbaColorMut<'i> {
@@ -48,6 +79,7 @@ impl<'i> Iterator for ImageRgbaColorMut<'i> {
 #[cfg(test)]
 mod decoder_tests {
     use super::*;
+    use crate::media::image::AlphaPolicy;
     use crate::test_utils::{
         prepare_4x6_linear_growing_colors_except_last_row_column_skipped_alpha, prepare_5x5_image,
         HELLO_WORLD_PNG,
@@ -190,4 +222,33 @@ mod decoder_tests {
             "Second Color (Green-Channel) should be equal."
         );
     }
+
+    #[test]
+    fn it_should_continue_at_the_cursor() {
+        let options = CodecOptions {
+            color_channel_step_increment: 2,
+            alpha_policy: AlphaPolicy::OpaqueOnly,
+            stealth_level: 1,
+            ..CodecOptions::default()
+        };
+        let mut img = image::open(HELLO_WORLD_PNG)
+            .expect("Input image is not readable.")
+            .to_rgba8();
+        img.put_pixel(0, 1, Rgba([1, 2, 3, 0]));
+        let address = |p: MediaPrimitiveMut| match p {
+            MediaPrimitiveMut::ImageColorChannel(c) => c as *const u8 as usize,
+            _ => unreachable!(),
+        };
+        let all: Vec<usize> = ImageRgbaColorMut::new_with_options(&mut img, &options)
+            .map(address)
+            .collect();
+
+        for split in [0, 1, 7, all.len() / 2, all.len()] {
+            let mut first = ImageRgbaColorMut::new_with_options(&mut img, &options);
+            let mut addresses: Vec<usize> = first.by_ref().take(split).map(address).collect();
+            let cursor = first.cursor();
+            addresses.extend(ImageRgbaColorMut::new_at(&mut img, &options, cursor).map(address));
+            assert_eq!(addresses, all, "split at {split}");
+        }
+    }
 }
@@ -1,37 +1,71 @@
-use image::buffer::{Pixels, PixelsMut, Rows, RowsMut};
-use image::Pixel;
-use std::iter::Take;
-use std::ops::Sub;
-use std::slice::{Iter, IterMut};
+use image::buffer::{Pixels, Rows};
+use image::{Pixel, Rgba};
+use std::iter::{Enumerate, Take};
+use std::ops::{Deref, Sub};
+use std::slice::{ChunksExactMut, Iter, IterMut};
 
 /// Allows transposed mutable access to pixel, like column based
 pub(crate) struct TransposeMut<'a, P: Pixel + 'a> {
     i: usize,
     i_max: usize,
     use_max_rows: u32,
-    rows_mut: Take<RowsMut<'a, P>>,
-    rows_buffer: Vec<PixelsMut<'a, P>>,
+    rows_buffer: Vec<ChunksExactMut<'a, P::Subpixel>>,
 }
 
 impl<'a, P: Pixel + 'a> TransposeMut<'a, P> {
-    /// utilises RowsMut to give Column based mut access to pixel
-    pub fn from_rows_mut(
-        rows_mut: RowsMut<'a, P>,
+    /// gives column based mut access to the pixels of an image, that are stored row by row
+    #[cfg(test)]
+    pub fn from_pixels_mut(
+        pixels: &'a mut [P::Subpixel],
         width: u32,
         skip_last_row_and_column: bool,
     ) -> Self {
+        Self::from_pixels_mut_at(pixels, width, skip_last_row_and_column, 0)
+    }
+
+    /// same as `from_pixels_mut`, but it starts at the pixel with the index `start` in column order,
+    /// without iterating over the pixels before
+    pub fn from_pixels_mut_at(
+        pixels: &'a mut [P::Subpixel],
+        width: u32,
+        skip_last_row_and_column: bool,
+        start: usize,
+    ) -> Self {
+        let channels = P::CHANNEL_COUNT as usize;
+        let row_len = (width as usize * channels).max(1);
+        let rows = pixels.len() / row_len;
         let (height, width) = if skip_last_row_and_column {
-            (rows_mut.len() - 1, width.sub(1) as usize)
+            (rows.saturating_sub(1), (width as usize).saturating_sub(1))
         } else {
-            (rows_mut.len(), width as usize)
+            (rows, width as usize)
+        };
+        let i_max = height * width;
+        let start = start.min(i_max);
+        let (column, first_row) = match height {
+            0 => (0, 0),
+            height => (start / height, start % height),
         };
+        let rows_buffer = pixels
+            .chunks_exact_mut(row_len)
+            .take(height)
+            .enumerate()
+            .map(|(row_idx, row)| {
+                // the rows above the start are one column further already
+                let done = if row_idx < first_row {
+                    column + 1
+                } else {
+                    column
+                };
+                let (_, rest) = row.split_at_mut((done * channels).min(row_len));
+                rest.chunks_exact_mut(channels)
+            })
+            .collect();
 
         Self {
-            i: 0,
-            i_max: height * width,
+            i: start,
+            i_max,
             use_max_rows: height as _,
-            rows_mut: rows_mut.take(height),
-            rows_buffer: Vec::with_capacity(height),
+            rows_buffer,
         }
     }
 }
@@ -45,17 +79,10 @@ impl<'a, P: Pixel + 'a> Iterator for TransposeMut<'a, P> {
         }
         let row_idx = ((self.i as u32) % self.use_max_rows) as usize;
         self.i += 1;
-        match self.rows_buffer.get_mut(row_idx) {
-            None => match self.rows_mut.next() {
-                Some(mut row) => {
-                    let p = row.next();
-                    self.rows_buffer.push(row);
-                    p
-                }
-                _ => None,
-            },
-            Some(row) => row.next(),
-        }
+        self.rows_buffer
+            .get_mut(row_idx)?
+            .next()
+            .map(<P as Pixel>::from_slice_mut)
     }
 }
 
@@ -109,70 +136,276 @@ impl<'a, P: Pixel + 'a> Iterator for Transpose<'a, P> {
     }
 }
 
-pub(crate) struct ColorIterMut<'a, P: Pixel + 'a> {
-    pixel: TransposeMut<'a, P>,
-    colors: Take<IterMut<'a, P::Subpixel>>,
-    take: u8,
+/// The channels of the pixel an iterator returned last that carry data, as bits from red (`0b0001`) to alpha
+pub(crate) trait ChannelMask {
+    fn channel_mask(&self) -> u8 {
+        ALL_CHANNELS
+    }
+}
+
+pub(crate) const ALL_CHANNELS: u8 = 0b1111;
+
+impl<'a, P: Pixel + 'a> ChannelMask for TransposeMut<'a, P> {}
+
+/// The index of the pixel an iterator returned last, in column order, see `TransposeMut`
+pub(crate) trait PixelPosition {
+    fn pixel_position(&self) -> usize;
+}
+
+impl<'a, P: Pixel + 'a> PixelPosition for TransposeMut<'a, P> {
+    fn pixel_position(&self) -> usize {
+        self.i.saturating_sub(1)
+    }
+}
+
+impl<'a, P: Pixel + 'a> ChannelMask for Transpose<'a, P> {}
+
+/// Skips the pixels with an alpha below `min_alpha`
+pub(crate) struct MinAlpha<I> {
+    pixels: I,
+    min_alpha: u8,
+}
+
+impl<I> MinAlpha<I> {
+    pub fn new(pixels: I, min_alpha: u8) -> Self {
+        Self { pixels, min_alpha }
+    }
+}
+
+impl<I, T> Iterator for MinAlpha<I>
+where
+    I: Iterator<Item = T>,
+    T: Deref<Target = Rgba<u8>>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let min_alpha = self.min_alpha;
+        self.pixels.find(|p| p.0[3] >= min_alpha)
+    }
+}
+
+impl<I: ChannelMask> ChannelMask for MinAlpha<I> {
+    fn channel_mask(&self) -> u8 {
+        self.pixels.channel_mask()
+    }
+}
+
+impl<I: PixelPosition> PixelPosition for MinAlpha<I> {
+    fn pixel_position(&self) -> usize {
+        self.pixels.pixel_position()
+    }
+}
+
+/// Uses the channels of each pixel that are set in `plan`, it goes along with the pixels.
+/// Pixels without any channel are skipped, `None` uses all channels of all pixels.
+pub(crate) struct Masked<I> {
+    pixels: I,
+    plan: Option<Vec<u8>>,
+    i: usize,
+}
+
+impl<I> Masked<I> {
+    pub fn new(pixels: I, plan: Option<Vec<u8>>) -> Self {
+        Self::new_at(pixels, plan, 0)
+    }
+
+    /// for `pixels` that start at the pixel with the index `start`, e.g. `TransposeMut::from_pixels_mut_at`
+    pub fn new_at(pixels: I, plan: Option<Vec<u8>>, start: usize) -> Self {
+        Self {
+            pixels,
+            plan,
+            i: start,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for Masked<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some(plan) = &self.plan else {
+            return self.pixels.next();
+        };
+        loop {
+            let p = self.pixels.next()?;
+            self.i += 1;
+            if plan.get(self.i - 1).copied().unwrap_or(0) != 0 {
+                return Some(p);
+            }
+        }
+    }
+}
+
+impl<I: PixelPosition> PixelPosition for Masked<I> {
+    fn pixel_position(&self) -> usize {
+        self.pixels.pixel_position()
+    }
+}
+
+impl<I> ChannelMask for Masked<I> {
+    fn channel_mask(&self) -> u8 {
+        match &self.plan {
+            Some(plan) => plan.get(self.i.wrapping_sub(1)).copied().unwrap_or(0),
+            None => ALL_CHANNELS,
+        }
+    }
+}
+
+/// The channels of one pixel that are set in `mask`
+pub(crate) struct MaskedChannels<C> {
+    channels: Enumerate<C>,
+    mask: u8,
+    /// the index of the channel after the one returned last
+    next: usize,
 }
 
-impl<'a, P: Pixel + 'a> ColorIterMut<'a, P> {
-    pub fn from_transpose(mut t: TransposeMut<'a, P>, skip_alpha: bool) -> Self {
-        let take: u8 = if skip_alpha { 3 } else { 4 };
-        let i = t
+impl<C: Iterator> MaskedChannels<C> {
+    fn new(channels: C, mask: u8) -> Self {
+        Self::new_from(channels, mask, 0)
+    }
+
+    /// leaves out the channels before `first`
+    fn new_from(channels: C, mask: u8, first: usize) -> Self {
+        Self {
+            channels: channels.enumerate(),
+            mask: mask & (ALL_CHANNELS << first),
+            next: first,
+        }
+    }
+}
+
+impl<C: Iterator> Iterator for MaskedChannels<C> {
+    type Item = C::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mask = self.mask;
+        let (i, channel) = self.channels.find(|(i, _)| (mask >> i) & 1 == 1)?;
+        self.next = i + 1;
+        Some(channel)
+    }
+}
+
+/// the channels in use, alpha only if not skipped
+fn base_mask(skip_alpha: bool) -> u8 {
+    if skip_alpha {
+        0b0111
+    } else {
+        ALL_CHANNELS
+    }
+}
+
+pub(crate) struct ColorIterMut<'a, P: Pixel + 'a, I: Iterator<Item = &'a mut P>> {
+    pixel: I,
+    colors: MaskedChannels<IterMut<'a, P::Subpixel>>,
+    mask: u8,
+}
+
+impl<'a, P, I> ColorIterMut<'a, P, I>
+where
+    P: Pixel + 'a,
+    I: Iterator<Item = &'a mut P> + ChannelMask + PixelPosition,
+{
+    #[cfg(test)]
+    pub fn from_transpose(t: I, skip_alpha: bool) -> Self {
+        Self::from_transpose_at(t, skip_alpha, 0, 0)
+    }
+
+    /// for pixels that start at the pixel with the index `start`, its channels before `first_channel`
+    /// are left out. If that pixel is not in use, the next pixel in use starts with its first channel
+    pub fn from_transpose_at(
+        mut t: I,
+        skip_alpha: bool,
+        start: usize,
+        first_channel: usize,
+    ) -> Self {
+        let mask = base_mask(skip_alpha);
+        let channels = t
             .next()
-            .unwrap()
-            .channels_mut()
-            .iter_mut()
-            .take(take as usize);
+            .map(|p| p.channels_mut())
+            .unwrap_or_default()
+            .iter_mut();
+        let first_channel = if t.pixel_position() == start {
+            first_channel
+        } else {
+            0
+        };
+        let colors = MaskedChannels::new_from(channels, mask & t.channel_mask(), first_channel);
         Self {
             pixel: t,
-            colors: i,
-            take,
+            colors,
+            mask,
         }
     }
+
+    /// where the next channel would come from: the index of the pixel in column order times 4, plus the channel
+    pub fn cursor(&self) -> usize {
+        self.pixel.pixel_position() * 4 + self.colors.next
+    }
 }
 
-impl<'a, P: Pixel + 'a> Iterator for ColorIterMut<'a, P> {
+impl<'a, P, I> Iterator for ColorIterMut<'a, P, I>
+where
+    P: Pixel + 'a,
+    I: Iterator<Item = &'a mut P> + ChannelMask,
+{
     type Item = &'a mut P::Subpixel;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.colors.next().or_else(|| {
-            if let Some(iter) = self.pixel.next() {
-                self.colors = iter.channels_mut().iter_mut().take(self.take as usize);
+        loop {
+            if let Some(color) = self.colors.next() {
+                return Some(color);
             }
-            self.colors.next()
-        })
+            let pixel = self.pixel.next()?;
+            self.colors = MaskedChannels::new(
+                pixel.channels_mut().iter_mut(),
+                self.mask & self.pixel.channel_mask(),
+            );
+        }
     }
 }
 
-pub(crate) struct ColorIter<'a, P: Pixel + 'a> {
-    pixel: Transpose<'a, P>,
-    colors: Take<Iter<'a, P::Subpixel>>,
-    take: u8,
+pub(crate) struct ColorIter<'a, P: Pixel + 'a, I: Iterator<Item = &'a P>> {
+    pixel: I,
+    colors: MaskedChannels<Iter<'a, P::Subpixel>>,
+    mask: u8,
 }
 
-impl<'a, P: Pixel + 'a> ColorIter<'a, P> {
-    pub fn from_transpose(mut t: Transpose<'a, P>, skip_alpha: bool) -> Self {
-        let take: u8 = if skip_alpha { 3 } else { 4 };
-        let i = t.next().unwrap().channels().iter().take(take as usize);
+impl<'a, P, I> ColorIter<'a, P, I>
+where
+    P: Pixel + 'a,
+    I: Iterator<Item = &'a P> + ChannelMask,
+{
+    pub fn from_transpose(mut t: I, skip_alpha: bool) -> Self {
+        let mask = base_mask(skip_alpha);
+        let channels = t.next().map(|p| p.channels()).unwrap_or_default().iter();
+        let colors = MaskedChannels::new(channels, mask & t.channel_mask());
         Self {
             pixel: t,
-            colors: i,
-            take,
+            colors,
+            mask,
         }
     }
 }
 
-impl<'a, P: Pixel + 'a> Iterator for ColorIter<'a, P> {
+impl<'a, P, I> Iterator for ColorIter<'a, P, I>
+where
+    P: Pixel + 'a,
+    I: Iterator<Item = &'a P> + ChannelMask,
+{
     type Item = &'a P::Subpixel;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.colors.next().or_else(|| {
-            if let Some(iter) = self.pixel.next() {
-                self.colors = iter.channels().iter().take(self.take as usize);
+        loop {
+            if let Some(color) = self.colors.next() {
+                return Some(color);
             }
-            self.colors.next()
-        })
+            let pixel = self.pixel.next()?;
+            self.colors = MaskedChannels::new(
+                pixel.channels().iter(),
+                self.mask & self.pixel.channel_mask(),
+            );
+        }
     }
 }
 
@@ -200,7 +433,7 @@ mod tests {
         }
 
         // now the mut iterator
-        let iter = TransposeMut::from_rows_mut(img.rows_mut(), width, false);
+        let iter = TransposeMut::<Rgba<u8>>::from_pixels_mut(&mut img, width, false);
         let color_iter = ColorIterMut::from_transpose(iter, true);
         for (i, c) in color_iter.enumerate() {
             let i: u8 = i as u8;
@@ -220,7 +453,7 @@ mod tests {
         }
 
         // now the mut iterator
-        let iter = TransposeMut::from_rows_mut(img.rows_mut(), width, true);
+        let iter = TransposeMut::<Rgba<u8>>::from_pixels_mut(&mut img, width, true);
         let color_iter = ColorIterMut::from_transpose(iter, true);
         for (i, c) in color_iter.enumerate() {
             let i: u8 = i as u8;
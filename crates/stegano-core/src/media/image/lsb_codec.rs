@@ -1,5 +1,6 @@
 use super::decoder::ImageRgbaColor;
 use super::encoder::ImageRgbaColorMut;
+use super::redundancy::{MajorityReader, RepeatingWriter};
 use crate::universal_decoder::{OneBitUnveil, UniversalDecoder};
 use crate::universal_encoder::{
     HideAlgorithms, OneBitHide, OneBitInLowFrequencyHide, UniversalEncoder,
@@ -30,6 +31,27 @@ pub struct CodecOptions {
 
     /// This limits all iterations to skip the least column and row, in fact it reduces width and height of the image by 1
     pub skip_last_row_and_column: bool,
+
+    /// If true the whole LSB plane of the carrier gets overwritten with random noise before hiding,
+    /// so that no remnants of previously hidden data survive outside of the new payload.
+    /// The noise is the keystream of a fresh random key, it does not depend on any password.
+    pub sanitize_lsb_plane: bool,
+
+    /// decides which pixels are used depending on their alpha, see `AlphaPolicy`
+    pub alpha_policy: AlphaPolicy,
+
+    /// Perceptual shaping, `0` uses all pixels. From `1` up to `3` only pixels in ever busier areas are used,
+    /// where the eye cannot see the changes, this trades capacity for imperceptibility.
+    pub stealth_level: u8,
+
+    /// If true smooth gradients only carry data in the blue channel, where the eye is least sensitive,
+    /// busier areas keep using all channels. This avoids visible banding in skies and the like, at the cost of capacity.
+    pub banding_aware: bool,
+
+    /// Every byte of the payload is hidden this many times in a row, unveiling takes the majority of each bit.
+    /// Single flipped bits, e.g. by a tool that touches a few pixels, are outvoted, at the cost of capacity.
+    /// `0` counts as `1`, odd numbers avoid ties.
+    pub redundancy: u8,
 }
 
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq)]
@@ -38,6 +60,31 @@ pub enum Concealer {
     LowFrequencies,
 }
 
+/// Which pixels carry data, depending on their alpha.
+///
+/// Except for `AllPixels` the alpha channel itself is never written,
+/// so that no pixel can move in or out of the policy by hiding data.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Copy)]
+pub enum AlphaPolicy {
+    /// all pixels, regardless of their alpha
+    AllPixels,
+    /// skips fully transparent pixels, other tools may normalize their colors away, e.g. to black
+    SkipTransparent,
+    /// only fully opaque pixels, they survive tools that premultiply the colors with alpha
+    OpaqueOnly,
+}
+
+impl AlphaPolicy {
+    /// the lowest alpha of a pixel that carries data
+    pub fn min_alpha(&self) -> u8 {
+        match self {
+            AlphaPolicy::AllPixels => 0,
+            AlphaPolicy::SkipTransparent => 1,
+            AlphaPolicy::OpaqueOnly => u8::MAX,
+        }
+    }
+}
+
 impl Default for CodecOptions {
     /// The good old golden options
     fn default() -> Self {
@@ -46,6 +93,11 @@ impl Default for CodecOptions {
             skip_alpha_channel: true,
             concealer: Concealer::LeastSignificantBit,
             skip_last_row_and_column: true,
+            sanitize_lsb_plane: false,
+            alpha_policy: AlphaPolicy::AllPixels,
+            stealth_level: 0,
+            banding_aware: false,
+            redundancy: 1,
         }
     }
 }
@@ -58,6 +110,16 @@ impl CodecOptions {
     pub fn get_skip_alpha_channel(&self) -> bool {
         self.skip_alpha_channel
     }
+
+    /// if the alpha channel is left untouched, either by choice or because of the alpha policy
+    pub(crate) fn alpha_channel_skipped(&self) -> bool {
+        self.skip_alpha_channel || self.alpha_policy != AlphaPolicy::AllPixels
+    }
+
+    /// how many times each byte of the payload is hidden, see `redundancy`
+    pub(crate) fn copies(&self) -> usize {
+        self.redundancy.max(1) as usize
+    }
 }
 
 /// Factory for decoder and encoder
@@ -66,25 +128,66 @@ pub struct LsbCodec;
 impl LsbCodec {
     /// builds a LSB Image Decoder that implements Read
     pub fn decoder<'i>(input: &'i RgbaImage, opts: &CodecOptions) -> Box<dyn Read + 'i> {
-        Box::new(UniversalDecoder::new(
+        let decoder = UniversalDecoder::new(
             ImageRgbaColor::new_with_options(input, opts),
             match opts.concealer {
                 Concealer::LeastSignificantBit => OneBitUnveil,
                 Concealer::LowFrequencies => OneBitUnveil,
             },
-        ))
+        );
+        match opts.copies() {
+            1 => Box::new(decoder),
+            copies => Box::new(MajorityReader::new(decoder, copies)),
+        }
     }
 
     /// builds a LSB Image Encoder that implements Write
     pub fn encoder<'i>(carrier: &'i mut RgbaImage, opts: &CodecOptions) -> Box<dyn Write + 'i> {
-        let algorithm: HideAlgorithms = match opts.concealer {
-            Concealer::LeastSignificantBit => OneBitHide.into(),
-            Concealer::LowFrequencies => OneBitInLowFrequencyHide.into(),
-        };
-        Box::new(UniversalEncoder::new(
-            ImageRgbaColorMut::new_with_options(carrier, opts),
-            algorithm,
-        ))
+        Self::encoder_at(carrier, opts, 0)
+    }
+
+    /// the bytes of a payload that fit into `carrier`, counted over the color channels that
+    /// the options leave for data, see `alpha_policy`, `stealth_level`, `banding_aware` and `redundancy`
+    pub fn capacity(carrier: &RgbaImage, opts: &CodecOptions) -> usize {
+        ImageRgbaColor::new_with_options(carrier, opts).count() / 8 / opts.copies()
+    }
+
+    /// builds a LSB Image Encoder that starts writing at the byte `offset` of the payload
+    pub fn encoder_at<'i>(
+        carrier: &'i mut RgbaImage,
+        opts: &CodecOptions,
+        offset: usize,
+    ) -> Box<dyn Write + 'i> {
+        let copies = opts.copies();
+        let encoder = UniversalEncoder::new(
+            ImageRgbaColorMut::new_with_options(carrier, opts).skip((offset * copies) << 3),
+            hide_algorithm(opts),
+        );
+        match copies {
+            1 => Box::new(encoder),
+            copies => Box::new(RepeatingWriter::new(encoder, copies)),
+        }
+    }
+
+    /// builds a LSB Image Encoder that starts writing at the `cursor` of a previous encoder,
+    /// see `ImageRgbaColorMut::cursor`, its `carrier` tells the cursor after writing.
+    /// It writes every byte once, the caller repeats the bytes for `CodecOptions::redundancy`
+    pub(crate) fn encoder_from<'i>(
+        carrier: &'i mut RgbaImage,
+        opts: &CodecOptions,
+        cursor: usize,
+    ) -> UniversalEncoder<'i, ImageRgbaColorMut<'i>, HideAlgorithms> {
+        UniversalEncoder::new(
+            ImageRgbaColorMut::new_at(carrier, opts, cursor),
+            hide_algorithm(opts),
+        )
+    }
+}
+
+fn hide_algorithm(opts: &CodecOptions) -> HideAlgorithms {
+    match opts.concealer {
+        Concealer::LeastSignificantBit => OneBitHide.into(),
+        Concealer::LowFrequencies => OneBitInLowFrequencyHide.into(),
     }
 }
 
@@ -128,6 +231,8 @@ mod benchmarks {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::media::image::perceptual::{pixel_plan, MAX_STEALTH_LEVEL};
+    use crate::media::Media;
 
     #[test]
     fn should_decode() {
@@ -144,6 +249,96 @@ mod tests {
         assert_eq!(msg, "\u{1}Hello World!");
     }
 
+    #[test]
+    fn should_leave_transparent_pixels_alone() {
+        let mut carrier = RgbaImage::from_fn(16, 16, |x, _| {
+            image::Rgba([100, 150, 200, if x % 2 == 0 { 0 } else { 255 }])
+        });
+        let opts = CodecOptions {
+            alpha_policy: AlphaPolicy::SkipTransparent,
+            ..CodecOptions::default()
+        };
+        let secret_message = "Hello World!".as_bytes();
+
+        LsbCodec::encoder(&mut carrier, &opts)
+            .write_all(secret_message)
+            .expect("Cannot write to codec");
+        for (x, _, pixel) in carrier.enumerate_pixels() {
+            if x % 2 == 0 {
+                assert_eq!(
+                    pixel.0,
+                    [100, 150, 200, 0],
+                    "transparent pixels must not change"
+                );
+            }
+        }
+
+        let mut buf = vec![0; secret_message.len()];
+        LsbCodec::decoder(&carrier, &opts)
+            .read_exact(&mut buf[..])
+            .expect("Cannot read 12 bytes from codec");
+        assert_eq!(buf, secret_message);
+    }
+
+    #[test]
+    fn should_hide_only_in_textured_areas() {
+        let mut carrier = image::open("tests/images/plain/carrier-image.png")
+            .expect("Cannot open carrier image")
+            .to_rgba8();
+        let plain = carrier.clone();
+        let opts = CodecOptions {
+            stealth_level: MAX_STEALTH_LEVEL,
+            ..CodecOptions::default()
+        };
+        let plan = pixel_plan(&carrier, &opts, true).unwrap();
+        let secret_message = "Hello World!".as_bytes();
+
+        LsbCodec::encoder(&mut carrier, &opts)
+            .write_all(secret_message)
+            .expect("Cannot write to codec");
+        let (width, height) = carrier.dimensions();
+        for x in 0..width - 1 {
+            for y in 0..height - 1 {
+                if plan[(x * (height - 1) + y) as usize] == 0 {
+                    assert_eq!(carrier.get_pixel(x, y), plain.get_pixel(x, y));
+                }
+            }
+        }
+
+        let mut buf = vec![0; secret_message.len()];
+        LsbCodec::decoder(&carrier, &opts)
+            .read_exact(&mut buf[..])
+            .expect("Cannot read 12 bytes from codec");
+        assert_eq!(buf, secret_message);
+    }
+
+    #[test]
+    fn should_only_touch_blue_in_smooth_gradients() {
+        let mut carrier = RgbaImage::from_fn(32, 32, |x, y| {
+            image::Rgba([60 + 2 * x as u8, 90 + 2 * y as u8, 180, 255])
+        });
+        let plain = carrier.clone();
+        let opts = CodecOptions {
+            banding_aware: true,
+            ..CodecOptions::default()
+        };
+        let secret_message = "Hello World!".as_bytes();
+
+        LsbCodec::encoder(&mut carrier, &opts)
+            .write_all(secret_message)
+            .expect("Cannot write to codec");
+        for (x, y, pixel) in carrier.enumerate_pixels() {
+            let before = plain.get_pixel(x, y);
+            assert_eq!(pixel.0[..2], before.0[..2], "only blue may change");
+        }
+
+        let mut buf = vec![0; secret_message.len()];
+        LsbCodec::decoder(&carrier, &opts)
+            .read_exact(&mut buf[..])
+            .expect("Cannot read 12 bytes from codec");
+        assert_eq!(buf, secret_message);
+    }
+
     #[test]
     fn should_encode() {
         let mut plain_image = image::open("tests/images/plain/carrier-image.png")
@@ -164,4 +359,75 @@ mod tests {
         let msg = String::from_utf8(buf).expect("Cannot convert result to string");
         assert_eq!(msg, "Hello World!");
     }
+
+    #[test]
+    fn should_outvote_flipped_bits_with_redundancy() {
+        let opts = CodecOptions {
+            redundancy: 3,
+            ..CodecOptions::default()
+        };
+        let mut media = Media::from_image(RgbaImage::from_pixel(
+            16,
+            16,
+            image::Rgba([100, 150, 200, 255]),
+        ));
+        media.hide_data(b"Hello World!".to_vec(), &opts).unwrap();
+        let Media::Image(mut carrier) = media else {
+            unreachable!()
+        };
+        // the first bit of the first copy of `H`
+        carrier.get_pixel_mut(0, 0)[0] ^= 1;
+
+        let mut buf = [0; 12];
+        LsbCodec::decoder(&carrier, &opts)
+            .read_exact(&mut buf)
+            .unwrap();
+        assert_eq!(&buf, b"Hello World!");
+    }
+
+    #[test]
+    fn should_tell_the_capacity_for_the_options() {
+        let mut carrier = RgbaImage::from_fn(16, 16, |x, _| {
+            image::Rgba([100, 150, 200, if x % 2 == 0 { 0 } else { 255 }])
+        });
+        let opts = CodecOptions::default();
+        let capacity = LsbCodec::capacity(&carrier, &opts);
+        assert_eq!(capacity, 15 * 15 * 3 / 8);
+
+        let opaque_only = CodecOptions {
+            alpha_policy: AlphaPolicy::OpaqueOnly,
+            ..CodecOptions::default()
+        };
+        assert_eq!(LsbCodec::capacity(&carrier, &opaque_only), 7 * 15 * 3 / 8);
+        let redundant = CodecOptions {
+            redundancy: 3,
+            ..CodecOptions::default()
+        };
+        assert_eq!(LsbCodec::capacity(&carrier, &redundant), capacity / 3);
+
+        let mut encoder = LsbCodec::encoder(&mut carrier, &opts);
+        assert!(encoder.write_all(&vec![0xaa; capacity]).is_ok());
+        assert!(encoder.write_all(&[0xaa]).is_err(), "it should be full");
+    }
+
+    #[test]
+    fn should_continue_the_copies_at_an_offset() {
+        let opts = CodecOptions {
+            redundancy: 3,
+            ..CodecOptions::default()
+        };
+        let mut carrier = RgbaImage::from_pixel(16, 16, image::Rgba([100, 150, 200, 255]));
+        LsbCodec::encoder(&mut carrier, &opts)
+            .write_all(b"Hello")
+            .unwrap();
+        LsbCodec::encoder_at(&mut carrier, &opts, 5)
+            .write_all(b" World!")
+            .unwrap();
+
+        let mut buf = [0; 12];
+        LsbCodec::decoder(&carrier, &opts)
+            .read_exact(&mut buf)
+            .unwrap();
+        assert_eq!(&buf, b"Hello World!");
+    }
 }
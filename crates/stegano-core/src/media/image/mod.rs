@@ -1,6 +1,10 @@
+pub mod bands;
 pub mod decoder;
 pub mod encoder;
 mod iterators;
 pub mod lsb_codec;
+pub mod perceptual;
+pub mod redundancy;
 
-pub use lsb_codec::{CodecOptions, LsbCodec};
+pub use bands::BandEncoder;
+pub use lsb_codec::{AlphaPolicy, CodecOptions, LsbCodec};
@@ -0,0 +1,128 @@
+use image::RgbaImage;
+
+use super::iterators::ALL_CHANNELS;
+use super::lsb_codec::{CodecOptions, Concealer};
+
+/// the highest stealth level, see `CodecOptions::stealth_level`
+pub const MAX_STEALTH_LEVEL: u8 = 3;
+
+/// the only channel used in smooth areas with `CodecOptions::banding_aware`,
+/// the eye is least sensitive to small steps in blue
+const SMOOTH_CHANNELS: u8 = 0b0100;
+
+/// Plans the channels of each pixel that hide data, in the order the pixels are iterated, column by column.
+/// `0` skips a pixel, see `stealth_level`, and smooth gradients only use blue, see `banding_aware`.
+/// `None` if all channels of all pixels are used.
+///
+/// The texture is measured on the bits the concealer leaves untouched,
+/// so that the very same plan is found again when unveiling.
+pub(crate) fn pixel_plan(
+    img: &RgbaImage,
+    opts: &CodecOptions,
+    skip_last_row_and_column: bool,
+) -> Option<Vec<u8>> {
+    let min_activity = match opts.stealth_level.min(MAX_STEALTH_LEVEL) {
+        0 if !opts.banding_aware => return None,
+        0 => 0,
+        level => 2u8 << level,
+    };
+    let untouched = match opts.concealer {
+        Concealer::LeastSignificantBit => !1u8,
+        Concealer::LowFrequencies => 0b11110000,
+    };
+    // neighbours that differ by no more than one untouched step form a smooth gradient
+    let smooth_step = !untouched + 1;
+
+    let (width, height) = img.dimensions();
+    let (used_width, used_height) = if skip_last_row_and_column {
+        (width.saturating_sub(1), height.saturating_sub(1))
+    } else {
+        (width, height)
+    };
+    let color = |x: u32, y: u32| img.get_pixel(x, y).0.map(|c| c & untouched);
+
+    let mut plan = Vec::with_capacity(used_width as usize * used_height as usize);
+    for x in 0..used_width {
+        for y in 0..used_height {
+            let center = color(x, y);
+            let neighbours = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+            // luminance masking, the largest step to a neighbour tells how busy the area is
+            let activity = neighbours
+                .into_iter()
+                .filter(|(nx, ny)| *nx < width && *ny < height)
+                .flat_map(|(nx, ny)| {
+                    let neighbour = color(nx, ny);
+                    (0..3).map(move |c| center[c].abs_diff(neighbour[c]))
+                })
+                .max()
+                .unwrap_or(0);
+            plan.push(if activity < min_activity {
+                0
+            } else if opts.banding_aware && activity <= smooth_step {
+                SMOOTH_CHANNELS
+            } else {
+                ALL_CHANNELS
+            });
+        }
+    }
+
+    Some(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_skip_pixels_that_are_not_textured() {
+        let mut img = RgbaImage::from_pixel(8, 8, image::Rgba([100, 100, 100, 255]));
+        for y in 0..8 {
+            img.put_pixel(5, y, image::Rgba([180, 100, 100, 255]));
+        }
+        let opts = CodecOptions {
+            stealth_level: 2,
+            ..CodecOptions::default()
+        };
+
+        let plan = pixel_plan(&img, &opts, true).unwrap();
+        assert_eq!(plan.len(), 7 * 7);
+        for (i, channels) in plan.into_iter().enumerate() {
+            let x = i / 7;
+            let textured = (4..=6).contains(&x);
+            assert_eq!(channels != 0, textured, "column {x}");
+        }
+
+        assert!(pixel_plan(&img, &CodecOptions::default(), true).is_none());
+    }
+
+    #[test]
+    fn should_only_use_blue_in_smooth_gradients() {
+        let mut img = RgbaImage::new(8, 8);
+        for (x, _, pixel) in img.enumerate_pixels_mut() {
+            let c = 100 + 2 * x as u8;
+            *pixel = image::Rgba([c, c, c, 255]);
+        }
+        for y in 0..8 {
+            img.put_pixel(2, y, image::Rgba([30, 200, 60, 255]));
+        }
+        let opts = CodecOptions {
+            banding_aware: true,
+            ..CodecOptions::default()
+        };
+
+        let plan = pixel_plan(&img, &opts, true).unwrap();
+        for (i, channels) in plan.into_iter().enumerate() {
+            let x = i / 7;
+            let expected = match x {
+                1..=3 => ALL_CHANNELS,
+                _ => SMOOTH_CHANNELS,
+            };
+            assert_eq!(channels, expected, "column {x}");
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::borrow::Cow;
+use std::io::{ErrorKind, Read, Result, Write};
+
+/// Writes every byte `copies` times in a row, see `CodecOptions::redundancy`.
+///
+/// Like the encoder it wraps, it writes short when the carrier is full,
+/// it then reports only the bytes of which all copies were written.
+pub struct RepeatingWriter<W> {
+    inner: W,
+    copies: usize,
+}
+
+impl<W: Write> RepeatingWriter<W> {
+    pub fn new(inner: W, copies: usize) -> Self {
+        Self {
+            inner,
+            copies: copies.max(1),
+        }
+    }
+}
+
+impl<W: Write> Write for RepeatingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let repeated = repeat(buf, self.copies);
+        let mut written = 0;
+        while written < repeated.len() {
+            match self.inner.write(&repeated[written..]) {
+                Ok(0) => break,
+                Ok(n) => written += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(written / self.copies)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads `copies` bytes for every byte and takes the majority of each bit,
+/// the counterpart of `RepeatingWriter`. Ties, with an even number of copies, unveil a `0`.
+pub struct MajorityReader<R> {
+    inner: R,
+    copies: usize,
+}
+
+impl<R: Read> MajorityReader<R> {
+    pub fn new(inner: R, copies: usize) -> Self {
+        Self {
+            inner,
+            copies: copies.max(1),
+        }
+    }
+}
+
+impl<R: Read> Read for MajorityReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut groups = vec![0; buf.len() * self.copies];
+        let mut read = 0;
+        while read < groups.len() {
+            match self.inner.read(&mut groups[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        // a group cut short by the end of the carrier is dropped
+        let groups = groups[..read].chunks_exact(self.copies);
+        let len = groups.len();
+        for (byte, group) in buf.iter_mut().zip(groups) {
+            *byte = majority(group);
+        }
+
+        Ok(len)
+    }
+}
+
+/// `data` with every byte `copies` times in a row
+pub(crate) fn repeat(data: &[u8], copies: usize) -> Cow<'_, [u8]> {
+    if copies <= 1 {
+        return Cow::Borrowed(data);
+    }
+
+    Cow::Owned(
+        data.iter()
+            .flat_map(|&byte| std::iter::repeat_n(byte, copies))
+            .collect(),
+    )
+}
+
+fn majority(group: &[u8]) -> u8 {
+    (0..8).fold(0, |byte, bit| {
+        let ones = group.iter().filter(|copy| (*copy >> bit) & 1 == 1).count();
+        if ones * 2 > group.len() {
+            byte | (1 << bit)
+        } else {
+            byte
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_unveil_what_was_repeated() {
+        let mut carrier = Vec::new();
+        RepeatingWriter::new(&mut carrier, 3)
+            .write_all(b"Hello World!")
+            .unwrap();
+        assert_eq!(carrier.len(), 36);
+        assert_eq!(&carrier[..6], b"HHHeee");
+
+        let mut unveiled = Vec::new();
+        MajorityReader::new(&carrier[..], 3)
+            .read_to_end(&mut unveiled)
+            .unwrap();
+        assert_eq!(unveiled, b"Hello World!");
+    }
+
+    #[test]
+    fn should_outvote_a_flipped_bit() {
+        let mut carrier = repeat(b"Hi", 3).into_owned();
+        carrier[0] ^= 0b0000_0001;
+        carrier[4] ^= 0b1000_0000;
+
+        let mut unveiled = Vec::new();
+        MajorityReader::new(&carrier[..], 3)
+            .read_to_end(&mut unveiled)
+            .unwrap();
+        assert_eq!(unveiled, b"Hi");
+    }
+
+    #[test]
+    fn should_report_only_bytes_with_all_copies() {
+        let mut carrier = [0; 7];
+        let written = RepeatingWriter::new(&mut carrier[..], 3)
+            .write(b"abc")
+            .unwrap();
+        assert_eq!(written, 2);
+    }
+}
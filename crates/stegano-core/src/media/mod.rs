@@ -2,11 +2,13 @@ pub mod audio;
 pub mod image;
 pub mod payload;
 mod primitives;
+mod resume;
 mod types;
 
 use std::path::Path;
 
 pub use primitives::*;
+pub use resume::HideProgress;
 pub use types::*;
 
 pub trait Persist {
@@ -1,7 +1,8 @@
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use std::io::Read;
+use std::io::{Read, Write};
 
 use crate::result::Result;
+use crate::SteganoError;
 
 pub(crate) const TEXT_ONLY: u8 = 1 << 0;
 pub(crate) const TEXT_AND_DOCUMENTS_TERMINATED: u8 = 1 << 1;
@@ -9,6 +10,8 @@ pub(crate) const TEXT_AND_DOCUMENTS: u8 = 1 << 2;
 pub(crate) const LENGTH_HEADER: u8 = 1 << 3;
 pub(crate) const AES_CRYPTO: u8 = 1 << 4;
 pub(crate) const CHA_CRYPTO: u8 = 1 << 5;
+pub(crate) const THRESHOLD_CRYPTO: u8 = 1 << 6;
+pub(crate) const PADDED: u8 = 1 << 7;
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum PayloadCodecFeatures {
@@ -18,6 +21,8 @@ pub enum PayloadCodecFeatures {
     LengthHeader,
     AesCrypto,
     ChaCrypto,
+    ThresholdCrypto,
+    Padded,
     MixedFeatures(u8),
 }
 
@@ -40,6 +45,8 @@ impl From<PayloadCodecFeatures> for u8 {
             PayloadCodecFeatures::LengthHeader => LENGTH_HEADER,
             PayloadCodecFeatures::AesCrypto => AES_CRYPTO,
             PayloadCodecFeatures::ChaCrypto => CHA_CRYPTO,
+            PayloadCodecFeatures::ThresholdCrypto => THRESHOLD_CRYPTO,
+            PayloadCodecFeatures::Padded => PADDED,
             PayloadCodecFeatures::MixedFeatures(other) => other,
         }
     }
@@ -87,20 +94,55 @@ where
     }
 }
 
+/// a u32 length of all ones announces that the real length follows as u64,
+/// so lengths below 4 GB keep their original 4 byte encoding
+const LONG_LENGTH_MARKER: u32 = u32::MAX;
+
+/// writes a length as u32, or as the marker followed by a u64 if it does not fit
+pub(crate) fn write_length(w: &mut dyn Write, len: usize) -> Result<()> {
+    match u32::try_from(len) {
+        Ok(len) if len != LONG_LENGTH_MARKER => w.write_u32::<BigEndian>(len)?,
+        _ => {
+            w.write_u32::<BigEndian>(LONG_LENGTH_MARKER)?;
+            w.write_u64::<BigEndian>(len as u64)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// the amount of bytes `write_length` writes for `len`
+pub(crate) fn length_len(len: usize) -> usize {
+    match u32::try_from(len) {
+        Ok(len) if len != LONG_LENGTH_MARKER => 4,
+        _ => 12,
+    }
+}
+
+/// reads a length written by `write_length`
+pub(crate) fn read_length(r: &mut dyn Read) -> Result<usize> {
+    let len = match r.read_u32::<BigEndian>()? {
+        LONG_LENGTH_MARKER => r.read_u64::<BigEndian>()?,
+        len => len as u64,
+    };
+
+    usize::try_from(len).map_err(|_| SteganoError::PayloadTooLarge(len))
+}
+
 #[derive(Debug, Default)]
 pub struct PayloadDecoderWithLengthHeader;
 impl PayloadDecoder for PayloadDecoderWithLengthHeader {
     fn decode(&self, content: &mut dyn Read) -> Result<Vec<u8>> {
-        let len = content.read_u32::<BigEndian>()? as usize;
+        let len = read_length(content)?;
+        // streams only the payload out of the carrier, not the rest of its capacity
         let mut buffer = Vec::new();
-        content.read_to_end(&mut buffer)?;
-        if len > buffer.len() {
-            panic!(
-                "read len value cannot be bigger `{len}` than the actual buffer `{}`",
-                buffer.len()
-            );
+        Read::take(&mut *content, len as u64).read_to_end(&mut buffer)?;
+        if buffer.len() < len {
+            return Err(SteganoError::TruncatedPayload {
+                expected: len as u64,
+                found: buffer.len() as u64,
+            });
         }
-        buffer.truncate(len);
 
         Ok(buffer)
     }
@@ -126,9 +168,9 @@ impl PayloadEncoder for PayloadEncoderWithLengthHeader {
         let mut src = Vec::new();
         content.read_to_end(&mut src)?;
 
-        let mut buffer = Vec::with_capacity(src.len() + 6);
+        let mut buffer = Vec::with_capacity(src.len() + 14);
         buffer.write_u8(self.version().into())?;
-        buffer.write_u32::<BigEndian>(src.len() as u32)?;
+        write_length(&mut buffer, src.len())?;
         buffer.extend_from_slice(&src[..]);
         buffer.write_u8(0xff)?;
 
@@ -188,4 +230,58 @@ mod tests {
         assert!(f.has_feature(PayloadCodecFeatures::LengthHeader));
         assert!(f.has_feature(PayloadCodecFeatures::ChaCrypto));
     }
+
+    #[test]
+    fn should_keep_short_lengths_at_4_bytes() {
+        let mut buf = Vec::new();
+        write_length(&mut buf, 42).unwrap();
+        assert_eq!(buf, vec![0, 0, 0, 42]);
+
+        assert_eq!(read_length(&mut buf.as_slice()).unwrap(), 42);
+    }
+
+    #[test]
+    fn should_write_long_lengths_as_u64() {
+        let len = 5 * 1024 * 1024 * 1024_u64;
+        let mut buf = Vec::new();
+        write_length(&mut buf, len as usize).unwrap();
+        assert_eq!(buf.len(), 12);
+        assert_eq!(buf[..4], [0xff; 4]);
+
+        let r = read_length(&mut buf.as_slice());
+        if cfg!(target_pointer_width = "64") {
+            assert_eq!(r.unwrap() as u64, len);
+        } else {
+            assert!(matches!(r, Err(SteganoError::PayloadTooLarge(l)) if l == len));
+        }
+    }
+
+    #[test]
+    fn should_read_no_further_than_the_length() {
+        let mut data = vec![0, 0, 0, 3, 1, 2, 3];
+        data.extend_from_slice(&[0xaa; 1024]);
+        let mut carrier = std::io::Cursor::new(data);
+
+        let payload = PayloadDecoderWithLengthHeader.decode(&mut carrier).unwrap();
+        assert_eq!(payload, vec![1, 2, 3]);
+        assert_eq!(
+            carrier.position(),
+            7,
+            "the rest of the carrier should stay unread"
+        );
+    }
+
+    #[test]
+    fn should_error_on_a_truncated_payload() {
+        let mut carrier = std::io::Cursor::new(vec![0, 0, 0, 10, 1, 2, 3]);
+
+        let r = PayloadDecoderWithLengthHeader.decode(&mut carrier);
+        assert!(matches!(
+            r,
+            Err(SteganoError::TruncatedPayload {
+                expected: 10,
+                found: 3
+            })
+        ));
+    }
 }
@@ -2,6 +2,8 @@ use std::io::Read;
 
 use stegano_seasmoke::decrypt_data;
 use stegano_seasmoke::encrypt_data;
+use stegano_seasmoke::{decrypt_data_threshold, encrypt_data_threshold};
+use zeroize::{Zeroize, Zeroizing};
 
 use super::FabA;
 use super::PayloadCodecFactory;
@@ -24,6 +26,12 @@ impl FabS {
     }
 }
 
+impl Drop for FabS {
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
+}
+
 impl PayloadCodecFactory for FabS {
     fn create_codec(&self, features: PayloadCodecFeatures) -> Result<Box<dyn PayloadCodec>> {
         let features = features
@@ -36,6 +44,10 @@ impl PayloadCodecFactory for FabS {
             self.password.clone(),
         )))
     }
+
+    fn encryption_overhead(&self) -> usize {
+        stegano_seasmoke::encryption_overhead()
+    }
 }
 
 pub struct CryptedPayloadCodec {
@@ -52,13 +64,19 @@ impl CryptedPayloadCodec {
     }
 }
 
+impl Drop for CryptedPayloadCodec {
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
+}
+
 impl PayloadEncoder for CryptedPayloadCodec {
     fn version(&self) -> PayloadCodecFeatures {
         self.inner_encoder.version()
     }
 
     fn encode(&self, content: &mut dyn Read) -> Result<Vec<u8>> {
-        let mut data = Vec::new();
+        let mut data = Zeroizing::new(Vec::new());
         // let's collect all data first
         content.read_to_end(&mut data)?;
 
@@ -84,6 +102,94 @@ impl PayloadDecoder for CryptedPayloadCodec {
 
 impl PayloadCodec for CryptedPayloadCodec {}
 
+/// Factory for payloads that require any `threshold` out of a set of passwords to decrypt.
+/// The `threshold` is only relevant for hiding, on unveil it is read from the payload.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FabT {
+    pub passwords: Vec<String>,
+    pub threshold: u8,
+}
+
+impl FabT {
+    pub fn new<I: Into<String>>(passwords: impl IntoIterator<Item = I>, threshold: u8) -> Self {
+        FabT {
+            passwords: passwords.into_iter().map(Into::into).collect(),
+            threshold,
+        }
+    }
+}
+
+impl Drop for FabT {
+    fn drop(&mut self) {
+        self.passwords.zeroize();
+    }
+}
+
+impl PayloadCodecFactory for FabT {
+    fn create_codec(&self, features: PayloadCodecFeatures) -> Result<Box<dyn PayloadCodec>> {
+        let features = features
+            .add_feature(PayloadCodecFeatures::ChaCrypto)
+            .add_feature(PayloadCodecFeatures::ThresholdCrypto)
+            .add_feature(PayloadCodecFeatures::LengthHeader);
+        let codec = FabA.create_codec(features)?;
+
+        Ok(Box::new(ThresholdCryptedPayloadCodec {
+            inner_encoder: codec,
+            passwords: self.passwords.clone(),
+            threshold: self.threshold,
+        }))
+    }
+
+    fn encryption_overhead(&self) -> usize {
+        stegano_seasmoke::threshold_encryption_overhead(self.passwords.len())
+    }
+}
+
+pub struct ThresholdCryptedPayloadCodec {
+    inner_encoder: Box<dyn PayloadCodec>,
+    passwords: Vec<String>,
+    threshold: u8,
+}
+
+impl Drop for ThresholdCryptedPayloadCodec {
+    fn drop(&mut self) {
+        self.passwords.zeroize();
+    }
+}
+
+impl ThresholdCryptedPayloadCodec {
+    fn passwords(&self) -> Vec<&str> {
+        self.passwords.iter().map(String::as_str).collect()
+    }
+}
+
+impl PayloadEncoder for ThresholdCryptedPayloadCodec {
+    fn version(&self) -> PayloadCodecFeatures {
+        self.inner_encoder.version()
+    }
+
+    fn encode(&self, content: &mut dyn Read) -> Result<Vec<u8>> {
+        let mut data = Zeroizing::new(Vec::new());
+        content.read_to_end(&mut data)?;
+
+        let data = encrypt_data_threshold(&self.passwords(), self.threshold, &data)
+            .map_err(SteganoError::EncryptionError)?;
+
+        let mut cursor = std::io::Cursor::new(data);
+        self.inner_encoder.encode(&mut cursor)
+    }
+}
+
+impl PayloadDecoder for ThresholdCryptedPayloadCodec {
+    fn decode(&self, content: &mut dyn Read) -> Result<Vec<u8>> {
+        let data = self.inner_encoder.decode(content)?;
+
+        decrypt_data_threshold(&self.passwords(), &data).map_err(SteganoError::DecryptionError)
+    }
+}
+
+impl PayloadCodec for ThresholdCryptedPayloadCodec {}
+
 #[cfg(test)]
 mod tests {
     use crate::{media::payload::HasFeature, Message};
@@ -112,4 +218,21 @@ mod tests {
 
         assert_eq!(msg_decrypted, msg);
     }
+
+    #[test]
+    fn test_threshold_encryption_codec() {
+        let cipher = FabT::new(["alice", "bob", "carol"], 2);
+        let msg = Message::from_files(&["LICENSE"]).unwrap();
+        let encrypted_data = msg.to_raw_data(&cipher).unwrap();
+
+        let features = PayloadCodecFeatures::MixedFeatures(encrypted_data[0]);
+        assert!(features.has_feature(PayloadCodecFeatures::ThresholdCrypto));
+        assert!(features.has_feature(PayloadCodecFeatures::LengthHeader));
+
+        let decipher = FabT::new(["bob", "carol"], 0);
+        let msg_decrypted =
+            Message::from_raw_data(&mut std::io::Cursor::new(encrypted_data), &decipher).unwrap();
+
+        assert_eq!(msg_decrypted, msg);
+    }
 }
@@ -6,6 +6,11 @@ use crate::result::Result;
 
 pub trait PayloadCodecFactory {
     fn create_codec(&self, features: PayloadCodecFeatures) -> Result<Box<dyn PayloadCodec>>;
+
+    /// the bytes the encryption of the codecs adds to the content, known without deriving any key
+    fn encryption_overhead(&self) -> usize {
+        0
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -5,6 +5,44 @@ use super::*;
 #[derive(Debug, PartialEq, Eq)]
 pub struct FabTextOnly;
 
+/// Which container layouts of older stegano-rs versions are read when unveiling
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyFormats {
+    /// the layout is told by the version byte, old containers are read even if a password is given,
+    /// because they never carried encryption
+    #[default]
+    Detect,
+    /// only current layouts, old containers are refused
+    Reject,
+    /// only the text only layout of message version 1
+    OnlyV1,
+    /// only the text and documents layout with a terminator of message version 2
+    OnlyV2,
+}
+
+/// Reads the old layouts as selected by `formats`, all other containers are left to `inner`
+pub struct FabLegacy<'f> {
+    pub formats: LegacyFormats,
+    pub inner: &'f dyn PayloadCodecFactory,
+}
+
+impl PayloadCodecFactory for FabLegacy<'_> {
+    fn create_codec(&self, features: PayloadCodecFeatures) -> Result<Box<dyn PayloadCodec>> {
+        let version: u8 = features.into();
+        let is_legacy = matches!(version, TEXT_ONLY | TEXT_AND_DOCUMENTS_TERMINATED);
+        match (self.formats, version) {
+            (LegacyFormats::Detect, _) if is_legacy => FabTextOnly.create_codec(features),
+            (LegacyFormats::Detect, _) => self.inner.create_codec(features),
+            (LegacyFormats::Reject, _) if !is_legacy => self.inner.create_codec(features),
+            (LegacyFormats::OnlyV1, TEXT_ONLY)
+            | (LegacyFormats::OnlyV2, TEXT_AND_DOCUMENTS_TERMINATED) => {
+                FabTextOnly.create_codec(features)
+            }
+            _ => Err(crate::SteganoError::UnsupportedMessageFormat(version)),
+        }
+    }
+}
+
 impl PayloadCodecFactory for FabTextOnly {
     fn create_codec(&self, features: PayloadCodecFeatures) -> Result<Box<dyn PayloadCodec>> {
         let version: u8 = features.into();
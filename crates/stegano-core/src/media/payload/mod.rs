@@ -2,7 +2,10 @@ mod codec;
 mod crypted;
 mod factory;
 pub mod legacy;
+mod padding;
 
 pub use codec::*;
 pub use crypted::*;
 pub use factory::*;
+pub use padding::PaddingBuckets;
+pub(crate) use padding::{pad, unpad};
@@ -0,0 +1,102 @@
+use zeroize::Zeroizing;
+
+use super::{length_len, read_length, write_length};
+
+use crate::result::Result;
+use crate::SteganoError;
+
+/// The size buckets a payload gets padded up to, so that the size of the modified region
+/// of a carrier does not reveal the exact size of the secret.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum PaddingBuckets {
+    /// pads up to the next power of two
+    PowersOfTwo,
+    /// pads up to the next multiple of the given amount of bytes
+    MultipleOf(usize),
+}
+
+impl PaddingBuckets {
+    /// the size of the bucket that fits `len` bytes
+    pub fn bucket_for(&self, len: usize) -> usize {
+        match self {
+            PaddingBuckets::PowersOfTwo => len.next_power_of_two(),
+            PaddingBuckets::MultipleOf(0) => len,
+            PaddingBuckets::MultipleOf(size) => len.div_ceil(*size) * size,
+        }
+    }
+
+    /// the size of `len` bytes of data once padded, including the length in front of it
+    pub fn padded_len(&self, len: usize) -> usize {
+        self.bucket_for(len + length_len(len))
+    }
+}
+
+/// prefixes `data` with its length and fills it up with random noise to the bucket size
+pub(crate) fn pad(data: &[u8], buckets: PaddingBuckets) -> Result<Zeroizing<Vec<u8>>> {
+    let padded_len = buckets.padded_len(data.len());
+    let mut buf = Zeroizing::new(Vec::with_capacity(padded_len));
+    write_length(&mut *buf, data.len())?;
+    buf.extend_from_slice(data);
+
+    let mut noise = vec![0u8; padded_len - buf.len()];
+    stegano_seasmoke::fill_noise(&mut noise).map_err(SteganoError::NoiseError)?;
+    buf.extend_from_slice(&noise);
+
+    Ok(buf)
+}
+
+/// strips the random noise `pad` added
+pub(crate) fn unpad(mut data: Vec<u8>) -> Result<Vec<u8>> {
+    let mut r = &data[..];
+    let len = read_length(&mut r)?;
+    let header_len = data.len() - r.len();
+    if len > r.len() {
+        return Err(SteganoError::InvalidPadding);
+    }
+    data.drain(..header_len);
+    data.truncate(len);
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_pick_the_bucket() {
+        assert_eq!(PaddingBuckets::PowersOfTwo.bucket_for(1000), 1024);
+        assert_eq!(PaddingBuckets::PowersOfTwo.bucket_for(1024), 1024);
+        assert_eq!(PaddingBuckets::MultipleOf(4096).bucket_for(4097), 8192);
+        assert_eq!(PaddingBuckets::MultipleOf(0).bucket_for(42), 42);
+    }
+
+    #[test]
+    fn should_count_the_length_in_front_of_the_data() {
+        assert_eq!(PaddingBuckets::MultipleOf(64).padded_len(60), 64);
+        assert_eq!(PaddingBuckets::MultipleOf(64).padded_len(61), 128);
+        assert_eq!(
+            PaddingBuckets::MultipleOf(0).padded_len(u32::MAX as usize),
+            u32::MAX as usize + 12
+        );
+    }
+
+    #[test]
+    fn should_pad_and_unpad() {
+        let data = b"Hello World".to_vec();
+        let padded = pad(&data, PaddingBuckets::MultipleOf(64)).unwrap();
+        assert_eq!(padded.len(), 64);
+
+        assert_eq!(unpad(padded.to_vec()).unwrap(), data);
+    }
+
+    #[test]
+    fn should_reject_a_broken_length() {
+        let mut padded = pad(b"Hello World", PaddingBuckets::PowersOfTwo)
+            .unwrap()
+            .to_vec();
+        padded[0] = 0xff;
+
+        assert!(matches!(unpad(padded), Err(SteganoError::InvalidPadding)));
+    }
+}
@@ -0,0 +1,220 @@
+use subtle::ConstantTimeEq;
+
+use crate::error::SteganoError;
+use crate::media::image::CodecOptions;
+use crate::result::Result;
+
+use super::Media;
+
+/// prefix of a serialized progress token, it carries the version of the token format
+const TOKEN_PREFIX: &str = "stegano-hide-v2";
+
+/// prefix of the tokens before the cursor was kept, they are resumed by going over the hidden part
+const TOKEN_PREFIX_V1: &str = "stegano-hide-v1";
+
+/// How far a payload has been hidden in a carrier, see `Media::hide_step`.
+///
+/// It can be serialized into a token, so that an interrupted hide can be resumed later
+/// from a snapshot of the carrier and the very same encoded payload.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct HideProgress {
+    offset: u64,
+    len: u64,
+    checksum: u32,
+    /// where in the carrier the next step continues, see `Media::hide_data_from`
+    cursor: Option<u64>,
+}
+
+impl HideProgress {
+    /// nothing of `payload` is hidden yet
+    pub fn new(payload: &[u8]) -> Self {
+        Self {
+            offset: 0,
+            len: payload.len() as u64,
+            checksum: crc32fast::hash(payload),
+            cursor: None,
+        }
+    }
+
+    /// the amount of bytes that are hidden already
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// the size of the whole payload
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.offset >= self.len
+    }
+
+    /// `true` if the progress was made for `payload`, the checksum is compared in constant time
+    fn belongs_to(&self, payload: &[u8]) -> bool {
+        let checksum = crc32fast::hash(payload);
+        self.len == payload.len() as u64 && bool::from(self.checksum.ct_eq(&checksum))
+    }
+
+    /// serializes the progress, e.g. to keep it in the local storage of a browser
+    pub fn to_token(&self) -> String {
+        match self.cursor {
+            Some(cursor) => format!(
+                "{TOKEN_PREFIX}:{}:{}:{:08x}:{cursor}",
+                self.offset, self.len, self.checksum
+            ),
+            None => format!(
+                "{TOKEN_PREFIX_V1}:{}:{}:{:08x}",
+                self.offset, self.len, self.checksum
+            ),
+        }
+    }
+
+    /// parses a token created by `to_token`
+    pub fn from_token(token: &str) -> Result<Self> {
+        let mut parts = token.split(':');
+        let with_cursor = match parts.next() {
+            Some(TOKEN_PREFIX) => true,
+            Some(TOKEN_PREFIX_V1) => false,
+            _ => return Err(SteganoError::InvalidProgressToken),
+        };
+        let mut next = || parts.next().ok_or(SteganoError::InvalidProgressToken);
+        let offset = next()?.parse();
+        let len = next()?.parse();
+        let checksum = u32::from_str_radix(next()?, 16);
+        let cursor = if with_cursor {
+            Some(
+                next()?
+                    .parse()
+                    .map_err(|_| SteganoError::InvalidProgressToken)?,
+            )
+        } else {
+            None
+        };
+
+        match (offset, len, checksum, parts.next()) {
+            (Ok(offset), Ok(len), Ok(checksum), None) if offset <= len => Ok(Self {
+                offset,
+                len,
+                checksum,
+                cursor,
+            }),
+            _ => Err(SteganoError::InvalidProgressToken),
+        }
+    }
+}
+
+impl Media {
+    /// Hides the next `max_bytes` of `payload` and advances `progress`, returns `true` once all is hidden.
+    ///
+    /// The carrier must be the one the previous steps were hidden in, e.g. restored from a lossless snapshot,
+    /// and `payload` must be the very same encoded payload, otherwise `ProgressMismatch` is returned.
+    pub fn hide_step(
+        &mut self,
+        payload: &[u8],
+        progress: &mut HideProgress,
+        max_bytes: usize,
+        opts: &CodecOptions,
+    ) -> Result<bool> {
+        if !progress.belongs_to(payload) {
+            return Err(SteganoError::ProgressMismatch);
+        }
+        if progress.offset == 0 && opts.sanitize_lsb_plane {
+            self.sanitize_lsb_plane(opts)?;
+        }
+
+        let offset = progress.offset as usize;
+        let end = payload.len().min(offset.saturating_add(max_bytes.max(1)));
+        let cursor = self.hide_data_from(&payload[offset..end], offset, progress.cursor, opts)?;
+        progress.offset = end as u64;
+        progress.cursor = Some(cursor);
+
+        Ok(progress.is_done())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_round_trip_the_token() {
+        let mut progress = HideProgress::new(b"Hello World");
+        progress.offset = 4;
+
+        let token = progress.to_token();
+        assert_eq!(HideProgress::from_token(&token).unwrap(), progress);
+
+        assert!(HideProgress::from_token("stegano-hide-v1:12:11:00000000").is_err());
+        assert!(HideProgress::from_token("stegano-hide-v2:0:11:00000000").is_err());
+        assert!(HideProgress::from_token("stegano-hide-v1:0:11").is_err());
+    }
+
+    #[test]
+    fn should_hide_in_steps_like_at_once() {
+        let payload: Vec<u8> = (0..=255).collect();
+        let carrier = image::RgbaImage::from_pixel(64, 64, image::Rgba([10, 20, 30, 255]));
+        let opts = CodecOptions::default();
+
+        let mut at_once = Media::from_image(carrier.clone());
+        at_once.hide_data(payload.clone(), &opts).unwrap();
+
+        let mut in_steps = Media::from_image(carrier);
+        let mut progress = HideProgress::new(&payload);
+        while !in_steps
+            .hide_step(&payload, &mut progress, 100, &opts)
+            .unwrap()
+        {
+            progress = HideProgress::from_token(&progress.to_token()).unwrap();
+        }
+
+        match (at_once, in_steps) {
+            (Media::Image(a), Media::Image(b)) => assert_eq!(a, b),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn should_resume_a_token_without_cursor() {
+        let payload: Vec<u8> = (0..=255).collect();
+        let carrier = image::RgbaImage::from_pixel(64, 64, image::Rgba([10, 20, 30, 255]));
+        let opts = CodecOptions {
+            color_channel_step_increment: 2,
+            ..CodecOptions::default()
+        };
+
+        let mut at_once = Media::from_image(carrier.clone());
+        at_once.hide_data(payload.clone(), &opts).unwrap();
+
+        let mut in_steps = Media::from_image(carrier);
+        let mut progress = HideProgress::new(&payload);
+        in_steps
+            .hide_step(&payload, &mut progress, 100, &opts)
+            .unwrap();
+        let token = format!("stegano-hide-v1:100:256:{:08x}", crc32fast::hash(&payload));
+        assert!(progress.to_token().starts_with("stegano-hide-v2:100:256:"));
+        let mut progress = HideProgress::from_token(&token).unwrap();
+        while !in_steps
+            .hide_step(&payload, &mut progress, 100, &opts)
+            .unwrap()
+        {}
+
+        match (at_once, in_steps) {
+            (Media::Image(a), Media::Image(b)) => assert_eq!(a, b),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn should_refuse_a_different_payload() {
+        let mut media = Media::from_image(image::RgbaImage::new(32, 32));
+        let mut progress = HideProgress::new(b"Hello World");
+
+        let r = media.hide_step(b"Hello Moon!", &mut progress, 4, &CodecOptions::default());
+        assert!(matches!(r, Err(SteganoError::ProgressMismatch)));
+    }
+}
@@ -1,4 +1,5 @@
 use std::fs::File;
+use std::io::Write;
 use std::path::Path;
 
 pub use hound::{WavReader, WavSpec, WavWriter};
@@ -6,7 +7,7 @@ pub use image::RgbaImage;
 use log::error;
 
 use crate::error::SteganoError;
-use crate::media::image::CodecOptions;
+use crate::media::image::{redundancy, CodecOptions};
 use crate::result::Result;
 
 use super::Persist;
@@ -54,19 +55,55 @@ impl Media {
     }
 
     pub fn hide_data(&mut self, msg_data: Vec<u8>, opts: &CodecOptions) -> Result<&mut Self> {
-        match self {
+        if opts.sanitize_lsb_plane {
+            self.sanitize_lsb_plane(opts)?;
+        }
+
+        self.hide_data_at(&msg_data, 0, opts)
+    }
+
+    /// Hides `chunk` as the part of a payload that starts at byte `offset`,
+    /// this way a payload can be hidden in several steps.
+    pub fn hide_data_at(
+        &mut self,
+        chunk: &[u8],
+        offset: usize,
+        opts: &CodecOptions,
+    ) -> Result<&mut Self> {
+        self.hide_data_from(chunk, offset, None, opts)?;
+
+        Ok(self)
+    }
+
+    /// Same as `hide_data_at`, but with the `cursor` returned for the previous chunk it continues
+    /// right there, instead of going over the part of the carrier that holds the chunks before.
+    /// Returns the cursor for the next chunk
+    pub(crate) fn hide_data_from(
+        &mut self,
+        chunk: &[u8],
+        offset: usize,
+        cursor: Option<u64>,
+        opts: &CodecOptions,
+    ) -> Result<u64> {
+        let cursor = match self {
             Media::Image(i) => {
                 let (width, height) = i.dimensions();
-                let mut encoder = super::image::LsbCodec::encoder(i, opts);
+                let chunk = &redundancy::repeat(chunk, opts.copies())[..];
+                let offset = offset * opts.copies();
+                let mut encoder =
+                    super::image::LsbCodec::encoder_from(i, opts, cursor.unwrap_or(0) as usize);
+                if cursor.is_none() && offset > 0 {
+                    encoder.carrier.nth((offset << 3) - 1);
+                }
 
-                encoder.write_all(msg_data.as_ref()).map_err(|e| {
+                encoder.write_all(chunk).map_err(|e| {
                     error!("Error encoding image: {e}, kind {}", e.kind());
 
                     match e.kind() {
                         std::io::ErrorKind::WriteZero => {
                             let capacity = width * height;
                             // let ratio = width as f64 / height as f64;
-                            let estimated_needed_dimensions = msg_data.len() * 8 / 3;
+                            let estimated_needed_dimensions = (offset + chunk.len()) * 8 / 3;
                             let scale = estimated_needed_dimensions as f64 / capacity as f64;
                             let w = scale * width as f64;
                             let h = scale * height as f64;
@@ -80,14 +117,48 @@ impl Media {
                         }
                         _ => SteganoError::ImageEncodingError,
                     }
-                })?
+                })?;
+                encoder.carrier.cursor() as u64
             }
             Media::Audio((_spec, samples)) => {
-                let mut encoder = super::audio::LsbCodec::encoder(samples);
+                // one bit per sample, so the cursor is the index of the next sample
+                let start = cursor.map_or(offset << 3, |cursor| cursor as usize);
+                let samples_len = samples.len();
+                let mut encoder =
+                    super::audio::LsbCodec::encoder_at(&mut samples[start.min(samples_len)..], 0);
 
                 encoder
-                    .write_all(msg_data.as_ref())
-                    .map_err(|_e| SteganoError::AudioEncodingError)?
+                    .write_all(chunk)
+                    .map_err(|_e| SteganoError::AudioEncodingError)?;
+                (start + (chunk.len() << 3)) as u64
+            }
+        };
+
+        Ok(cursor)
+    }
+
+    /// Overwrites the least significant bit of every color channel (and alpha, if not skipped)
+    /// or audio sample with random noise, this destroys anything that was hidden before.
+    pub fn sanitize_lsb_plane(&mut self, opts: &CodecOptions) -> Result<&mut Self> {
+        match self {
+            Media::Image(i) => {
+                let skip_alpha = opts.alpha_channel_skipped();
+                let len = if skip_alpha { i.len() / 4 * 3 } else { i.len() };
+                let noise = noise_bits(len)?;
+                let channels = i
+                    .iter_mut()
+                    .enumerate()
+                    .filter(|(idx, _)| !skip_alpha || idx % 4 != 3)
+                    .map(|(_, c)| c);
+                for (channel, bit) in channels.zip(noise) {
+                    *channel = (*channel & !1) | bit;
+                }
+            }
+            Media::Audio((_spec, samples)) => {
+                let noise = noise_bits(samples.len())?;
+                for (sample, bit) in samples.iter_mut().zip(noise) {
+                    *sample = (*sample & !1) | bit as i16;
+                }
             }
         }
 
@@ -95,6 +166,14 @@ impl Media {
     }
 }
 
+/// `len` random bits, one per item
+pub(crate) fn noise_bits(len: usize) -> Result<impl Iterator<Item = u8>> {
+    let mut noise = vec![0u8; len.div_ceil(8)];
+    stegano_seasmoke::fill_noise(&mut noise).map_err(SteganoError::NoiseError)?;
+
+    Ok((0..len).map(move |idx| (noise[idx / 8] >> (idx % 8)) & 1))
+}
+
 impl Persist for Media {
     fn save_as(&mut self, file: &Path) -> Result<()> {
         let f = File::create(file).map_err(|e| {
@@ -1,32 +1,278 @@
-use crate::media::payload::{PayloadCodec, PayloadCodecFactory, PayloadCodecFeatures};
+use crate::checksums::{BlockChecksums, CHECKSUMS_EXTRA_FIELD_ID};
+use crate::digests::{sha256, HashingReader};
+use crate::manifest::{Manifest, MANIFEST_ENTRY};
+use crate::media::payload::{
+    length_len, pad, unpad, PaddingBuckets, PayloadCodec, PayloadCodecFactory, PayloadCodecFeatures,
+};
+use crate::metadata::{Metadata, METADATA_ENTRY};
 use crate::result::Result;
+use crate::timestamp::{TimestampToken, TIMESTAMP_ENTRY};
 use crate::SteganoError;
 
 use byteorder::ReadBytesExt;
+use ed25519_dalek::SigningKey;
 use image::EncodableLayout;
+use std::collections::HashMap;
 use std::default::Default;
 use std::fs::File;
-use std::io::{Cursor, Read};
+use std::io::{Cursor, Read, Write};
 use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::{Zeroize, Zeroizing};
+use zip::write::FullFileOptions;
 use zip::{ZipArchive, ZipWriter};
 
-#[derive(Debug, PartialEq, Eq)]
+/// the file type bits of a unix mode, and the types of regular files and symlinks
+const S_IFMT: u32 = 0o170000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+/// stegano keeps its own records below this folder, unknown ones from newer versions are left out
+const RESERVED_DIR: &str = ".stegano/";
+
+/// What to do with symlinks and special files (devices, fifos) found inside of a container.
+/// stegano never creates such entries, but foreign or crafted containers can contain them.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum SpecialEntryPolicy {
+    /// leave them out
+    #[default]
+    Skip,
+    /// unveil them like regular files, a symlink then contains its target path
+    AsRegularFile,
+    /// unveil symlinks as empty files with their target recorded as link target,
+    /// special files are left out
+    LinkTargetAsMetadata,
+}
+
+/// the id of the zip extra field that holds the comment of a file, it reads `sc` for stegano comment
+const COMMENT_EXTRA_FIELD_ID: u16 = 0x6373;
+
+/// every zip extra field starts with its id and its length, 2 bytes each
+const EXTRA_FIELD_HEADER_LEN: usize = 4;
+
+/// deflate keeps data it cannot compress in stored blocks with a header of 5 bytes each,
+/// a block ends once the buffer of deflate is full, and one more block holds the rest
+const STORED_BLOCK_LEN: usize = 16_383;
+const STORED_BLOCK_HEADER_LEN: usize = 5;
+
+#[derive(Debug)]
 pub struct Message {
     pub files: Vec<(String, Vec<u8>)>,
     pub text: Option<String>,
+    /// optional comments of the files, by file name
+    pub comments: HashMap<String, String>,
+    /// if set, the documents get padded up to the bucket size before they are encoded
+    pub padding: Option<PaddingBuckets>,
+    /// optional provenance record, hidden along with the files
+    pub metadata: Option<Metadata>,
+    /// the comments of unveiled files, by the index of the file in `files`.
+    /// Unlike `comments` it tells files of the same name apart, it is not part of the equality of messages
+    pub entry_comments: HashMap<usize, String>,
+    /// the targets of unveiled symlinks, by the index of the file in `files`
+    pub link_targets: HashMap<usize, String>,
+    /// what happened to entries that were not unveiled as they are
+    pub warnings: Vec<String>,
+    /// the SHA-256 of each unveiled file, in the same order as `files`,
+    /// it is taken while reading and not part of the equality of messages
+    pub digests: Vec<[u8; 32]>,
+    /// if set, a manifest of the files is signed with this key and hidden along with them
+    pub signing_key: Option<SigningKey>,
+    /// the signed manifest that was hidden along with the files
+    pub manifest: Option<Manifest>,
+    /// the RFC 3161 timestamp token that is hidden along with the files
+    pub timestamp: Option<TimestampToken>,
+}
+
+/// One entry of the table of contents, it describes a hidden file without its content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    /// the path inside of the container, in NFC
+    pub name: String,
+    /// uncompressed size in bytes
+    pub size: u64,
+    pub comment: Option<String>,
+}
+
+impl FileEntry {
+    /// the path as it gets unveiled on disk, see `UnveiledFile::display_name`
+    pub fn display_name(&self) -> String {
+        display_path(&self.name)
+    }
+}
+
+/// What is hidden inside of a message, read without extracting any file
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TableOfContents {
+    pub files: Vec<FileEntry>,
+    /// the metadata record, if one was hidden
+    pub metadata: Option<Metadata>,
+    /// the RFC 3161 timestamp token, if one was hidden
+    pub timestamp: Option<TimestampToken>,
+}
+
+/// A hidden file with only its first bytes, enough for a thumbnail or a text snippet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePreview {
+    pub entry: FileEntry,
+    /// the first bytes of the file, at most as many as asked for
+    pub head: Vec<u8>,
+}
+
+impl Drop for FilePreview {
+    fn drop(&mut self) {
+        self.head.zeroize();
+    }
+}
+
+impl TableOfContents {
+    /// Reads the table of contents based on the message serialization format,
+    /// the payload gets decrypted but the files inside stay compressed
+    pub fn from_raw_data(
+        dec: &mut dyn Read,
+        codec_factory: &dyn PayloadCodecFactory,
+    ) -> Result<Self> {
+        Self::from_raw_data_with_policy(dec, codec_factory, SpecialEntryPolicy::default())
+    }
+
+    /// Same as `from_raw_data`, but symlinks and special files are listed according to `policy`,
+    /// the same way `Message::from_raw_data_with_policy` would unveil them
+    pub fn from_raw_data_with_policy(
+        dec: &mut dyn Read,
+        codec_factory: &dyn PayloadCodecFactory,
+        policy: SpecialEntryPolicy,
+    ) -> Result<Self> {
+        Ok(Self::read(dec, codec_factory, None, policy)?.0)
+    }
+
+    /// Same as `from_raw_data_with_policy`, but with the first `max_head` bytes of each file,
+    /// only those get decompressed
+    pub fn previews_from_raw_data(
+        dec: &mut dyn Read,
+        codec_factory: &dyn PayloadCodecFactory,
+        max_head: usize,
+        policy: SpecialEntryPolicy,
+    ) -> Result<Vec<FilePreview>> {
+        let (toc, heads) = Self::read(dec, codec_factory, Some(max_head), policy)?;
+
+        Ok(toc
+            .files
+            .into_iter()
+            .zip(heads)
+            .map(|(entry, head)| FilePreview { entry, head })
+            .collect())
+    }
+
+    /// reads the table of contents, and the heads of the files if `max_head` is given
+    fn read(
+        dec: &mut dyn Read,
+        codec_factory: &dyn PayloadCodecFactory,
+        max_head: Option<usize>,
+        policy: SpecialEntryPolicy,
+    ) -> Result<(Self, Vec<Vec<u8>>)> {
+        let version = dec.read_u8()?;
+        let codec: Box<dyn PayloadCodec> =
+            codec_factory.create_codec(PayloadCodecFeatures::MixedFeatures(version))?;
+        let content = Zeroizing::new(decode_content(&*codec, dec)?);
+        let head_of = |data: &[u8]| data[..data.len().min(max_head.unwrap_or(0))].to_vec();
+
+        if codec.has_feature(PayloadCodecFeatures::TextOnly) {
+            let toc = Self {
+                files: vec![FileEntry {
+                    name: "secret-message.txt".to_owned(),
+                    size: content.len() as u64,
+                    comment: None,
+                }],
+                ..Self::default()
+            };
+            return Ok((toc, vec![head_of(&content)]));
+        }
+        if !codec.has_feature(PayloadCodecFeatures::TextAndDocuments) {
+            return Err(SteganoError::UnsupportedMessageFormat(version));
+        }
+
+        let mut toc = Self::default();
+        let mut heads = Vec::new();
+        let mut zip = ZipArchive::new(Cursor::new(&content[..]))?;
+        for i in 0..zip.len() {
+            let file = zip.by_index_raw(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            if file.name() == METADATA_ENTRY {
+                drop(file);
+                let file = zip.by_index(i)?;
+                toc.metadata = Some(Metadata::read_from(&mut std::io::BufReader::new(file))?);
+                continue;
+            }
+            if file.name() == MANIFEST_ENTRY {
+                continue;
+            }
+            if file.name() == TIMESTAMP_ENTRY {
+                drop(file);
+                // stegano checks the token before hiding it, a broken one is left out
+                toc.timestamp = TimestampToken::from_der(read_entry(zip.by_index(i)?)?).ok();
+                continue;
+            }
+            let name = checked_entry_path(file.name_raw())?;
+            if name.starts_with(RESERVED_DIR) {
+                continue;
+            }
+            let file_type = file.unix_mode().map_or(0, |mode| mode & S_IFMT);
+            let as_empty_file = match policy {
+                _ if file_type == 0 || file_type == S_IFREG => false,
+                SpecialEntryPolicy::AsRegularFile => false,
+                SpecialEntryPolicy::LinkTargetAsMetadata if file_type == S_IFLNK => true,
+                _ => continue,
+            };
+
+            toc.files.push(FileEntry {
+                name,
+                size: if as_empty_file { 0 } else { file.size() },
+                comment: file.extra_data().and_then(read_comment),
+            });
+            drop(file);
+            let mut head = Vec::new();
+            if let Some(max_head) = max_head.filter(|_| !as_empty_file) {
+                zip.by_index(i)?
+                    .take(max_head as u64)
+                    .read_to_end(&mut head)?;
+            }
+            heads.push(head);
+        }
+        if !zip.comment().is_empty() {
+            toc.files.push(FileEntry {
+                name: "secret-message.txt".to_owned(),
+                size: zip.comment().len() as u64,
+                comment: None,
+            });
+            heads.push(head_of(zip.comment()));
+        }
+
+        Ok((toc, heads))
+    }
 }
 
 impl Message {
     /// Creates a new message with the content based on the message serialization format.
+    #[cfg(test)]
     pub fn from_raw_data(
         dec: &mut dyn Read,
         codec_factory: &dyn PayloadCodecFactory,
+    ) -> Result<Self> {
+        Self::from_raw_data_with_policy(dec, codec_factory, SpecialEntryPolicy::default())
+    }
+
+    /// Same as `from_raw_data`, but symlinks and special files are treated according to `policy`
+    pub fn from_raw_data_with_policy(
+        dec: &mut dyn Read,
+        codec_factory: &dyn PayloadCodecFactory,
+        policy: SpecialEntryPolicy,
     ) -> Result<Self> {
         let version = dec.read_u8()?;
         let codec: Box<dyn PayloadCodec> =
             codec_factory.create_codec(PayloadCodecFeatures::MixedFeatures(version))?;
 
-        let message = decode_message(&*codec, dec)?;
+        let message = decode_message(&*codec, dec, policy)?;
 
         Ok(message)
     }
@@ -38,6 +284,16 @@ impl Message {
         Ok(Self {
             files: Default::default(),
             text: Some(text),
+            comments: HashMap::new(),
+            padding: None,
+            metadata: None,
+            entry_comments: HashMap::new(),
+            link_targets: HashMap::new(),
+            digests: Vec::new(),
+            signing_key: None,
+            manifest: None,
+            timestamp: None,
+            warnings: Vec::new(),
         })
     }
 
@@ -63,6 +319,19 @@ impl Message {
         Ok(self)
     }
 
+    /// Adds a file under a path inside of the container, like `docs/2024/report.pdf`.
+    /// Both `/` and `\\` separate directories, `.` and `..` are dropped.
+    pub fn add_file_data_at(&mut self, path: &str, data: Vec<u8>) -> Result<&mut Self> {
+        let path = normalize_path(path);
+        if path.is_empty() {
+            return Err(SteganoError::InvalidFileName);
+        }
+
+        self.files.push((path, data));
+
+        Ok(self)
+    }
+
     pub fn add_file_data<P: AsRef<Path> + ?Sized>(
         &mut self,
         file: &P,
@@ -75,7 +344,7 @@ impl Message {
             .to_str()
             .ok_or(SteganoError::InvalidFileName)?;
 
-        self.files.push((file.to_owned(), data));
+        self.files.push((file.nfc().collect(), data));
 
         Ok(self)
     }
@@ -83,6 +352,10 @@ impl Message {
     pub fn features(&self) -> PayloadCodecFeatures {
         if self.files.is_empty() {
             PayloadCodecFeatures::TextOnly
+        } else if self.padding.is_some() {
+            PayloadCodecFeatures::TextAndDocuments
+                .add_feature(PayloadCodecFeatures::LengthHeader)
+                .add_feature(PayloadCodecFeatures::Padded)
         } else {
             PayloadCodecFeatures::TextAndDocuments
         }
@@ -96,12 +369,22 @@ impl Message {
         Message {
             files: Vec::new(),
             text: None,
+            comments: HashMap::new(),
+            padding: None,
+            metadata: None,
+            entry_comments: HashMap::new(),
+            link_targets: HashMap::new(),
+            warnings: Vec::new(),
+            digests: Vec::new(),
+            signing_key: None,
+            manifest: None,
+            timestamp: None,
         }
     }
 
-    fn from_documents_data(buf: Vec<u8>) -> Result<Message> {
+    fn from_documents_data(buf: Vec<u8>, policy: SpecialEntryPolicy) -> Result<Message> {
         // todo: thinking about refactoring that, so that the this whole logic is actually ankered in the codec, or at least in the codec factory
-        let mut buf = Cursor::new(buf);
+        let mut buf = Cursor::new(Zeroizing::new(buf));
         let mut m = Message::new();
 
         let mut zip = ZipArchive::new(&mut buf)?;
@@ -111,16 +394,80 @@ impl Message {
 
         for i in 0..zip.len() {
             let mut file = zip.by_index(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            if file.name() == METADATA_ENTRY {
+                m.metadata = Some(Metadata::read_from(&mut std::io::BufReader::new(file))?);
+                continue;
+            }
+            if file.name() == MANIFEST_ENTRY {
+                m.manifest = Manifest::read_from(&mut std::io::BufReader::new(file))?;
+                if m.manifest.is_none() {
+                    m.warnings.push("The manifest is malformed".to_string());
+                }
+                continue;
+            }
+            if file.name() == TIMESTAMP_ENTRY {
+                match TimestampToken::from_der(read_entry(file)?) {
+                    Ok(token) => m.timestamp = Some(token),
+                    Err(e) => m.warnings.push(e.to_string()),
+                }
+                continue;
+            }
+            let name = checked_entry_path(file.name_raw())?;
+            if name.starts_with(RESERVED_DIR) {
+                m.warnings
+                    .push(format!("Skipped the unknown stegano record `{name}`"));
+                continue;
+            }
             let mut writer = Vec::new();
-            file.read_to_end(&mut writer)?;
-
-            m.files.push((
-                file.mangled_name()
-                    .to_str()
-                    .unwrap_or("--no-file-name--")
-                    .to_string(),
-                writer,
-            ));
+            let checksums = file
+                .extra_data()
+                .and_then(|extra| find_extra_field(extra, CHECKSUMS_EXTRA_FIELD_ID))
+                .and_then(BlockChecksums::from_bytes);
+            let mut reader = HashingReader::new(&mut file);
+            match checksums {
+                Some(checksums) => checksums.read_verified(&mut reader, &name, &mut writer)?,
+                None => {
+                    reader.read_to_end(&mut writer)?;
+                }
+            }
+            let mut digest = reader.finish();
+
+            let file_type = file.unix_mode().map_or(0, |mode| mode & S_IFMT);
+            if file_type != 0 && file_type != S_IFREG {
+                let kind = if file_type == S_IFLNK {
+                    "symlink"
+                } else {
+                    "special file"
+                };
+                match policy {
+                    SpecialEntryPolicy::AsRegularFile => {
+                        m.warnings
+                            .push(format!("Unveiled the {kind} `{name}` as a regular file"));
+                    }
+                    SpecialEntryPolicy::LinkTargetAsMetadata if file_type == S_IFLNK => {
+                        let target = String::from_utf8_lossy(&writer).into_owned();
+                        m.warnings.push(format!(
+                            "Unveiled the symlink `{name}` as an empty file, it points to `{target}`"
+                        ));
+                        m.link_targets.insert(m.files.len(), target);
+                        writer.clear();
+                        digest = sha256(&writer);
+                    }
+                    _ => {
+                        m.warnings.push(format!("Skipped the {kind} `{name}`"));
+                        continue;
+                    }
+                }
+            }
+            if let Some(comment) = file.extra_data().and_then(read_comment) {
+                m.entry_comments.insert(m.files.len(), comment.clone());
+                m.comments.insert(name.clone(), comment);
+            }
+            m.files.push((name, writer));
+            m.digests.push(digest);
         }
 
         Ok(m)
@@ -130,8 +477,73 @@ impl Message {
         let codec = codec_factory.create_codec(self.features())?;
         encode_message(&*codec, self)
     }
+
+    /// An upper bound of the length of `to_raw_data`, once each file grows by the bytes of `growths`, in the order
+    /// of `files`, that cannot be compressed. Nothing gets encrypted, the encryption of `codec_factory` is counted instead,
+    /// so that it takes no longer than zipping the message as it is
+    pub fn raw_len_with(
+        &self,
+        growths: &[usize],
+        codec_factory: &dyn PayloadCodecFactory,
+    ) -> Result<usize> {
+        let zip_len = zip_message(self)?.len()
+            + growths
+                .iter()
+                .map(|&growth| {
+                    // the block checksums are in the local and in the central header
+                    growth
+                        + (growth / STORED_BLOCK_LEN + 1) * STORED_BLOCK_HEADER_LEN
+                        + 2 * (BlockChecksums::bytes_len_for(growth)
+                            - BlockChecksums::bytes_len_for(0))
+                })
+                .sum::<usize>();
+        let content_len = match self.padding {
+            Some(buckets) => buckets.padded_len(zip_len),
+            None => zip_len,
+        } + codec_factory.encryption_overhead();
+
+        // the features byte, the length and the terminator around the content
+        Ok(1 + length_len(content_len) + content_len + 1)
+    }
 }
 
+impl Drop for Message {
+    /// the message holds the plain secrets, so they should not linger in memory
+    fn drop(&mut self) {
+        for (name, data) in self.files.iter_mut() {
+            name.zeroize();
+            data.zeroize();
+        }
+        self.text.zeroize();
+        for comment in self
+            .comments
+            .values_mut()
+            .chain(self.entry_comments.values_mut())
+        {
+            comment.zeroize();
+        }
+    }
+}
+
+impl PartialEq for Message {
+    /// the digests only exist on unveiled messages, so a hidden and an unveiled message are
+    /// equal if they hold the same content
+    fn eq(&self, other: &Self) -> bool {
+        self.files == other.files
+            && self.text == other.text
+            && self.comments == other.comments
+            && self.padding == other.padding
+            && self.metadata == other.metadata
+            && self.link_targets == other.link_targets
+            && self.warnings == other.warnings
+            && self.signing_key == other.signing_key
+            && self.manifest == other.manifest
+            && self.timestamp == other.timestamp
+    }
+}
+
+impl Eq for Message {}
+
 // impl TryFrom<&mut Vec<u8>> for Message {
 //     type Error = SteganoError;
 
@@ -151,35 +563,209 @@ impl Message {
 // }
 
 pub(crate) fn encode_message(encoder: &dyn PayloadCodec, msg: &Message) -> Result<Vec<u8>> {
-    let mut buf = Vec::new();
+    let buf = zip_message(msg)?;
+
+    if let Some(buckets) = msg.padding {
+        let buf = pad(&buf, buckets)?;
+        return encoder.encode(&mut Cursor::new(&buf[..]));
+    }
+
+    encoder.encode(&mut Cursor::new(&buf[..]))
+}
+
+/// the files and records of the message in a zip, before padding and encryption
+fn zip_message(msg: &Message) -> Result<Zeroizing<Vec<u8>>> {
+    for (name, data) in msg.files.iter() {
+        check_extra_fields_len(name, msg.comments.get(name), data.len())?;
+    }
+    let mut buf = Zeroizing::new(Vec::new());
 
     {
-        let w = Cursor::new(&mut buf);
+        let w = Cursor::new(&mut *buf);
         let mut zip = ZipWriter::new(w);
 
-        let options = zip::write::SimpleFileOptions::default()
-            .compression_method(zip::CompressionMethod::Deflated);
+        let options =
+            FullFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
 
         for (name, buf) in (msg.files).iter().map(|(name, buf)| (name, buf)) {
+            // entries of 4 GB and more need the zip64 extensions
+            let mut options = options
+                .clone()
+                .large_file(buf.len() as u64 >= u32::MAX as u64);
+            if let Some(comment) = msg.comments.get(name) {
+                options.add_extra_data(COMMENT_EXTRA_FIELD_ID, comment.as_bytes(), false)?;
+            }
+            options.add_extra_data(
+                CHECKSUMS_EXTRA_FIELD_ID,
+                BlockChecksums::for_data(buf).to_bytes(),
+                false,
+            )?;
             zip.start_file(name, options)?;
 
             let mut r = Cursor::new(buf);
             std::io::copy(&mut r, &mut zip)?;
         }
 
+        if let Some(metadata) = msg.metadata.as_ref() {
+            zip.start_file(METADATA_ENTRY, options.clone())?;
+            metadata.write_to(&mut zip)?;
+        }
+
+        if let Some(key) = msg.signing_key.as_ref() {
+            // listed under the names they are unveiled with
+            let names: Vec<_> = msg.files.iter().map(|(name, _)| entry_path(name)).collect();
+            let files = names
+                .iter()
+                .zip(msg.files.iter())
+                .map(|(name, (_, data))| (name.as_str(), data.as_slice()));
+            zip.start_file(MANIFEST_ENTRY, options.clone())?;
+            Manifest::sign(files, key).write_to(&mut zip)?;
+        }
+
+        if let Some(token) = msg.timestamp.as_ref() {
+            zip.start_file(TIMESTAMP_ENTRY, options.clone())?;
+            zip.write_all(token.as_der())?;
+        }
+
         zip.finish()?;
     }
 
-    encoder.encode(&mut Cursor::new(buf))
+    Ok(buf)
+}
+
+/// the relative path in NFC with `/` as separator and without any `.` or `..`,
+/// so that it cannot escape the folder it gets unveiled into.
+/// NFC makes names that look the same compare the same, no matter how the platform that hid them composes accents
+fn normalize_path(name: &str) -> String {
+    name.nfc()
+        .collect::<String>()
+        .split(['/', '\\'])
+        .filter(|c| !c.is_empty() && *c != "." && *c != "..")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// characters that are not allowed in file names on at least one platform
+const ILLEGAL_CHARS: [char; 8] = ['<', '>', ':', '"', '|', '?', '*', '\\'];
+
+/// names that Windows reserves for devices, with any extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// The path as it can be written on any platform: characters that are illegal somewhere become `_`,
+/// trailing dots and spaces are dropped and reserved device names get a leading `_`.
+/// The same rules apply everywhere, so that a container unveils to the same names on every platform
+pub(crate) fn display_path(name: &str) -> String {
+    name.split('/')
+        .map(|component| {
+            let component: String = component
+                .chars()
+                .map(|c| {
+                    if c.is_control() || ILLEGAL_CHARS.contains(&c) {
+                        '_'
+                    } else {
+                        c
+                    }
+                })
+                .collect();
+            let component = component.trim_end_matches(['.', ' ']);
+            let stem = component.split('.').next().unwrap_or_default();
+            if component.is_empty() {
+                "_".to_string()
+            } else if RESERVED_NAMES.contains(&stem.to_ascii_uppercase().as_str()) {
+                format!("_{component}")
+            } else {
+                component.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// the normalized path of a zip entry
+fn entry_path(name: &str) -> String {
+    let path = normalize_path(name);
+    if path.is_empty() {
+        return "--no-file-name--".to_string();
+    }
+
+    path
+}
+
+/// the normalized path of a zip entry, from the bytes of its name that have to be UTF-8.
+/// Other encodings, like the CP 437 of old zip tools, could not be told apart reliably
+fn checked_entry_path(raw_name: &[u8]) -> Result<String> {
+    std::str::from_utf8(raw_name)
+        .map(entry_path)
+        .map_err(|_| SteganoError::NonUtf8FileName(String::from_utf8_lossy(raw_name).into_owned()))
+}
+
+/// reads a small entry, like the timestamp token, completely
+fn read_entry(mut entry: impl Read) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data)?;
+
+    Ok(data)
+}
+
+/// finds the comment among the extra fields of a zip entry
+fn read_comment(extra_data: &[u8]) -> Option<String> {
+    find_extra_field(extra_data, COMMENT_EXTRA_FIELD_ID)
+        .map(|field| String::from_utf8_lossy(field).into_owned())
+}
+
+/// fails with `CommentTooLong` if the comment and the block checksums of a file
+/// do not fit into the extra fields of its zip entry, checked before anything is written
+fn check_extra_fields_len(name: &str, comment: Option<&String>, data_len: usize) -> Result<()> {
+    let checksums_len = EXTRA_FIELD_HEADER_LEN + BlockChecksums::bytes_len_for(data_len);
+    let max = (u16::MAX as usize).saturating_sub(checksums_len + EXTRA_FIELD_HEADER_LEN);
+    match comment {
+        Some(comment) if comment.len() > max => Err(SteganoError::CommentTooLong {
+            name: name.to_string(),
+            max,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// finds the content of the extra field with the given id
+fn find_extra_field(mut extra_data: &[u8], id: u16) -> Option<&[u8]> {
+    while extra_data.len() >= 4 {
+        let field_id = u16::from_le_bytes([extra_data[0], extra_data[1]]);
+        let len = u16::from_le_bytes([extra_data[2], extra_data[3]]) as usize;
+        let field = extra_data.get(4..4 + len)?;
+        if field_id == id {
+            return Some(field);
+        }
+        extra_data = &extra_data[4 + len..];
+    }
+
+    None
 }
 
-pub(crate) fn decode_message(decoder: &dyn PayloadCodec, data: &mut dyn Read) -> Result<Message> {
+/// decodes the content of a message, without interpreting it
+fn decode_content(decoder: &dyn PayloadCodec, data: &mut dyn Read) -> Result<Vec<u8>> {
     let content = decoder.decode(data)?;
+    if decoder.has_feature(PayloadCodecFeatures::Padded) {
+        return unpad(content);
+    }
+
+    Ok(content)
+}
+
+pub(crate) fn decode_message(
+    decoder: &dyn PayloadCodec,
+    data: &mut dyn Read,
+    policy: SpecialEntryPolicy,
+) -> Result<Message> {
+    let content = decode_content(decoder, data)?;
 
     if decoder.has_feature(PayloadCodecFeatures::TextOnly) {
         Message::from_utf8(content)
     } else if decoder.has_feature(PayloadCodecFeatures::TextAndDocuments) {
-        Message::from_documents_data(content)
+        Message::from_documents_data(content, policy)
     } else {
         Err(SteganoError::UnsupportedMessageFormat(
             decoder.version().into(),
@@ -192,7 +778,7 @@ mod tests {
     use crate::media::payload::{legacy, FabA, HasFeature, TEXT_ONLY};
 
     use super::*;
-    use std::io::{copy, BufReader};
+    use std::io::{copy, BufReader, Write};
     use zip::write::SimpleFileOptions;
     use zip::{CompressionMethod, ZipWriter};
 
@@ -235,6 +821,203 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_pad_documents_into_the_same_bucket() {
+        let mut short = Message::empty();
+        short.add_file_data("a.txt", b"short".to_vec()).unwrap();
+        short.padding = Some(PaddingBuckets::MultipleOf(1024));
+        let mut long = Message::empty();
+        long.add_file_data("a.txt", vec![42; 200]).unwrap();
+        long.padding = Some(PaddingBuckets::MultipleOf(1024));
+
+        let short = short.to_raw_data(&FabA).unwrap();
+        let long = long.to_raw_data(&FabA).unwrap();
+        assert_eq!(short.len(), long.len());
+
+        let m = Message::from_raw_data(&mut Cursor::new(short), &FabA).unwrap();
+        assert_eq!(m.files[0].1, b"short");
+    }
+
+    #[test]
+    fn should_keep_the_comments_of_files() {
+        let mut m = Message::empty();
+        m.add_file_data("a.txt", b"a".to_vec()).unwrap();
+        m.add_file_data("b.txt", b"b".to_vec()).unwrap();
+        m.comments
+            .insert("b.txt".to_string(), "the b file ✓".to_string());
+
+        let b = m.to_raw_data(&FabA).unwrap();
+        let m = Message::from_raw_data(&mut Cursor::new(b), &FabA).unwrap();
+
+        assert_eq!(m.comments.get("a.txt"), None);
+        assert_eq!(m.comments.get("b.txt").unwrap(), "the b file ✓");
+    }
+
+    #[test]
+    fn should_reject_a_comment_that_does_not_fit_the_extra_fields() {
+        let mut m = Message::empty();
+        m.add_file_data("a.txt", b"a".to_vec()).unwrap();
+        m.comments.insert("a.txt".to_string(), "x".repeat(65_519));
+        assert!(m.to_raw_data(&FabA).is_ok());
+
+        m.comments.insert("a.txt".to_string(), "x".repeat(65_520));
+        assert!(matches!(
+            m.to_raw_data(&FabA),
+            Err(SteganoError::CommentTooLong { max: 65_519, .. })
+        ));
+    }
+
+    #[test]
+    fn should_round_trip_nested_paths() {
+        let mut m = Message::empty();
+        m.add_file_data_at("docs/2024/report.pdf", b"pdf".to_vec())
+            .unwrap();
+        m.add_file_data_at("..\\windows\\notes.txt", b"txt".to_vec())
+            .unwrap();
+        assert!(m.add_file_data_at("../", vec![]).is_err());
+
+        let b = m.to_raw_data(&FabA).unwrap();
+        let m = Message::from_raw_data(&mut Cursor::new(b), &FabA).unwrap();
+
+        assert_eq!(m.files[0].0, "docs/2024/report.pdf");
+        assert_eq!(m.files[1].0, "windows/notes.txt");
+    }
+
+    #[test]
+    fn should_store_names_in_nfc() {
+        let mut m = Message::empty();
+        // `é` as `e` followed by a combining acute accent, the way macOS writes it
+        m.add_file_data_at("cafe\u{301}/menu.txt", b"menu".to_vec())
+            .unwrap();
+        m.add_file_data("r\u{e9}sum\u{e9}.pdf", b"pdf".to_vec())
+            .unwrap();
+
+        let b = m.to_raw_data(&FabA).unwrap();
+        let m = Message::from_raw_data(&mut Cursor::new(b), &FabA).unwrap();
+
+        assert_eq!(m.files[0].0, "caf\u{e9}/menu.txt");
+        assert_eq!(m.files[1].0, "r\u{e9}sum\u{e9}.pdf");
+        assert!(m.warnings.is_empty());
+    }
+
+    #[test]
+    fn should_make_names_legal_on_every_platform() {
+        assert_eq!(display_path("docs/a:b?.txt"), "docs/a_b_.txt");
+        assert_eq!(display_path("con.txt/aux"), "_con.txt/_aux");
+        assert_eq!(display_path("notes. /tab\there"), "notes/tab_here");
+        assert_eq!(display_path("..."), "_");
+        assert_eq!(display_path("caf\u{e9}/menu.txt"), "caf\u{e9}/menu.txt");
+    }
+
+    #[test]
+    fn should_apply_the_policy_to_symlinks() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("a.txt", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"a").unwrap();
+        zip.add_symlink("link", "/etc/passwd", SimpleFileOptions::default())
+            .unwrap();
+        let zip = zip.finish().unwrap().into_inner();
+        let raw = FabA
+            .create_codec(PayloadCodecFeatures::TextAndDocuments)
+            .unwrap()
+            .encode(&mut Cursor::new(zip))
+            .unwrap();
+        let unveil = |policy| {
+            Message::from_raw_data_with_policy(&mut Cursor::new(&raw), &FabA, policy).unwrap()
+        };
+
+        let m = unveil(SpecialEntryPolicy::Skip);
+        assert_eq!(m.files.len(), 1);
+        assert_eq!(m.warnings, vec!["Skipped the symlink `link`"]);
+
+        let m = unveil(SpecialEntryPolicy::AsRegularFile);
+        assert_eq!(m.files[1], ("link".to_string(), b"/etc/passwd".to_vec()));
+
+        let m = unveil(SpecialEntryPolicy::LinkTargetAsMetadata);
+        assert_eq!(m.files[1], ("link".to_string(), Vec::new()));
+        assert_eq!(m.link_targets.get(&1).unwrap(), "/etc/passwd");
+    }
+
+    #[test]
+    fn should_reject_names_that_are_not_utf8() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("cafX.txt", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"a").unwrap();
+        let mut zip = zip.finish().unwrap().into_inner();
+        // the name is written twice, in the local header and in the central directory
+        for start in 0..zip.len() - 8 {
+            if &zip[start..start + 8] == b"cafX.txt" {
+                zip[start + 3] = 0xff;
+            }
+        }
+        let raw = FabA
+            .create_codec(PayloadCodecFeatures::TextAndDocuments)
+            .unwrap()
+            .encode(&mut Cursor::new(zip))
+            .unwrap();
+
+        assert!(matches!(
+            Message::from_raw_data(&mut Cursor::new(&raw), &FabA),
+            Err(SteganoError::NonUtf8FileName(name)) if name == "caf\u{fffd}.txt"
+        ));
+        assert!(matches!(
+            TableOfContents::from_raw_data_with_policy(
+                &mut Cursor::new(&raw),
+                &FabA,
+                SpecialEntryPolicy::default()
+            ),
+            Err(SteganoError::NonUtf8FileName(_))
+        ));
+    }
+
+    #[test]
+    fn should_list_the_entries_like_they_get_unveiled() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("a.txt", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"a").unwrap();
+        zip.add_symlink("link", "/etc/passwd", SimpleFileOptions::default())
+            .unwrap();
+        zip.start_file(".stegano/from-the-future", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"?").unwrap();
+        let zip = zip.finish().unwrap().into_inner();
+        let raw = FabA
+            .create_codec(PayloadCodecFeatures::TextAndDocuments)
+            .unwrap()
+            .encode(&mut Cursor::new(zip))
+            .unwrap();
+        let list = |policy| {
+            TableOfContents::from_raw_data_with_policy(&mut Cursor::new(&raw), &FabA, policy)
+                .unwrap()
+                .files
+                .into_iter()
+                .map(|file| (file.name, file.size))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            list(SpecialEntryPolicy::Skip),
+            vec![("a.txt".to_string(), 1)]
+        );
+        assert_eq!(
+            list(SpecialEntryPolicy::AsRegularFile),
+            vec![("a.txt".to_string(), 1), ("link".to_string(), 11)]
+        );
+        assert_eq!(
+            list(SpecialEntryPolicy::LinkTargetAsMetadata),
+            vec![("a.txt".to_string(), 1), ("link".to_string(), 0)]
+        );
+
+        let m = Message::from_raw_data(&mut Cursor::new(&raw), &FabA).unwrap();
+        assert_eq!(m.files.len(), 1);
+        assert!(m.warnings.contains(
+            &"Skipped the unknown stegano record `.stegano/from-the-future`".to_string()
+        ));
+    }
+
     #[test]
     fn should_instantiate_from_read_trait() {
         let files = &["tests/images/with_text/hello_world.png"];
@@ -263,7 +1046,11 @@ mod tests {
         const BUF: [u8; 6] = [TEXT_ONLY, b'H', b'e', 0xff, 0xff, 0xcd];
 
         let m = Message::from_raw_data(&mut Cursor::new(BUF), &legacy::FabTextOnly).unwrap();
-        assert_eq!(m.text.unwrap(), "He", "Message.text was not as expected");
+        assert_eq!(
+            m.text.clone().unwrap(),
+            "He",
+            "Message.text was not as expected"
+        );
         assert_eq!(m.files.len(), 0, "Message.files were not empty.");
     }
 
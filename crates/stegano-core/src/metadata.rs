@@ -0,0 +1,101 @@
+//! # Metadata
+//! An optional provenance record that is hidden along with the files,
+//! it gets encrypted together with them when a password is used.
+
+use std::io::{BufRead, Write};
+
+use crate::result::Result;
+
+/// the name of the zip entry that holds the metadata record
+pub(crate) const METADATA_ENTRY: &str = ".stegano/metadata";
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    /// who hid the data
+    pub author: Option<String>,
+    /// unix timestamp in seconds of when the data was hidden
+    pub created_at: Option<u64>,
+    /// version of the application that hid the data
+    pub app_version: Option<String>,
+}
+
+impl Metadata {
+    /// serializes the record as `key=value` lines
+    pub(crate) fn write_to(&self, w: &mut dyn Write) -> Result<()> {
+        if let Some(author) = &self.author {
+            writeln!(w, "author={}", escape(author))?;
+        }
+        if let Some(created_at) = self.created_at {
+            writeln!(w, "created_at={created_at}")?;
+        }
+        if let Some(app_version) = &self.app_version {
+            writeln!(w, "app_version={}", escape(app_version))?;
+        }
+
+        Ok(())
+    }
+
+    /// parses a record written by `write_to`, unknown keys are ignored
+    pub(crate) fn read_from(r: &mut dyn BufRead) -> Result<Self> {
+        let mut metadata = Metadata::default();
+        for line in r.lines() {
+            let line = line?;
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            match key {
+                "author" => metadata.author = Some(unescape(value)),
+                "created_at" => metadata.created_at = value.parse().ok(),
+                "app_version" => metadata.app_version = Some(unescape(value)),
+                _ => {}
+            }
+        }
+
+        Ok(metadata)
+    }
+}
+
+/// escapes backslashes and line breaks, so that a value fits on one line
+pub(crate) fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+pub(crate) fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                result.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                result.push('\\');
+                chars.next();
+            }
+            (c, _) => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_round_trip_the_record() {
+        let metadata = Metadata {
+            author: Some("Jane \\ Doe\nACME".to_string()),
+            created_at: Some(1_700_000_000),
+            app_version: Some("1.2.3".to_string()),
+        };
+
+        let mut buf = Vec::new();
+        metadata.write_to(&mut buf).unwrap();
+        let parsed = Metadata::read_from(&mut buf.as_slice()).unwrap();
+
+        assert_eq!(parsed, metadata);
+    }
+}
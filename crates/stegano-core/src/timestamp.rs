@@ -0,0 +1,344 @@
+//! # Timestamp token
+//! An optional RFC 3161 timestamp token that was obtained from a time stamping authority (TSA)
+//! for the hash of a document, it is hidden along with the files and proves that the document existed
+//! at the time the TSA signed.
+//!
+//! Only the structure of the token is checked locally, the signature of the TSA needs its certificate chain
+//! and is left to tools like `openssl ts -verify`.
+
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use subtle::ConstantTimeEq;
+
+use crate::digests::to_hex;
+use crate::result::Result;
+use crate::SteganoError;
+
+/// the name of the zip entry that holds the DER of the token
+pub(crate) const TIMESTAMP_ENTRY: &str = ".stegano/timestamp.tsr";
+
+/// 1.2.840.113549.1.7.2
+const OID_SIGNED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+/// 1.2.840.113549.1.9.16.1.4
+const OID_TST_INFO: &[u8] = &[
+    0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x01, 0x04,
+];
+const OID_SHA256: &str = "2.16.840.1.101.3.4.2.1";
+const OID_SHA384: &str = "2.16.840.1.101.3.4.2.2";
+const OID_SHA512: &str = "2.16.840.1.101.3.4.2.3";
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_CONTEXT_0: u8 = 0xa0;
+const TAG_CONTEXT_1: u8 = 0xa1;
+
+/// A RFC 3161 timestamp token, its structure was checked when it was created
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampToken {
+    der: Vec<u8>,
+    info: TimestampInfo,
+}
+
+/// What the TSA signed, taken from the `TSTInfo` of the token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampInfo {
+    /// unix timestamp in seconds of when the TSA signed
+    pub gen_time: u64,
+    /// the policy of the TSA, as dotted OID
+    pub policy: String,
+    /// the hash algorithm of the imprint, as dotted OID
+    pub hash_algorithm: String,
+    /// the hash of the document that was timestamped
+    pub hashed_message: Vec<u8>,
+    /// the serial number the TSA gave the token
+    pub serial_number: Vec<u8>,
+}
+
+impl TimestampToken {
+    /// Takes a token as the TSA handed it out, DER encoded.
+    /// Fails with `InvalidTimestampToken` if it is not a `SignedData` with a `TSTInfo` inside
+    pub fn from_der(der: Vec<u8>) -> Result<Self> {
+        let info = parse_token(&der).map_err(SteganoError::InvalidTimestampToken)?;
+
+        Ok(Self { der, info })
+    }
+
+    /// the token as DER, e.g. for `openssl ts -verify`
+    pub fn as_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    pub fn info(&self) -> &TimestampInfo {
+        &self.info
+    }
+}
+
+impl TimestampInfo {
+    /// `true` if the imprint is the hash of `data`, SHA-256, SHA-384 and SHA-512 are known
+    pub fn covers(&self, data: &[u8]) -> bool {
+        let digest = match self.hash_algorithm.as_str() {
+            OID_SHA256 => Sha256::digest(data).to_vec(),
+            OID_SHA384 => Sha384::digest(data).to_vec(),
+            OID_SHA512 => Sha512::digest(data).to_vec(),
+            _ => return false,
+        };
+
+        digest.ct_eq(&self.hashed_message).into()
+    }
+
+    /// the hash of the document as lowercase hex
+    pub fn hashed_message_hex(&self) -> String {
+        to_hex(&self.hashed_message)
+    }
+}
+
+/// A reader of DER encoded values, one after the other
+struct Der<'a>(&'a [u8]);
+
+impl<'a> Der<'a> {
+    fn peek_tag(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    /// the content of the next value, that has to have the given tag
+    fn expect(
+        &mut self,
+        tag: u8,
+        what: &'static str,
+    ) -> std::result::Result<&'a [u8], &'static str> {
+        let (&actual, rest) = self.0.split_first().ok_or(what)?;
+        if actual != tag {
+            return Err(what);
+        }
+        let (&first, mut rest) = rest.split_first().ok_or(what)?;
+        let len = match first {
+            0..=0x7f => first as usize,
+            0x81..=0x84 => {
+                let (len, tail) = rest.split_at_checked((first & 0x7f) as usize).ok_or(what)?;
+                rest = tail;
+                len.iter().fold(0usize, |acc, b| acc << 8 | *b as usize)
+            }
+            _ => return Err(what),
+        };
+        let (content, rest) = rest.split_at_checked(len).ok_or(what)?;
+        self.0 = rest;
+
+        Ok(content)
+    }
+
+    fn skip_if(&mut self, tag: u8, what: &'static str) -> std::result::Result<(), &'static str> {
+        if self.peek_tag() == Some(tag) {
+            self.expect(tag, what)?;
+        }
+        Ok(())
+    }
+}
+
+/// walks down `ContentInfo` > `SignedData` > `EncapsulatedContentInfo` > `TSTInfo`
+fn parse_token(der: &[u8]) -> std::result::Result<TimestampInfo, &'static str> {
+    let mut token = Der(der);
+    let mut content_info = Der(token.expect(TAG_SEQUENCE, "no ContentInfo")?);
+    if content_info.expect(TAG_OID, "no content type")? != OID_SIGNED_DATA {
+        return Err("not a SignedData");
+    }
+    let mut explicit = Der(content_info.expect(TAG_CONTEXT_0, "no SignedData")?);
+    let mut signed_data = Der(explicit.expect(TAG_SEQUENCE, "no SignedData")?);
+    signed_data.expect(TAG_INTEGER, "no SignedData version")?;
+    signed_data.expect(TAG_SET, "no digest algorithms")?;
+
+    let mut encap = Der(signed_data.expect(TAG_SEQUENCE, "no encapsulated content")?);
+    if encap.expect(TAG_OID, "no encapsulated content type")? != OID_TST_INFO {
+        return Err("not a TSTInfo");
+    }
+    let mut explicit = Der(encap.expect(TAG_CONTEXT_0, "no TSTInfo")?);
+    let tst_info = explicit.expect(TAG_OCTET_STRING, "no TSTInfo")?;
+
+    signed_data.skip_if(TAG_CONTEXT_0, "broken certificates")?;
+    signed_data.skip_if(TAG_CONTEXT_1, "broken CRLs")?;
+    if signed_data.expect(TAG_SET, "no signer infos")?.is_empty() {
+        return Err("no signer infos");
+    }
+
+    parse_tst_info(tst_info)
+}
+
+fn parse_tst_info(der: &[u8]) -> std::result::Result<TimestampInfo, &'static str> {
+    let mut tst_info = Der(Der(der).expect(TAG_SEQUENCE, "no TSTInfo")?);
+    if tst_info.expect(TAG_INTEGER, "no TSTInfo version")? != [1] {
+        return Err("unknown TSTInfo version");
+    }
+    let policy = oid_to_string(tst_info.expect(TAG_OID, "no policy")?)?;
+    let mut imprint = Der(tst_info.expect(TAG_SEQUENCE, "no message imprint")?);
+    let mut algorithm = Der(imprint.expect(TAG_SEQUENCE, "no hash algorithm")?);
+    let hash_algorithm = oid_to_string(algorithm.expect(TAG_OID, "no hash algorithm")?)?;
+    let hashed_message = imprint.expect(TAG_OCTET_STRING, "no hashed message")?;
+    let serial_number = tst_info.expect(TAG_INTEGER, "no serial number")?;
+    let gen_time = tst_info.expect(TAG_GENERALIZED_TIME, "no time")?;
+    let gen_time = parse_generalized_time(gen_time).ok_or("invalid time")?;
+
+    Ok(TimestampInfo {
+        gen_time,
+        policy,
+        hash_algorithm,
+        hashed_message: hashed_message.to_vec(),
+        serial_number: serial_number.to_vec(),
+    })
+}
+
+fn oid_to_string(oid: &[u8]) -> std::result::Result<String, &'static str> {
+    let mut arcs = Vec::new();
+    let mut arc = 0u64;
+    for b in oid {
+        arc = arc.checked_mul(128).ok_or("invalid OID")? | (b & 0x7f) as u64;
+        if b & 0x80 != 0 {
+            continue;
+        }
+        if arcs.is_empty() {
+            // the first two arcs share a byte
+            let first = (arc / 40).min(2);
+            arcs.extend([first, arc - first * 40]);
+        } else {
+            arcs.push(arc);
+        }
+        arc = 0;
+    }
+    if arcs.is_empty() || oid.last().is_some_and(|b| b & 0x80 != 0) {
+        return Err("invalid OID");
+    }
+
+    Ok(arcs
+        .iter()
+        .map(|arc| arc.to_string())
+        .collect::<Vec<_>>()
+        .join("."))
+}
+
+/// `YYYYMMDDhhmmss[.f+]Z` as unix timestamp in seconds, fractions are dropped
+fn parse_generalized_time(time: &[u8]) -> Option<u64> {
+    let time = std::str::from_utf8(time).ok()?.strip_suffix('Z')?;
+    let (time, _fraction) = time.split_once('.').unwrap_or((time, ""));
+    if time.len() != 14 || !time.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| time[range].parse::<u64>().ok();
+    let (year, month, day) = (field(0..4)?, field(4..6)?, field(6..8)?);
+    let (hour, minute, second) = (field(8..10)?, field(10..12)?, field(12..14)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+
+    // days since 1970-01-01 of the proleptic Gregorian calendar
+    let (y, m) = if month <= 2 {
+        (year.checked_sub(1)?, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let day_of_era =
+        (y % 400) * 365 + (y % 400) / 4 - (y % 400) / 100 + (153 * m + 2) / 5 + day - 1;
+    let days = (era * 146_097 + day_of_era).checked_sub(719_468)?;
+
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut der = vec![tag];
+        if content.len() < 0x80 {
+            der.push(content.len() as u8);
+        } else {
+            der.push(0x82);
+            der.extend((content.len() as u16).to_be_bytes());
+        }
+        der.extend(content);
+        der
+    }
+
+    /// a token with the structure of RFC 3161 for the SHA-256 of `data`, the signer info is a dummy
+    pub(crate) fn token_for(data: &[u8]) -> Vec<u8> {
+        let algorithm = tlv(
+            TAG_SEQUENCE,
+            &tlv(
+                TAG_OID,
+                &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01],
+            ),
+        );
+        let imprint = tlv(
+            TAG_SEQUENCE,
+            &[
+                algorithm.clone(),
+                tlv(TAG_OCTET_STRING, &Sha256::digest(data)),
+            ]
+            .concat(),
+        );
+        let tst_info = tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_INTEGER, &[1]),
+                tlv(TAG_OID, &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x01]),
+                imprint,
+                tlv(TAG_INTEGER, &[0x01, 0x02]),
+                tlv(TAG_GENERALIZED_TIME, b"20240229123456.5Z"),
+            ]
+            .concat(),
+        );
+        let encap = tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_OID, OID_TST_INFO),
+                tlv(TAG_CONTEXT_0, &tlv(TAG_OCTET_STRING, &tst_info)),
+            ]
+            .concat(),
+        );
+        let signed_data = tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_INTEGER, &[3]),
+                tlv(TAG_SET, &algorithm),
+                encap,
+                tlv(TAG_SET, &tlv(TAG_SEQUENCE, &[])),
+            ]
+            .concat(),
+        );
+
+        tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_OID, OID_SIGNED_DATA),
+                tlv(TAG_CONTEXT_0, &signed_data),
+            ]
+            .concat(),
+        )
+    }
+
+    #[test]
+    fn should_read_what_the_tsa_signed() {
+        let token = TimestampToken::from_der(token_for(b"document")).unwrap();
+        let info = token.info();
+
+        assert_eq!(info.gen_time, 1_709_210_096);
+        assert_eq!(info.policy, "1.3.6.1.4.1.311.1");
+        assert_eq!(info.hash_algorithm, OID_SHA256);
+        assert_eq!(info.serial_number, [1, 2]);
+        assert!(info.covers(b"document"));
+        assert!(!info.covers(b"another document"));
+    }
+
+    #[test]
+    fn should_reject_what_is_not_a_token() {
+        let mut der = token_for(b"document");
+        der.truncate(der.len() - 1);
+
+        assert!(matches!(
+            TimestampToken::from_der(der),
+            Err(SteganoError::InvalidTimestampToken(_))
+        ));
+        assert!(TimestampToken::from_der(b"not a token".to_vec()).is_err());
+    }
+}
@@ -54,3 +54,43 @@ fn ensure_text_and_documents_without_password_from_v2_1_1_9_unveils() {
     let contained_file = File::open(out_dir.as_ref().join("image-with-hello-world.png")).unwrap();
     assert_eq!(contained_file.metadata().unwrap().len(), 188292);
 }
+
+#[test]
+fn ensure_old_formats_unveil_even_with_a_password() {
+    let files = api::unveil::prepare()
+        .from_secret_file(DEMO_IMG_V1_TEXT_ONLY_WITHOUT_PASSWD_V2_1_1_9)
+        .using_password("not used by old formats")
+        .execute_to_memory()
+        .unwrap();
+
+    assert_eq!(files[0].1, b"Welcome to a Text Only Secret Message");
+}
+
+#[test]
+fn ensure_old_formats_can_be_selected_or_rejected() {
+    use api::unveil::LegacyFormats;
+
+    let unveil = |formats| {
+        api::unveil::prepare()
+            .from_secret_file(DEMO_IMG_V1_TEXT_ONLY_WITHOUT_PASSWD_V2_1_1_9)
+            .with_legacy_formats(formats)
+            .execute_to_memory()
+    };
+
+    assert!(unveil(LegacyFormats::OnlyV1).is_ok());
+    assert!(matches!(
+        unveil(LegacyFormats::OnlyV2),
+        Err(stegano_core::SteganoError::UnsupportedMessageFormat(1))
+    ));
+    assert!(matches!(
+        unveil(LegacyFormats::Reject),
+        Err(stegano_core::SteganoError::UnsupportedMessageFormat(1))
+    ));
+
+    let files = api::unveil::prepare()
+        .from_secret_file(DEMO_IMG_TEXT_AND_DOCUMENT_WITHOUT_PASSWD_V2_2_5)
+        .with_legacy_formats(LegacyFormats::Reject)
+        .execute_to_memory()
+        .unwrap();
+    assert_eq!(files.len(), 2);
+}
@@ -19,4 +19,13 @@ pub enum SeasmokeError {
 
     #[error("Random Salt initialization error")]
     RandomSaltError(RandCoreError),
+
+    #[error("Invalid threshold {0} for {1} shares")]
+    InvalidThreshold(u8, u8),
+
+    #[error("Not enough valid passwords, {0} required but only {1} matched")]
+    ThresholdNotReached(u8, u8),
+
+    #[error("Threshold encrypted data is malformed")]
+    MalformedThresholdData,
 }
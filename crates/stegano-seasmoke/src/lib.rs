@@ -3,24 +3,41 @@
 
 use argon2::password_hash::rand_core::{OsRng, RngCore};
 use argon2::{Argon2, ParamsBuilder};
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use chacha20::ChaCha20;
 use chacha20poly1305::aead::{Aead, AeadCore};
 use chacha20poly1305::{KeyInit, XChaCha20Poly1305};
+use subtle::{Choice, ConditionallySelectable};
 // use rand::RngCore;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, Zeroizing};
 
 pub mod error;
 pub mod ffi;
 pub mod ffi_utils;
+mod shamir;
 
 pub use crate::error::SeasmokeError;
 
 const NONCE_LEN: usize = 24;
 const SALT_LEN: usize = 32;
 const KEY_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+/// one share slot: the x coordinate followed by the password encrypted share
+const THRESHOLD_SLOT_LEN: usize = 1 + KEY_LEN + TAG_LEN + NONCE_LEN + SALT_LEN;
 
 pub type Result<T> = std::result::Result<T, SeasmokeError>;
 pub type Key = [u8; KEY_LEN];
 
+/// the bytes [`encrypt_data`] adds to the data: the tag, nonce and salt
+pub const fn encryption_overhead() -> usize {
+    TAG_LEN + NONCE_LEN + SALT_LEN
+}
+
+/// the bytes [`encrypt_data_threshold`] adds to the data for the given number of passwords
+pub const fn threshold_encryption_overhead(passwords: usize) -> usize {
+    2 + passwords * THRESHOLD_SLOT_LEN + TAG_LEN + NONCE_LEN
+}
+
 /// decrypt data with password, it uses argon2id for key derivation and XChaCha20Poly1305 for encryption
 pub fn decrypt_data(password: &str, data: &[u8]) -> Result<Vec<u8>> {
     assert!(data.len() >= SALT_LEN + NONCE_LEN, "data is too short");
@@ -28,7 +45,7 @@ pub fn decrypt_data(password: &str, data: &[u8]) -> Result<Vec<u8>> {
     let nonce = &data[data.len() - SALT_LEN - NONCE_LEN..data.len() - SALT_LEN];
     let key = derive_key(password.as_bytes(), salt)?;
 
-    let decryptor = XChaCha20Poly1305::new(&key.into());
+    let decryptor = XChaCha20Poly1305::new((&*key).into());
     let decipher_data = decryptor
         .decrypt(nonce.into(), &data[0..data.len() - SALT_LEN - NONCE_LEN])
         .map_err(SeasmokeError::DecryptionError)?;
@@ -49,7 +66,7 @@ pub fn encrypt_data(password: &str, data: &[u8]) -> Result<Vec<u8>> {
     let mut nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
     assert!(nonce.len() == NONCE_LEN);
 
-    let encryptor = XChaCha20Poly1305::new(&key.into());
+    let encryptor = XChaCha20Poly1305::new((&*key).into());
     let mut cipher_data = encryptor
         .encrypt(&nonce, data)
         .map_err(SeasmokeError::EncryptionError)?;
@@ -62,6 +79,128 @@ pub fn encrypt_data(password: &str, data: &[u8]) -> Result<Vec<u8>> {
     Ok(cipher_data)
 }
 
+/// encrypt data so that any `threshold` of the given passwords can decrypt it again.
+///
+/// A random data key is split via Shamir's secret sharing into one share per password,
+/// each share is then encrypted with its own password, see [`encrypt_data`].
+pub fn encrypt_data_threshold(passwords: &[&str], threshold: u8, data: &[u8]) -> Result<Vec<u8>> {
+    let share_count = u8::try_from(passwords.len())
+        .map_err(|_| SeasmokeError::InvalidThreshold(threshold, u8::MAX))?;
+
+    let mut key: Zeroizing<Key> = Zeroizing::new([0u8; KEY_LEN]);
+    OsRng
+        .try_fill_bytes(&mut *key)
+        .map_err(SeasmokeError::RandomSaltError)?;
+    let shares = shamir::split(&*key, threshold, share_count)?;
+
+    let mut cipher_data = Vec::with_capacity(
+        2 + passwords.len() * THRESHOLD_SLOT_LEN + data.len() + TAG_LEN + NONCE_LEN,
+    );
+    cipher_data.push(threshold);
+    cipher_data.push(share_count);
+    for (password, share) in passwords.iter().zip(shares.iter()) {
+        cipher_data.push(share.x);
+        cipher_data.extend_from_slice(&encrypt_data(password, &share.y)?);
+    }
+
+    let mut nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let encryptor = XChaCha20Poly1305::new((&*key).into());
+    let encrypted = encryptor
+        .encrypt(&nonce, data)
+        .map_err(SeasmokeError::EncryptionError)?;
+    cipher_data.extend_from_slice(&encrypted);
+    cipher_data.extend_from_slice(&nonce);
+
+    nonce.zeroize();
+
+    Ok(cipher_data)
+}
+
+/// decrypt data that was encrypted with [`encrypt_data_threshold`].
+///
+/// The passwords can be given in any order, every password is tried against every share.
+pub fn decrypt_data_threshold(passwords: &[&str], data: &[u8]) -> Result<Vec<u8>> {
+    let [threshold, share_count, ..] = *data else {
+        return Err(SeasmokeError::MalformedThresholdData);
+    };
+    let body_offset = 2 + share_count as usize * THRESHOLD_SLOT_LEN;
+    if threshold == 0 || threshold > share_count || data.len() < body_offset + NONCE_LEN {
+        return Err(SeasmokeError::MalformedThresholdData);
+    }
+
+    // Every password is tried against every share, and the shares are picked in constant time,
+    // so that the timing does not reveal which passwords matched which share.
+    let slots: Vec<&[u8]> = data[2..body_offset]
+        .chunks_exact(THRESHOLD_SLOT_LEN)
+        .collect();
+    let mut shares: Vec<shamir::Share> = slots
+        .iter()
+        .map(|slot| shamir::Share {
+            x: slot[0],
+            y: vec![0u8; KEY_LEN],
+        })
+        .collect();
+    let mut unlocked = vec![Choice::from(0); slots.len()];
+    for password in passwords {
+        for ((slot, share), unlocked) in
+            slots.iter().zip(shares.iter_mut()).zip(unlocked.iter_mut())
+        {
+            let attempt = decrypt_data(password, &slot[1..]);
+            let matched = Choice::from(attempt.is_ok() as u8);
+            let y = Zeroizing::new(attempt.unwrap_or_else(|_| vec![0u8; KEY_LEN]));
+            let take = matched & !*unlocked;
+            for (dst, src) in share.y.iter_mut().zip(y.iter()) {
+                dst.conditional_assign(src, take);
+            }
+            *unlocked |= matched;
+        }
+    }
+
+    let unlocked_count = unlocked.iter().fold(0u8, |count, unlocked| {
+        count + u8::conditional_select(&0, &1, *unlocked)
+    });
+    if unlocked_count < threshold {
+        return Err(SeasmokeError::ThresholdNotReached(
+            threshold,
+            unlocked_count,
+        ));
+    }
+
+    let shares: Vec<shamir::Share> = shares
+        .into_iter()
+        .zip(unlocked)
+        .filter(|(_, unlocked)| bool::from(*unlocked))
+        .map(|(share, _)| share)
+        .take(threshold as usize)
+        .collect();
+    let key = Zeroizing::new(shamir::combine(&shares)?);
+
+    let body = &data[body_offset..];
+    let nonce = &body[body.len() - NONCE_LEN..];
+    let decryptor = XChaCha20Poly1305::new_from_slice(&key)
+        .map_err(|_| SeasmokeError::MalformedThresholdData)?;
+    decryptor
+        .decrypt(nonce.into(), &body[..body.len() - NONCE_LEN])
+        .map_err(SeasmokeError::DecryptionError)
+}
+
+/// fill the buffer with cryptographically secure noise, it uses a ChaCha20 keystream of a random key
+pub fn fill_noise(buffer: &mut [u8]) -> Result<()> {
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    let mut nonce = [0u8; 12];
+    OsRng
+        .try_fill_bytes(&mut *key)
+        .map_err(SeasmokeError::RandomSaltError)?;
+    OsRng
+        .try_fill_bytes(&mut nonce)
+        .map_err(SeasmokeError::RandomSaltError)?;
+
+    buffer.fill(0);
+    ChaCha20::new((&*key).into(), (&nonce).into()).apply_keystream(buffer);
+
+    Ok(())
+}
+
 fn default_secure_argon<'key>() -> Result<Argon2<'key>> {
     // increased time costs to make it more secure
     let params = ParamsBuilder::default()
@@ -77,10 +216,10 @@ fn default_secure_argon<'key>() -> Result<Argon2<'key>> {
     ))
 }
 
-fn derive_key(password: &[u8], salt: &[u8]) -> Result<[u8; 32]> {
-    let mut output_key_material = [0u8; 32]; // Can be any desired size
+fn derive_key(password: &[u8], salt: &[u8]) -> Result<Zeroizing<Key>> {
+    let mut output_key_material = Zeroizing::new([0u8; KEY_LEN]);
     default_secure_argon()?
-        .hash_password_into(password, salt, &mut output_key_material)
+        .hash_password_into(password, salt, &mut *output_key_material)
         .map_err(SeasmokeError::KeyDerivationError)?;
 
     Ok(output_key_material)
@@ -146,4 +285,70 @@ mod tests {
         assert_ne!(data, cipher_data.as_slice());
         assert_eq!(data, decipher_data.as_slice());
     }
+
+    #[test]
+    fn test_threshold_encryption_round_trip() {
+        let data = b"only two of us can read this";
+        let cipher_data = encrypt_data_threshold(&["alice", "bob", "carol"], 2, data).unwrap();
+
+        let decipher_data = decrypt_data_threshold(&["carol", "alice"], &cipher_data).unwrap();
+        assert_eq!(data, decipher_data.as_slice());
+    }
+
+    #[test]
+    fn test_noise_is_not_repeated() {
+        let mut a = [0u8; 64];
+        let mut b = [0u8; 64];
+        fill_noise(&mut a).unwrap();
+        fill_noise(&mut b).unwrap();
+
+        assert_ne!(a, [0u8; 64]);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_tampered_tag_is_rejected() {
+        let mut cipher_data = encrypt_data("hunter42", b"secret").unwrap();
+        // the tag sits right before the nonce and salt
+        let tag_end = cipher_data.len() - NONCE_LEN - SALT_LEN;
+        cipher_data[tag_end - 1] ^= 0x01;
+
+        assert!(matches!(
+            decrypt_data("hunter42", &cipher_data),
+            Err(SeasmokeError::DecryptionError(_))
+        ));
+    }
+
+    #[test]
+    fn test_threshold_decryption_with_wrong_passwords_in_between() {
+        let data = b"only two of us can read this";
+        let cipher_data = encrypt_data_threshold(&["alice", "bob", "carol"], 2, data).unwrap();
+
+        let decipher_data =
+            decrypt_data_threshold(&["mallory", "carol", "eve", "alice"], &cipher_data).unwrap();
+        assert_eq!(data, decipher_data.as_slice());
+    }
+
+    #[test]
+    fn test_threshold_tampered_body_is_rejected() {
+        let mut cipher_data = encrypt_data_threshold(&["alice", "bob"], 2, b"secret").unwrap();
+        let body_start = 2 + 2 * THRESHOLD_SLOT_LEN;
+        cipher_data[body_start] ^= 0x01;
+
+        assert!(matches!(
+            decrypt_data_threshold(&["alice", "bob"], &cipher_data),
+            Err(SeasmokeError::DecryptionError(_))
+        ));
+    }
+
+    #[test]
+    fn test_threshold_encryption_not_enough_passwords() {
+        let cipher_data = encrypt_data_threshold(&["alice", "bob", "carol"], 2, b"secret").unwrap();
+
+        let result = decrypt_data_threshold(&["bob", "mallory"], &cipher_data);
+        assert!(matches!(
+            result,
+            Err(SeasmokeError::ThresholdNotReached(2, 1))
+        ));
+    }
 }
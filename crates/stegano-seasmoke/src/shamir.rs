@@ -0,0 +1,166 @@
+//! # Shamir's Secret Sharing over GF(256)
+//! Splits a secret byte-wise into shares, any `threshold` of them reconstruct the secret.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::{Result, SeasmokeError};
+
+/// one share of a split secret, `x` is the evaluation point and never `0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    pub x: u8,
+    pub y: Vec<u8>,
+}
+
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.y.zeroize();
+    }
+}
+
+/// multiplication in GF(256) with the AES polynomial `x^8 + x^4 + x^3 + x + 1`
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        // branch free, so that the timing does not depend on the operands
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+
+    product
+}
+
+/// multiplicative inverse in GF(256), based on `a^254 = a^-1`
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exp = 254u8;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exp >>= 1;
+    }
+
+    result
+}
+
+/// splits `secret` into `shares` shares, of which any `threshold` reconstruct it
+pub fn split(secret: &[u8], threshold: u8, shares: u8) -> Result<Vec<Share>> {
+    if threshold == 0 || threshold > shares {
+        return Err(SeasmokeError::InvalidThreshold(threshold, shares));
+    }
+
+    let mut result: Vec<Share> = (1..=shares)
+        .map(|x| Share {
+            x,
+            y: Vec::with_capacity(secret.len()),
+        })
+        .collect();
+
+    let mut coefficients = vec![0u8; threshold as usize];
+    for byte in secret {
+        coefficients[0] = *byte;
+        OsRng
+            .try_fill_bytes(&mut coefficients[1..])
+            .map_err(SeasmokeError::RandomSaltError)?;
+
+        for share in result.iter_mut() {
+            // horner's method, starting at the highest coefficient
+            let y = coefficients
+                .iter()
+                .rev()
+                .fold(0u8, |acc, c| gf_mul(acc, share.x) ^ c);
+            share.y.push(y);
+        }
+    }
+    coefficients.zeroize();
+
+    Ok(result)
+}
+
+/// reconstructs the secret from the given shares by lagrange interpolation at `x = 0`
+pub fn combine(shares: &[Share]) -> Result<Vec<u8>> {
+    let Some(first) = shares.first() else {
+        return Err(SeasmokeError::MalformedThresholdData);
+    };
+    let len = first.y.len();
+    for (i, share) in shares.iter().enumerate() {
+        if share.x == 0
+            || share.y.len() != len
+            || shares[..i].iter().any(|other| other.x == share.x)
+        {
+            return Err(SeasmokeError::MalformedThresholdData);
+        }
+    }
+
+    let mut secret = vec![0u8; len];
+    for (i, share) in shares.iter().enumerate() {
+        let mut basis = 1u8;
+        for (j, other) in shares.iter().enumerate() {
+            if i != j {
+                basis = gf_mul(basis, gf_mul(other.x, gf_inv(other.x ^ share.x)));
+            }
+        }
+        for (s, y) in secret.iter_mut().zip(share.y.iter()) {
+            *s ^= gf_mul(*y, basis);
+        }
+    }
+
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gf_inverse() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1, "inverse of {a} is wrong");
+        }
+    }
+
+    #[test]
+    fn test_any_threshold_subset_reconstructs() {
+        let secret = b"the cake is a lie".to_vec();
+        let shares = split(&secret, 2, 3).unwrap();
+        assert_eq!(shares.len(), 3);
+
+        for (a, b) in [(0, 1), (0, 2), (1, 2), (2, 0)] {
+            let subset = [shares[a].clone(), shares[b].clone()];
+            assert_eq!(combine(&subset).unwrap(), secret);
+        }
+    }
+
+    #[test]
+    fn test_below_threshold_does_not_reconstruct() {
+        let secret = [42u8; 32];
+        let shares = split(&secret, 3, 3).unwrap();
+
+        assert_ne!(combine(&shares[..2]).unwrap(), secret);
+        assert_eq!(combine(&shares).unwrap(), secret);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert!(matches!(
+            split(b"x", 3, 2),
+            Err(SeasmokeError::InvalidThreshold(3, 2))
+        ));
+        assert!(matches!(
+            split(b"x", 0, 2),
+            Err(SeasmokeError::InvalidThreshold(0, 2))
+        ));
+
+        let share = split(b"x", 1, 1).unwrap().remove(0);
+        assert!(matches!(
+            combine(&[share.clone(), share]),
+            Err(SeasmokeError::MalformedThresholdData)
+        ));
+    }
+}
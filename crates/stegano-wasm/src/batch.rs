@@ -0,0 +1,188 @@
+use wasm_bindgen::prelude::*;
+
+use crate::options::UnveilOptions;
+use crate::{decode, paste};
+use crate::{load_secret_media, unveil_report, UnveilResult};
+
+/// The outcome of one carrier of a batch, it never fails the whole batch
+#[wasm_bindgen]
+pub struct BatchItem {
+    index: u32,
+    error_code: Option<String>,
+    error: Option<String>,
+    duration_ms: f64,
+    result: Option<UnveilResult>,
+}
+
+#[wasm_bindgen]
+impl BatchItem {
+    /// the position of the carrier in the batch
+    #[wasm_bindgen(getter)]
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// `"ok"` or `"error"`
+    #[wasm_bindgen(getter)]
+    pub fn status(&self) -> String {
+        match self.error {
+            None => "ok",
+            Some(_) => "error",
+        }
+        .to_string()
+    }
+
+    /// `"image_too_large"`, `"load_failed"` or `"unveil_failed"`, none if the item succeeded
+    #[wasm_bindgen(getter)]
+    pub fn error_code(&self) -> Option<String> {
+        self.error_code.clone()
+    }
+
+    /// what went wrong, for humans
+    #[wasm_bindgen(getter)]
+    pub fn error(&self) -> Option<String> {
+        self.error.clone()
+    }
+
+    /// how long the item took, in milliseconds
+    #[wasm_bindgen(getter)]
+    pub fn duration_ms(&self) -> f64 {
+        self.duration_ms
+    }
+
+    /// Hands out the unveiled files of the item, a second call returns none
+    pub fn take_result(&mut self) -> Option<UnveilResult> {
+        self.result.take()
+    }
+}
+
+/// The outcome of `unveil_batch`, one item per carrier in the same order
+#[wasm_bindgen]
+pub struct BatchResult {
+    items: Vec<BatchItem>,
+}
+
+#[wasm_bindgen]
+impl BatchResult {
+    #[wasm_bindgen(getter)]
+    pub fn succeeded(&self) -> u32 {
+        self.items
+            .iter()
+            .filter(|item| item.error.is_none())
+            .count() as u32
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn failed(&self) -> u32 {
+        self.items.len() as u32 - self.succeeded()
+    }
+
+    /// Hands out the items, a second call returns none
+    pub fn take_items(&mut self) -> Vec<BatchItem> {
+        std::mem::take(&mut self.items)
+    }
+}
+
+/// Unveils each carrier with the same options. A carrier that fails is reported in its item,
+/// the others are unveiled anyway, so that the UI can show a summary of the whole batch
+#[wasm_bindgen]
+pub fn unveil_batch(carriers: Vec<js_sys::Uint8Array>, options: &UnveilOptions) -> BatchResult {
+    unveil_all(carriers.iter().map(|carrier| carrier.to_vec()), options)
+}
+
+fn unveil_all(carriers: impl Iterator<Item = Vec<u8>>, options: &UnveilOptions) -> BatchResult {
+    let items = carriers
+        .enumerate()
+        .map(|(index, carrier)| {
+            let started = now_ms();
+            let outcome = unveil_one(&carrier, options);
+            let duration_ms = now_ms() - started;
+            let index = index as u32;
+
+            match outcome {
+                Ok(result) => BatchItem {
+                    index,
+                    error_code: None,
+                    error: None,
+                    duration_ms,
+                    result: Some(result),
+                },
+                Err((code, error)) => BatchItem {
+                    index,
+                    error_code: Some(code.to_string()),
+                    error: Some(error),
+                    duration_ms,
+                    result: None,
+                },
+            }
+        })
+        .collect();
+
+    BatchResult { items }
+}
+
+fn unveil_one(
+    carrier: &[u8],
+    options: &UnveilOptions,
+) -> Result<UnveilResult, (&'static str, String)> {
+    if let Some(too_large) = decode::too_large(&paste::normalize(carrier)) {
+        return Err(("image_too_large", too_large.message()));
+    }
+    let message = |e: JsValue| e.as_string().unwrap_or_default();
+    let media = load_secret_media(carrier).map_err(|e| ("load_failed", message(e)))?;
+    let unveil = options
+        .unveil()
+        .map_err(|e| ("unveil_failed", message(e)))?;
+
+    unveil_report(unveil, media).map_err(|e| ("unveil_failed", message(e)))
+}
+
+/// milliseconds since some point in time, only the differences matter
+fn now_ms() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64() * 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hide_data_with_options, HideOptions};
+
+    #[test]
+    fn should_report_each_carrier_of_the_batch() {
+        let mut carrier = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::from_pixel(64, 64, image::Rgba([90, 120, 150, 255]))
+            .write_to(&mut carrier, image::ImageFormat::Png)
+            .unwrap();
+        let stego = |secret: &[u8]| {
+            hide_data_with_options(
+                carrier.get_ref(),
+                "secret.txt",
+                secret.to_vec(),
+                &HideOptions::new(),
+            )
+            .unwrap()
+            .into_data()
+        };
+
+        let mut batch = unveil_all(
+            [stego(b"first"), stego(b"second")].into_iter(),
+            &UnveilOptions::new(),
+        );
+        assert_eq!((batch.succeeded(), batch.failed()), (2, 0));
+        let mut items = batch.take_items();
+        assert_eq!(items[1].index(), 1);
+        assert_eq!(items[1].status(), "ok");
+        assert!(items[1].duration_ms() >= 0.0);
+        let files = items[1].take_result().unwrap().take_files();
+        assert_eq!(files[0].name(), "secret.txt");
+    }
+}
@@ -0,0 +1,71 @@
+use image::RgbaImage;
+use wasm_bindgen::{Clamped, JsCast, JsValue};
+use web_sys::{ImageBitmap, ImageData, OffscreenCanvas, OffscreenCanvasRenderingContext2d};
+
+/// Resizes on an `OffscreenCanvas` of the browser, which is much faster than Lanczos3 in wasm.
+/// Returns `None` if the browser offers no `OffscreenCanvas`, e.g. outside of a browser.
+///
+/// Note the canvas works with premultiplied alpha, so semi-transparent pixels may change slightly.
+pub(crate) fn resize_with_canvas(img: &RgbaImage, width: u32, height: u32) -> Option<RgbaImage> {
+    let has_canvas = js_sys::Reflect::has(&js_sys::global(), &JsValue::from_str("OffscreenCanvas"));
+    if !has_canvas.unwrap_or(false) {
+        return None;
+    }
+
+    let source = OffscreenCanvas::new(img.width(), img.height()).ok()?;
+    let data = ImageData::new_with_u8_clamped_array_and_sh(
+        Clamped(img.as_raw()),
+        img.width(),
+        img.height(),
+    )
+    .ok()?;
+    context_2d(&source)?.put_image_data(&data, 0.0, 0.0).ok()?;
+
+    let target = OffscreenCanvas::new(width, height).ok()?;
+    let context = context_2d(&target)?;
+    context.set_image_smoothing_enabled(true);
+    js_sys::Reflect::set(
+        &context,
+        &JsValue::from_str("imageSmoothingQuality"),
+        &JsValue::from_str("high"),
+    )
+    .ok()?;
+    context
+        .draw_image_with_offscreen_canvas_and_dw_and_dh(
+            &source,
+            0.0,
+            0.0,
+            width as f64,
+            height as f64,
+        )
+        .ok()?;
+    let resized = context
+        .get_image_data(0.0, 0.0, width as f64, height as f64)
+        .ok()?;
+
+    RgbaImage::from_raw(width, height, resized.data().0)
+}
+
+/// Reads the pixels of an `ImageBitmap` back through an `OffscreenCanvas`, the browser hands them out
+/// with straight alpha. Returns `None` if the browser offers no `OffscreenCanvas`.
+pub(crate) fn pixels_of_bitmap(bitmap: &ImageBitmap) -> Option<RgbaImage> {
+    let (width, height) = (bitmap.width(), bitmap.height());
+    let canvas = OffscreenCanvas::new(width, height).ok()?;
+    let context = context_2d(&canvas)?;
+    context
+        .draw_image_with_image_bitmap(bitmap, 0.0, 0.0)
+        .ok()?;
+    let data = context
+        .get_image_data(0.0, 0.0, width as f64, height as f64)
+        .ok()?;
+
+    RgbaImage::from_raw(width, height, data.data().0)
+}
+
+fn context_2d(canvas: &OffscreenCanvas) -> Option<OffscreenCanvasRenderingContext2d> {
+    canvas
+        .get_context("2d")
+        .ok()??
+        .dyn_into::<OffscreenCanvasRenderingContext2d>()
+        .ok()
+}
@@ -0,0 +1,320 @@
+use std::io::Read;
+
+use stegano_core::media::payload::{HasFeature, PayloadCodecFeatures};
+use stegano_core::media::{audio, image::LsbCodec, Media};
+use wasm_bindgen::prelude::*;
+
+use crate::options::HideOptions;
+use crate::{codecs, decode, jxl, lsb_capacity, scaled_to_fit, wav};
+
+/// the features byte and the length in front of the hidden content
+const HEADER_LEN: usize = 5;
+/// the byte behind the hidden content
+const TERMINATOR: u8 = 0xff;
+/// the overhead is estimated for a file name of this length, longer names take twice their extra length
+const PROBE_NAME: &str = "a-secret-file-name-of-32-bytes.x";
+
+/// What a carrier can hold and whether something is hidden already, from `inspect_carrier`
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct CarrierInfo {
+    width: u32,
+    height: u32,
+    samples: Option<usize>,
+    format: String,
+    capacity: usize,
+    overhead: usize,
+    max_secret_size: usize,
+    features: Option<u8>,
+}
+
+#[wasm_bindgen]
+impl CarrierInfo {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// the number of samples of a WAV, of all channels together, width and height are 0 then
+    #[wasm_bindgen(getter)]
+    pub fn samples(&self) -> Option<f64> {
+        self.samples.map(|samples| samples as f64)
+    }
+
+    /// e.g. `"png"`, `"webp"`, `"jxl"` or `"wav"`, or the name of a decoder registered by `register_decoder`
+    #[wasm_bindgen(getter)]
+    pub fn format(&self) -> String {
+        self.format.clone()
+    }
+
+    /// bytes that fit into the LSBs of the carrier, of its color channels or its samples, the payload with all its headers included.
+    /// For an image only the channels the options leave for data count, see `HideOptions.alpha_policy`,
+    /// `HideOptions.stealth`, `HideOptions.banding_aware` and `HideOptions.redundancy`
+    #[wasm_bindgen(getter)]
+    pub fn capacity(&self) -> f64 {
+        self.capacity as f64
+    }
+
+    /// bytes the payload takes besides the secret, for an empty secret with the options
+    #[wasm_bindgen(getter)]
+    pub fn overhead(&self) -> f64 {
+        self.overhead as f64
+    }
+
+    /// The largest secret that fits without autoscale, even if it cannot be compressed at all.
+    /// With padding, see `HideOptions.padding`, the secret has to be smaller by up to a bucket
+    #[wasm_bindgen(getter)]
+    pub fn max_secret_size(&self) -> f64 {
+        self.max_secret_size as f64
+    }
+
+    /// `true` if the LSBs start with a payload header whose length and end marker fit,
+    /// which random pixels do with a chance of about one in a million.
+    /// Payloads of the legacy formats without a length header are not detected
+    #[wasm_bindgen(getter)]
+    pub fn has_payload(&self) -> bool {
+        self.features.is_some()
+    }
+
+    /// `true` if the payload found is encrypted, by one password or by several with a threshold
+    #[wasm_bindgen(getter)]
+    pub fn encrypted(&self) -> bool {
+        self.features.is_some_and(|features| {
+            features.has_feature(PayloadCodecFeatures::AesCrypto)
+                || features.has_feature(PayloadCodecFeatures::ChaCrypto)
+                || features.has_feature(PayloadCodecFeatures::ThresholdCrypto)
+        })
+    }
+}
+
+/// Tells the dimensions, format and capacity of a carrier, and whether a payload is hidden in it already
+#[wasm_bindgen]
+pub fn inspect_carrier(carrier_data: &[u8]) -> Result<CarrierInfo, JsValue> {
+    inspect_carrier_with_options(carrier_data, &HideOptions::new())
+}
+
+/// Same as `inspect_carrier`, with the capacity and overhead for the given options, e.g. with a password or metadata
+#[wasm_bindgen]
+pub fn inspect_carrier_with_options(
+    carrier_data: &[u8],
+    options: &HideOptions,
+) -> Result<CarrierInfo, JsValue> {
+    let overhead = options.payload_size(PROBE_NAME, 0)?;
+
+    if wav::is_wav(carrier_data) {
+        let Media::Audio((_, samples)) = wav::load(carrier_data)? else {
+            unreachable!("a WAV is loaded as audio");
+        };
+        let capacity = wav::capacity(samples.len());
+
+        return Ok(CarrierInfo {
+            width: 0,
+            height: 0,
+            samples: Some(samples.len()),
+            format: "wav".to_string(),
+            capacity,
+            overhead,
+            max_secret_size: max_secret_size(capacity, options)?,
+            features: payload_features(audio::LsbCodec::decoder(&samples), capacity),
+        });
+    }
+
+    let img = decode::load_image(carrier_data)?.to_rgba8();
+    let codec_options = options.codec_options()?;
+    let capacity = LsbCodec::capacity(&img, &codec_options);
+
+    Ok(CarrierInfo {
+        width: img.width(),
+        height: img.height(),
+        samples: None,
+        format: format_of(carrier_data),
+        capacity,
+        overhead,
+        max_secret_size: max_secret_size(capacity, options)?,
+        features: payload_features(LsbCodec::decoder(&img, &codec_options), capacity),
+    })
+}
+
+/// The largest secret whose payload, as `hide_data` estimates it, fits into `capacity` bytes
+fn max_secret_size(capacity: usize, options: &HideOptions) -> Result<usize, JsValue> {
+    if options.payload_size(PROBE_NAME, 0)? > capacity {
+        return Ok(0);
+    }
+
+    let (mut fits, mut too_large) = (0, capacity + 1);
+    while too_large - fits > 1 {
+        let secret_len = fits + (too_large - fits) / 2;
+        if options.payload_size(PROBE_NAME, secret_len)? <= capacity {
+            fits = secret_len;
+        } else {
+            too_large = secret_len;
+        }
+    }
+
+    Ok(fits)
+}
+
+/// The `[width, height]` autoscale would upscale a carrier of the given dimensions to, for a secret of `secret_len` bytes.
+/// The dimensions stay as they are if the secret fits already.
+/// It counts all pixels of an opaque carrier. With an alpha policy, stealth or banding awareness the pixels
+/// left for data depend on the content, see `inspect_carrier_with_options`, autoscale then may upscale further
+#[wasm_bindgen]
+pub fn estimate_required_dimensions(
+    width: u32,
+    height: u32,
+    secret_len: f64,
+    options: &HideOptions,
+) -> Result<Vec<u32>, JsValue> {
+    let payload_size = options.payload_size(PROBE_NAME, secret_len as usize)?;
+    let capacity = lsb_capacity(width, height, options);
+    let (width, height) = if payload_size <= capacity {
+        (width, height)
+    } else {
+        scaled_to_fit(width, height, capacity.max(1), payload_size)
+    };
+
+    Ok(vec![width, height])
+}
+
+fn format_of(data: &[u8]) -> String {
+    if let Some(name) = codecs::decoder_name(data) {
+        return name;
+    }
+    if jxl::is_jxl(data) {
+        return "jxl".to_string();
+    }
+
+    image::guess_format(data)
+        .ok()
+        .and_then(|format| format.extensions_str().first())
+        .unwrap_or(&"unknown")
+        .to_string()
+}
+
+/// The features of the payload header in the LSBs, if its length fits the capacity and the end marker is in place
+fn payload_features(mut lsb: Box<dyn Read + '_>, capacity: usize) -> Option<u8> {
+    let mut header = [0; HEADER_LEN];
+    lsb.read_exact(&mut header).ok()?;
+    let features = header[0];
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if !features.has_feature(PayloadCodecFeatures::LengthHeader)
+        || len > capacity.saturating_sub(HEADER_LEN + 1)
+    {
+        return None;
+    }
+
+    std::io::copy(&mut (&mut lsb).take(len as u64), &mut std::io::sink()).ok()?;
+    let mut end = [0];
+    lsb.read_exact(&mut end).ok()?;
+
+    (end[0] == TERMINATOR).then_some(features)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hide_data_with_options;
+
+    fn carrier() -> Vec<u8> {
+        let mut carrier = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::from_fn(64, 64, |x, y| {
+            image::Rgba([x as u8 * 3, y as u8 * 3, (x + y) as u8, 255])
+        })
+        .write_to(&mut carrier, image::ImageFormat::Png)
+        .unwrap();
+        carrier.into_inner()
+    }
+
+    #[test]
+    fn should_tell_the_capacity_of_a_carrier() {
+        let info = inspect_carrier(&carrier()).unwrap();
+
+        assert_eq!((info.width(), info.height()), (64, 64));
+        assert_eq!(info.format(), "png");
+        assert_eq!(info.capacity(), 1488.0);
+        assert!(info.overhead() > 0.0);
+        assert!(info.max_secret_size() + info.overhead() <= info.capacity());
+        assert!(!info.has_payload());
+    }
+
+    #[test]
+    fn should_hide_a_secret_of_the_max_secret_size_without_autoscale() {
+        let options = HideOptions::with_resize_and_format(false, None);
+        let info = inspect_carrier_with_options(&carrier(), &options).unwrap();
+        let mut secret = vec![0; info.max_secret_size() as usize];
+        getrandom::getrandom(&mut secret).unwrap();
+
+        assert!(info.max_secret_size() > 1000.0);
+        assert!(hide_data_with_options(&carrier(), "secret.bin", secret, &options).is_ok());
+    }
+
+    #[test]
+    fn should_count_only_the_pixels_the_alpha_policy_leaves() {
+        let mut carrier = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::from_fn(64, 64, |x, _| {
+            image::Rgba([x as u8, 100, 200, if x < 32 { 0 } else { 255 }])
+        })
+        .write_to(&mut carrier, image::ImageFormat::Png)
+        .unwrap();
+        let mut options = HideOptions::new();
+        options.set_alpha_policy(Some("opaque_only".to_string()));
+
+        let info = inspect_carrier_with_options(carrier.get_ref(), &options).unwrap();
+        assert_eq!(info.capacity(), (31 * 63 * 3 / 8) as f64);
+
+        options.set_redundancy(3);
+        let info = inspect_carrier_with_options(carrier.get_ref(), &options).unwrap();
+        assert_eq!(info.capacity(), (31 * 63 * 3 / 8 / 3) as f64);
+    }
+
+    #[test]
+    fn should_find_a_hidden_payload() {
+        let mut options = HideOptions::new();
+        let stego = hide_data_with_options(&carrier(), "secret.txt", b"hidden".to_vec(), &options)
+            .unwrap()
+            .into_data();
+        let info = inspect_carrier(&stego).unwrap();
+        assert!(info.has_payload());
+        assert!(!info.encrypted());
+
+        options.set_password(Some("hunter42".to_string()));
+        let stego = hide_data_with_options(&carrier(), "secret.txt", b"hidden".to_vec(), &options)
+            .unwrap()
+            .into_data();
+        let info = inspect_carrier(&stego).unwrap();
+        assert!(info.has_payload());
+        assert!(info.encrypted());
+    }
+
+    #[test]
+    fn should_tell_the_capacity_of_a_wav() {
+        let samples: Vec<i16> = (0..16_000).map(|i| (i % 512) as i16).collect();
+        let info = inspect_carrier(&wav::tests::wav(&samples)).unwrap();
+
+        assert_eq!(info.format(), "wav");
+        assert_eq!(info.samples(), Some(16_000.0));
+        assert_eq!(info.capacity(), 2000.0);
+    }
+
+    #[test]
+    fn should_estimate_the_autoscaled_dimensions() {
+        let options = HideOptions::new();
+
+        assert_eq!(
+            estimate_required_dimensions(100, 50, 10.0, &options).unwrap(),
+            [100, 50]
+        );
+        let dimensions = estimate_required_dimensions(100, 50, 10_000.0, &options).unwrap();
+        let (width, height) = (dimensions[0], dimensions[1]);
+        assert!(
+            lsb_capacity(width, height, &options)
+                >= options.payload_size(PROBE_NAME, 10_000).unwrap()
+        );
+        assert_eq!(width / height, 2, "it should keep the ratio");
+    }
+}
@@ -0,0 +1,236 @@
+//! Decoders and encoders registered from JS, for formats the wasm build does not bring along, e.g. HEIC.
+//! The pixels they hand over run through the same pipeline as any other image.
+
+use std::cell::{Cell, RefCell};
+
+use image::RgbaImage;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::decode;
+
+struct ExternalDecoder {
+    name: String,
+    magic: Vec<u8>,
+    offset: usize,
+    decode: js_sys::Function,
+}
+
+struct ExternalEncoder {
+    id: u32,
+    format: String,
+    encode: js_sys::Function,
+}
+
+thread_local! {
+    static DECODERS: RefCell<Vec<ExternalDecoder>> = const { RefCell::new(Vec::new()) };
+    static ENCODERS: RefCell<Vec<ExternalEncoder>> = const { RefCell::new(Vec::new()) };
+    static NEXT_ENCODER_ID: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Registers `decode` for the images that have the bytes `magic` at `offset`, e.g. `ftypheic` at 4 for HEIC.
+/// It is called with the image as `Uint8Array` and returns an `ImageData`, or any `{ width, height, data }`
+/// with the RGBA pixels in `data`. Decoders are tried before the built-in ones, in the order they were registered.
+///
+/// The pipeline is synchronous, a decoder that returns a `Promise`, e.g. by `createImageBitmap`,
+/// can only be used by `decode_carrier`
+#[wasm_bindgen]
+pub fn register_decoder(name: String, magic: Vec<u8>, offset: u32, decode: js_sys::Function) {
+    DECODERS.with(|decoders| {
+        decoders.borrow_mut().push(ExternalDecoder {
+            name,
+            magic,
+            offset: offset as usize,
+            decode,
+        })
+    });
+}
+
+/// Registers `encode` for the output format `format`, see `HideOptions.output_format`, it replaces a built-in one.
+/// It is called with the RGBA pixels as `Uint8Array`, the width and the height, and returns the encoded image
+/// as `Uint8Array`. The encoding has to be lossless, any change of a pixel destroys the hidden data
+#[wasm_bindgen]
+pub fn register_encoder(format: String, encode: js_sys::Function) {
+    let id = NEXT_ENCODER_ID.with(|next| {
+        let id = next.get();
+        next.set(id.wrapping_add(1));
+        id
+    });
+    ENCODERS.with(|encoders| {
+        let mut encoders = encoders.borrow_mut();
+        encoders.retain(|encoder| !encoder.format.eq_ignore_ascii_case(&format));
+        encoders.push(ExternalEncoder { id, format, encode });
+    });
+}
+
+/// Removes all decoders and encoders registered from JS
+#[wasm_bindgen]
+pub fn clear_codecs() {
+    DECODERS.with(|decoders| decoders.borrow_mut().clear());
+    ENCODERS.with(|encoders| encoders.borrow_mut().clear());
+}
+
+/// Decodes the carrier with a registered decoder, that may return a `Promise`,
+/// and resolves to a lossless PNG of its pixels, to be handed to any other call
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub async fn decode_carrier(carrier_data: Vec<u8>) -> Result<js_sys::Uint8Array, JsValue> {
+    let Some((name, decode)) = decoder_for(&carrier_data) else {
+        return Err(JsValue::from_str(
+            "Failed to decode image: no registered decoder knows the format",
+        ));
+    };
+    let pixels = decode
+        .call1(&JsValue::NULL, &js_sys::Uint8Array::from(&carrier_data[..]))
+        .map_err(|e| decode_failed(&name, e))?;
+    let pixels = match pixels.dyn_into::<js_sys::Promise>() {
+        Ok(promise) => wasm_bindgen_futures::JsFuture::from(promise)
+            .await
+            .map_err(|e| decode_failed(&name, e))?,
+        Err(pixels) => pixels,
+    };
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    to_image(&name, &pixels)?
+        .write_to(&mut png, image::ImageFormat::Png)
+        .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
+
+    Ok(js_sys::Uint8Array::from(&png.get_ref()[..]))
+}
+
+/// The pixels of the image by a registered decoder, `None` if none of them knows its format
+pub(crate) fn decode(data: &[u8]) -> Option<Result<RgbaImage, JsValue>> {
+    let (name, decode) = decoder_for(data)?;
+    let pixels = match decode.call1(&JsValue::NULL, &js_sys::Uint8Array::from(data)) {
+        Ok(pixels) => pixels,
+        Err(e) => return Some(Err(decode_failed(&name, e))),
+    };
+    if pixels.is_instance_of::<js_sys::Promise>() {
+        return Some(Err(JsValue::from_str(&format!(
+            "Failed to decode image: the decoder {} returned a Promise, use `decode_carrier` first",
+            name
+        ))));
+    }
+
+    Some(to_image(&name, &pixels))
+}
+
+/// the name of the registered decoder that knows the format of the image
+pub(crate) fn decoder_name(data: &[u8]) -> Option<String> {
+    decoder_for(data).map(|(name, _)| name)
+}
+
+/// The id of the encoder registered for `format`, see `OutputFormat::External`
+pub(crate) fn encoder_id(format: &str) -> Option<u32> {
+    ENCODERS.with(|encoders| {
+        encoders
+            .borrow()
+            .iter()
+            .find(|encoder| encoder.format.eq_ignore_ascii_case(format))
+            .map(|encoder| encoder.id)
+    })
+}
+
+/// Encodes the image with the registered encoder of the given id
+pub(crate) fn encode(id: u32, img: &RgbaImage) -> Result<Vec<u8>, JsValue> {
+    let Some((format, encode)) = ENCODERS.with(|encoders| {
+        encoders
+            .borrow()
+            .iter()
+            .find(|encoder| encoder.id == id)
+            .map(|encoder| (encoder.format.clone(), encoder.encode.clone()))
+    }) else {
+        return Err(JsValue::from_str(
+            "Failed to encode image: the encoder has been removed",
+        ));
+    };
+
+    let encoded = encode
+        .call3(
+            &JsValue::NULL,
+            &js_sys::Uint8Array::from(img.as_raw().as_slice()),
+            &img.width().into(),
+            &img.height().into(),
+        )
+        .map_err(|e| {
+            JsValue::from_str(&format!(
+                "Failed to encode image as {}: {}",
+                format,
+                reason(e)
+            ))
+        })?;
+    if !encoded.is_instance_of::<js_sys::Uint8Array>() {
+        return Err(JsValue::from_str(&format!(
+            "Failed to encode image as {}: the encoder has to return a Uint8Array",
+            format
+        )));
+    }
+
+    Ok(js_sys::Uint8Array::new(&encoded).to_vec())
+}
+
+/// the first registered decoder whose magic bytes match, the function is cloned to call it without a borrow
+fn decoder_for(data: &[u8]) -> Option<(String, js_sys::Function)> {
+    DECODERS.with(|decoders| {
+        decoders
+            .borrow()
+            .iter()
+            .find(|decoder| has_magic(data, &decoder.magic, decoder.offset))
+            .map(|decoder| (decoder.name.clone(), decoder.decode.clone()))
+    })
+}
+
+fn has_magic(data: &[u8], magic: &[u8], offset: usize) -> bool {
+    let Some(end) = offset.checked_add(magic.len()) else {
+        return false;
+    };
+
+    !magic.is_empty() && data.get(offset..end) == Some(magic)
+}
+
+/// `ImageData` or `{ width, height, data }` as RGBA image, within the decode limits
+fn to_image(name: &str, pixels: &JsValue) -> Result<RgbaImage, JsValue> {
+    let field = |key: &str| js_sys::Reflect::get(pixels, &JsValue::from_str(key));
+    let width = field("width")?.as_f64().unwrap_or_default() as u32;
+    let height = field("height")?.as_f64().unwrap_or_default() as u32;
+    decode::check_dimensions(width, height)?;
+    let data = js_sys::Uint8Array::new(&field("data")?).to_vec();
+
+    RgbaImage::from_raw(width, height, data).ok_or_else(|| {
+        JsValue::from_str(&format!(
+            "Failed to decode image: the decoder {} returned less than {}x{} RGBA pixels",
+            name, width, height
+        ))
+    })
+}
+
+fn decode_failed(name: &str, e: JsValue) -> JsValue {
+    JsValue::from_str(&format!(
+        "Failed to decode image with {}: {}",
+        name,
+        reason(e)
+    ))
+}
+
+/// the message of what a callback threw
+fn reason(e: JsValue) -> String {
+    match e.dyn_ref::<js_sys::Error>() {
+        Some(error) => String::from(error.message()),
+        None => e.as_string().unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_match_the_magic_bytes_at_the_offset() {
+        let heic = b"\0\0\0\x18ftypheic\0\0\0\0";
+
+        assert!(has_magic(heic, b"ftypheic", 4));
+        assert!(!has_magic(heic, b"ftypheic", 0));
+        assert!(!has_magic(heic, b"ftypheic", 12), "beyond the end");
+        assert!(!has_magic(heic, b"", 0), "empty magic matches nothing");
+    }
+}
@@ -0,0 +1,134 @@
+use image::{ImageFormat, RgbaImage};
+use moxcms::{ColorProfile, Layout, TransformOptions};
+use wasm_bindgen::prelude::*;
+
+use crate::output::OutputFormat;
+
+/// the warning when the profile of a carrier gets ignored
+pub(crate) const PROFILE_DROPPED: &str =
+    "The ICC color profile of the carrier was dropped, colors may shift in the output";
+
+/// What happens to the ICC color profile of a carrier, see `HideOptions.color_profile`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColorProfilePolicy {
+    /// the pixels are taken as they are and the profile is dropped
+    Ignore,
+    /// the pixels are converted to sRGB before hiding
+    ConvertToSrgb,
+    /// the pixels are taken as they are and the profile is written into the output
+    Preserve,
+}
+
+impl ColorProfilePolicy {
+    pub(crate) fn parse(policy: Option<&str>) -> Result<Self, JsValue> {
+        match policy {
+            None | Some("") | Some("ignore") => Ok(Self::Ignore),
+            Some("srgb") => Ok(Self::ConvertToSrgb),
+            Some("preserve") => Ok(Self::Preserve),
+            Some(other) => Err(JsValue::from_str(&format!(
+                "Invalid color profile policy: {}",
+                other
+            ))),
+        }
+    }
+
+    /// Applies the policy to a carrier with the given profile, before anything is hidden in it.
+    /// Returns the profile to write into the output, every decision is recorded in `warnings`.
+    pub(crate) fn apply(
+        self,
+        img: &mut RgbaImage,
+        icc_profile: Option<Vec<u8>>,
+        output: OutputFormat,
+        warnings: &mut Vec<String>,
+    ) -> Option<Vec<u8>> {
+        let icc_profile = icc_profile?;
+
+        match self {
+            Self::Ignore => {
+                warnings.push(PROFILE_DROPPED.to_string());
+                None
+            }
+            Self::ConvertToSrgb => {
+                match convert_to_srgb(img, &icc_profile) {
+                    Ok(()) => warnings
+                        .push("The colors of the carrier were converted to sRGB".to_string()),
+                    Err(e) => warnings.push(format!(
+                        "The ICC color profile of the carrier was dropped, it cannot be converted to sRGB: {}",
+                        e
+                    )),
+                }
+                None
+            }
+            Self::Preserve if keeps_icc_profile(output) => {
+                warnings.push(
+                    "The ICC color profile of the carrier was kept in the output".to_string(),
+                );
+                Some(icc_profile)
+            }
+            Self::Preserve => {
+                warnings.push(
+                    "The ICC color profile of the carrier was dropped, the output format cannot hold it"
+                        .to_string(),
+                );
+                None
+            }
+        }
+    }
+}
+
+/// if the profile can be written into the output format
+fn keeps_icc_profile(output: OutputFormat) -> bool {
+    matches!(
+        output,
+        OutputFormat::Image(ImageFormat::Png) | OutputFormat::Image(ImageFormat::WebP)
+    )
+}
+
+fn convert_to_srgb(img: &mut RgbaImage, icc_profile: &[u8]) -> Result<(), moxcms::CmsError> {
+    let source = ColorProfile::new_from_slice(icc_profile)?;
+    let transform = source.create_transform_8bit(
+        Layout::Rgba,
+        &ColorProfile::new_srgb(),
+        Layout::Rgba,
+        TransformOptions::default(),
+    )?;
+
+    let pixels = img.as_raw().clone();
+    transform.transform(&pixels, img)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_record_the_decision_in_the_warnings() {
+        let mut img = RgbaImage::new(4, 4);
+        let mut warnings = Vec::new();
+        let png = OutputFormat::Image(ImageFormat::Png);
+
+        assert_eq!(
+            ColorProfilePolicy::Preserve.apply(&mut img, None, png, &mut warnings),
+            None
+        );
+        assert!(warnings.is_empty(), "no profile, nothing to warn about");
+
+        let kept =
+            ColorProfilePolicy::Preserve.apply(&mut img, Some(b"icc".to_vec()), png, &mut warnings);
+        assert_eq!(kept, Some(b"icc".to_vec()));
+
+        let jxl = OutputFormat::Jxl { effort: 4 };
+        let kept =
+            ColorProfilePolicy::Preserve.apply(&mut img, Some(b"icc".to_vec()), jxl, &mut warnings);
+        assert_eq!(kept, None);
+
+        ColorProfilePolicy::ConvertToSrgb.apply(
+            &mut img,
+            Some(b"icc".to_vec()),
+            png,
+            &mut warnings,
+        );
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[2].contains("cannot be converted"));
+    }
+}
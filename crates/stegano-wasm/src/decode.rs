@@ -0,0 +1,230 @@
+use std::cell::Cell;
+use std::io::Cursor;
+
+use image::{DynamicImage, ImageDecoder, ImageReader, Limits};
+use wasm_bindgen::prelude::*;
+
+use crate::{codecs, jxl, paste};
+
+/// about 512 MB once decoded to RGBA
+const DEFAULT_MAX_PIXELS: u64 = 128 * 1024 * 1024;
+
+const DEFAULT_MAX_SIDE: u32 = 32_768;
+
+/// the widest pixel a decoder allocates, RGBA with 32-bit float channels
+const MAX_BYTES_PER_PIXEL: u64 = 16;
+
+#[derive(Debug, Clone, Copy)]
+struct DecodeLimits {
+    max_pixels: u64,
+    max_side: u32,
+}
+
+const DEFAULT_LIMITS: DecodeLimits = DecodeLimits {
+    max_pixels: DEFAULT_MAX_PIXELS,
+    max_side: DEFAULT_MAX_SIDE,
+};
+
+thread_local! {
+    static LIMITS: Cell<DecodeLimits> = const { Cell::new(DEFAULT_LIMITS) };
+}
+
+/// Limits the size of the images that get decoded, larger ones fail with `ImageTooLarge`
+/// before any pixel is decoded. The defaults are 134,217,728 pixels and 32,768 pixels per side.
+#[wasm_bindgen]
+pub fn set_decode_limits(max_pixels: f64, max_side: u32) {
+    LIMITS.with(|limits| {
+        limits.set(DecodeLimits {
+            max_pixels: max_pixels as u64,
+            max_side,
+        })
+    });
+}
+
+/// Puts the limits of `set_decode_limits` back to the defaults
+pub(crate) fn reset_limits() {
+    LIMITS.with(|limits| limits.set(DEFAULT_LIMITS));
+}
+
+/// The error when an image exceeds the limits of `set_decode_limits`, e.g. a decompression bomb
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct ImageTooLarge {
+    width: u32,
+    height: u32,
+    max_pixels: u64,
+    max_side: u32,
+}
+
+#[wasm_bindgen]
+impl ImageTooLarge {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_pixels(&self) -> f64 {
+        self.max_pixels as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_side(&self) -> u32 {
+        self.max_side
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        format!(
+            "Image too large! {}x{} pixels exceed the limit of {} pixels or {} pixels per side.",
+            self.width, self.height, self.max_pixels, self.max_side
+        )
+    }
+}
+
+/// Fails with `ImageTooLarge` if the header of the image exceeds the decode limits.
+/// Images with an unreadable header pass, decoding them fails later on anyway.
+pub(crate) fn check_limits(data: &[u8]) -> Result<(), JsValue> {
+    match too_large(data) {
+        Some(e) => Err(e.into()),
+        None => Ok(()),
+    }
+}
+
+/// Decodes an image, within the decode limits
+pub(crate) fn load_image(data: &[u8]) -> Result<DynamicImage, JsValue> {
+    load_image_with_profile(data).map(|(img, _)| img)
+}
+
+/// Decodes an image together with its ICC color profile, within the decode limits.
+/// Pasted images, e.g. data URIs or a DIB of the clipboard, are taken as well
+pub(crate) fn load_image_with_profile(
+    data: &[u8],
+) -> Result<(DynamicImage, Option<Vec<u8>>), JsValue> {
+    let data = paste::normalize(data);
+    let data = data.as_ref();
+    if let Some(img) = codecs::decode(data) {
+        return Ok((DynamicImage::ImageRgba8(img?), None));
+    }
+    if jxl::is_jxl(data) {
+        return Ok((DynamicImage::ImageRgba8(jxl::decode(data)?), None));
+    }
+    check_limits(data)?;
+
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
+    reader.limits(image_limits(LIMITS.with(Cell::get)));
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    let img = DynamicImage::from_decoder(decoder)
+        .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
+
+    Ok((img, icc_profile))
+}
+
+/// The limits for the `image` decoders, the allocations are bound by the pixel limit as well
+fn image_limits(decode_limits: DecodeLimits) -> Limits {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(decode_limits.max_side);
+    limits.max_image_height = Some(decode_limits.max_side);
+    limits.max_alloc = Some(decode_limits.max_pixels.saturating_mul(MAX_BYTES_PER_PIXEL));
+    limits
+}
+
+/// The ICC color profile of an image, without decoding it
+pub(crate) fn icc_profile(data: &[u8]) -> Option<Vec<u8>> {
+    ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?
+        .icc_profile()
+        .ok()?
+}
+
+/// Fails with `ImageTooLarge` if an image of the given dimensions exceeds the decode limits
+pub(crate) fn check_dimensions(width: u32, height: u32) -> Result<(), JsValue> {
+    match too_large_dimensions(width, height) {
+        Some(e) => Err(e.into()),
+        None => Ok(()),
+    }
+}
+
+/// The width and height of an image, read from its header
+pub(crate) fn dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+pub(crate) fn too_large(data: &[u8]) -> Option<ImageTooLarge> {
+    let (width, height) = dimensions(data)?;
+
+    too_large_dimensions(width, height)
+}
+
+fn too_large_dimensions(width: u32, height: u32) -> Option<ImageTooLarge> {
+    let DecodeLimits {
+        max_pixels,
+        max_side,
+    } = LIMITS.with(Cell::get);
+
+    let exceeded =
+        width > max_side || height > max_side || width as u64 * height as u64 > max_pixels;
+    exceeded.then_some(ImageTooLarge {
+        width,
+        height,
+        max_pixels,
+        max_side,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_refuse_images_beyond_the_limits() {
+        let mut png = Cursor::new(Vec::new());
+        image::RgbaImage::new(300, 200)
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+        let png = png.into_inner();
+        assert!(too_large(&png).is_none());
+
+        set_decode_limits(50_000.0, 1000);
+        let e = too_large(&png).expect("the pixels exceed the limit");
+        assert_eq!((e.width(), e.height()), (300, 200));
+
+        set_decode_limits(1e9, 250);
+        assert!(too_large(&png).is_some(), "the width exceeds the limit");
+
+        set_decode_limits(DEFAULT_MAX_PIXELS as f64, DEFAULT_MAX_SIDE);
+    }
+
+    #[test]
+    fn should_bound_the_allocations_by_the_pixel_limit() {
+        let limits = image_limits(DecodeLimits {
+            max_pixels: 1000,
+            max_side: 100,
+        });
+        assert_eq!(limits.max_alloc, Some(16_000));
+        assert_eq!(limits.max_image_width, Some(100));
+
+        let limits = image_limits(DecodeLimits {
+            max_pixels: u64::MAX,
+            max_side: 100,
+        });
+        assert_eq!(limits.max_alloc, Some(u64::MAX), "it should not overflow");
+    }
+}
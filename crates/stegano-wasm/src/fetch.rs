@@ -0,0 +1,85 @@
+//! Carriers fetched by the browser from a URL, so that remote images never pass through JS
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{hide_data, unveil_data, HideResult};
+
+/// Same as `hide_data`, but the carrier is fetched from `url` first.
+/// The server has to allow the origin of the webapp by CORS. Resolves to a `HideResult`
+#[wasm_bindgen]
+pub async fn hide_from_url(
+    url: String,
+    secret_name: String,
+    secret_data: Vec<u8>,
+    password: Option<String>,
+    should_resize: bool,
+    output_format_str: Option<String>,
+) -> Result<HideResult, JsValue> {
+    let carrier_data = fetch_carrier(&url).await?;
+    hide_data(
+        &carrier_data,
+        &secret_name,
+        secret_data,
+        password,
+        should_resize,
+        output_format_str,
+    )
+}
+
+/// Same as `unveil_data`, but the carrier is fetched from `url` first.
+/// Resolves to an array of `UnveiledFile`
+#[wasm_bindgen]
+pub async fn unveil_from_url(
+    url: String,
+    password: Option<String>,
+) -> Result<js_sys::Array, JsValue> {
+    let carrier_data = fetch_carrier(&url).await?;
+
+    Ok(unveil_data(&carrier_data, password)?
+        .into_iter()
+        .map(JsValue::from)
+        .collect())
+}
+
+/// The body of the response to `url`, fetched in a window or a worker alike
+async fn fetch_carrier(url: &str) -> Result<Vec<u8>, JsValue> {
+    let global = js_sys::global();
+    let request = if let Some(window) = global.dyn_ref::<web_sys::Window>() {
+        window.fetch_with_str(url)
+    } else if let Some(worker) = global.dyn_ref::<web_sys::WorkerGlobalScope>() {
+        worker.fetch_with_str(url)
+    } else {
+        return Err(JsValue::from_str(
+            "Failed to fetch carrier: the fetch API is not available",
+        ));
+    };
+
+    let response: web_sys::Response = JsFuture::from(request)
+        .await
+        .map_err(fetch_failed)?
+        .dyn_into()?;
+    if !response.ok() {
+        return Err(JsValue::from_str(&format!(
+            "Failed to fetch carrier: HTTP {} {}",
+            response.status(),
+            response.status_text()
+        )));
+    }
+    let body = JsFuture::from(response.array_buffer().map_err(fetch_failed)?)
+        .await
+        .map_err(fetch_failed)?;
+
+    Ok(js_sys::Uint8Array::new(&body).to_vec())
+}
+
+/// the browser rejects with a `TypeError` on network and CORS errors
+fn fetch_failed(e: JsValue) -> JsValue {
+    let reason = match e.dyn_ref::<js_sys::Error>() {
+        Some(error) => String::from(error.message()),
+        None => e.as_string().unwrap_or_default(),
+    };
+
+    JsValue::from_str(&format!("Failed to fetch carrier: {}", reason))
+}
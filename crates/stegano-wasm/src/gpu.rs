@@ -0,0 +1,212 @@
+use image::RgbaImage;
+use stegano_core::media::image::lsb_codec::Concealer;
+use stegano_core::media::Media;
+use stegano_core::{AlphaPolicy, CodecOptions};
+use wasm_bindgen::prelude::*;
+use wgpu::util::DeviceExt;
+use zeroize::Zeroizing;
+
+use crate::options::HideOptions;
+use crate::{carrier_for, encoder_for, Carrier, HideResult};
+
+/// invocations per workgroup, as declared in the shader
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Same as `hide_data_with_options`, but the bits are scattered into the carrier by a WebGPU compute shader.
+/// Resolves to a `HideResult`, if the browser offers no WebGPU adapter or the carrier exceeds its limits,
+/// the data is hidden on the CPU instead.
+#[wasm_bindgen]
+pub fn hide_data_gpu(
+    carrier_data: &[u8],
+    secret_name: &str,
+    secret_data: Vec<u8>,
+    options: &HideOptions,
+) -> Result<js_sys::Promise, JsValue> {
+    let secret_data = Zeroizing::new(secret_data);
+    let payload = encoder_for(secret_name, &secret_data, options)?
+        .encode_payload()
+        .map_err(|e| JsValue::from_str(&format!("Failed to encode payload: {}", e)))?;
+    let payload = Zeroizing::new(payload);
+    let Carrier {
+        mut media,
+        icc_profile,
+        warnings,
+        ..
+    } = carrier_for(
+        carrier_data,
+        options.payload_size(secret_name, secret_data.len())?,
+        options,
+    )?;
+    let codec_options = options.codec_options()?;
+    if codec_options.sanitize_lsb_plane {
+        media
+            .sanitize_lsb_plane(&codec_options)
+            .map_err(|e| JsValue::from_str(&format!("Failed to hide data: {}", e)))?;
+    }
+    let format = options.output()?;
+
+    Ok(wasm_bindgen_futures::future_to_promise(async move {
+        let on_gpu = match &mut media {
+            Media::Image(img) => embed(img, &payload, &codec_options).await?,
+            Media::Audio(_) => false,
+        };
+        if !on_gpu {
+            media
+                .hide_data_at(&payload, 0, &codec_options)
+                .map_err(|e| JsValue::from_str(&format!("Failed to hide data: {}", e)))?;
+        }
+
+        let output = format.encode(&mut media, icc_profile.as_deref())?;
+
+        Ok(HideResult::new(output, warnings).into())
+    }))
+}
+
+/// Hides `payload` in `img` on the GPU, returns `false` if the CPU has to do it instead
+async fn embed(img: &mut RgbaImage, payload: &[u8], opts: &CodecOptions) -> Result<bool, JsValue> {
+    if opts.color_channel_step_increment != 1
+        || opts.concealer != Concealer::LeastSignificantBit
+        || opts.alpha_policy != AlphaPolicy::AllPixels
+        || opts.stealth_level != 0
+        || opts.banding_aware
+        || opts.redundancy > 1
+    {
+        return Ok(false);
+    }
+    let (width, height) = img.dimensions();
+    let (used_width, used_height) = if opts.skip_last_row_and_column {
+        (width.saturating_sub(1), height.saturating_sub(1))
+    } else {
+        (width, height)
+    };
+    let channels = if opts.skip_alpha_channel { 3 } else { 4 };
+    let capacity = used_width as u64 * used_height as u64 * channels;
+    let bits = payload.len() as u64 * 8;
+    if bits > capacity || bits > u32::MAX as u64 {
+        // the CPU path reports the capacity error
+        return Ok(false);
+    }
+
+    let instance = wgpu::Instance::default();
+    let Ok(adapter) = instance
+        .request_adapter(&wgpu::RequestAdapterOptions::default())
+        .await
+    else {
+        return Ok(false);
+    };
+    let limits = adapter.limits();
+    let pixel_bytes = img.len() as u64;
+    if pixel_bytes > limits.max_storage_buffer_binding_size || pixel_bytes > limits.max_buffer_size
+    {
+        return Ok(false);
+    }
+    let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            label: Some("stegano"),
+            required_limits: limits.clone(),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| JsValue::from_str(&format!("Failed to open the GPU: {}", e)))?;
+
+    let pixel_count = used_width * used_height;
+    let groups = pixel_count.div_ceil(WORKGROUP_SIZE);
+    let groups_x = groups.min(limits.max_compute_workgroups_per_dimension);
+    let groups_y = groups.div_ceil(groups_x.max(1));
+
+    let params = [
+        width,
+        used_width,
+        used_height,
+        channels as u32,
+        bits as u32,
+        groups_x,
+    ];
+    let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("params"),
+        contents: &words_to_bytes(&params),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+    let pixels = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("pixels"),
+        contents: img.as_raw(),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    });
+    // storage buffers are read in words, so the payload is padded to a multiple of 4 bytes
+    let mut payload_words = Zeroizing::new(payload.to_vec());
+    payload_words.resize(payload.len().div_ceil(4).max(1) * 4, 0);
+    let payload_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("payload"),
+        contents: &payload_words,
+        usage: wgpu::BufferUsages::STORAGE,
+    });
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("readback"),
+        size: pixel_bytes,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("embed"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("gpu/embed.wgsl").into()),
+    });
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("embed"),
+        layout: None,
+        module: &shader,
+        entry_point: Some("main"),
+        compilation_options: Default::default(),
+        cache: None,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("embed"),
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: pixels.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: payload_buffer.as_entire_binding(),
+            },
+        ],
+    });
+
+    let mut commands = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut pass = commands.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        pass.set_pipeline(&pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups(groups_x, groups_y, 1);
+    }
+    commands.copy_buffer_to_buffer(&pixels, 0, &readback, 0, pixel_bytes);
+    queue.submit([commands.finish()]);
+
+    let (sender, receiver) = futures_channel::oneshot::channel();
+    readback.map_async(wgpu::MapMode::Read, .., move |result| {
+        let _ = sender.send(result);
+    });
+    receiver
+        .await
+        .map_err(|_| JsValue::from_str("Failed to read back from the GPU: device lost"))?
+        .map_err(|e| JsValue::from_str(&format!("Failed to read back from the GPU: {}", e)))?;
+    {
+        let view = readback
+            .get_mapped_range(..)
+            .map_err(|e| JsValue::from_str(&format!("Failed to read back from the GPU: {}", e)))?;
+        img.copy_from_slice(&view);
+    }
+    readback.unmap();
+
+    Ok(true)
+}
+
+fn words_to_bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
@@ -0,0 +1,46 @@
+// Hides one bit per color channel in the least significant bit, in the same order as the CPU path:
+// column by column, top to bottom, red, green, blue (and alpha unless skipped) of every pixel.
+
+struct Params {
+    // width of the whole image
+    width: u32,
+    // width and height that carry data, the last row and column may be skipped
+    used_width: u32,
+    used_height: u32,
+    // color channels per pixel that carry data, 3 if alpha is skipped
+    channels: u32,
+    // amount of payload bits
+    bits: u32,
+    // workgroups dispatched along x, to turn 2d dispatches into a pixel index
+    groups_x: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> pixels: array<u32>;
+@group(0) @binding(2) var<storage, read> payload: array<u32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    // index of the pixel in column major order
+    let i = id.x + id.y * params.groups_x * 64u;
+    if (i >= params.used_width * params.used_height) {
+        return;
+    }
+    let first_bit = i * params.channels;
+    if (first_bit >= params.bits) {
+        return;
+    }
+
+    let x = i / params.used_height;
+    let y = i % params.used_height;
+    let idx = y * params.width + x;
+    var pixel = pixels[idx];
+    for (var c = 0u; c < params.channels; c++) {
+        let k = first_bit + c;
+        if (k < params.bits) {
+            let bit = (payload[k / 32u] >> (k % 32u)) & 1u;
+            pixel = (pixel & ~(1u << (8u * c))) | (bit << (8u * c));
+        }
+    }
+    pixels[idx] = pixel;
+}
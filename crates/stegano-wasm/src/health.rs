@@ -0,0 +1,154 @@
+//! Recovery after a panic, that traps the wasm instance in the browser.
+//!
+//! The module keeps little state of its own: the secrets registry, the decode limits and the registered codecs.
+//! Everything else lives in objects held by JS, that can be rebuilt in a new instance, e.g. after a worker restart:
+//! a `KeyHandle` by `derive_key` from the password, a `ResumableHide` by `ResumableHide.resume` from its snapshot,
+//! the options by `UnveilOptions.from_config`. The decode limits have to be set again by `set_decode_limits`,
+//! the codecs by `register_decoder` and `register_encoder`.
+
+use std::cell::Cell;
+use std::sync::Once;
+
+use wasm_bindgen::prelude::*;
+
+use crate::codecs::clear_codecs;
+use crate::options::HideOptions;
+use crate::{decode, hide_data_with_options, secrets, unveil_data, wipe_secrets};
+
+thread_local! {
+    /// set by the panic hook, a panic aborts in the middle of whatever it was doing
+    static PANICKED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Logs panics to the console like `console_error_panic_hook`, and remembers them for `health_check`
+pub(crate) fn set_panic_hook() {
+    static SET_HOOK: Once = Once::new();
+    SET_HOOK.call_once(|| {
+        std::panic::set_hook(Box::new(|info| {
+            PANICKED.with(|panicked| panicked.set(true));
+            console_error_panic_hook::hook(info);
+        }));
+    });
+}
+
+/// The outcome of `health_check`
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct Health {
+    problems: Vec<String>,
+    live_secrets: u32,
+}
+
+#[wasm_bindgen]
+impl Health {
+    /// `false` if the module has to be instantiated anew, e.g. by restarting its worker
+    #[wasm_bindgen(getter)]
+    pub fn ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// what is broken, for humans
+    #[wasm_bindgen(getter)]
+    pub fn problems(&self) -> Vec<String> {
+        self.problems.clone()
+    }
+
+    /// the number of passwords and unveiled files that are still held in the wasm memory
+    #[wasm_bindgen(getter)]
+    pub fn live_secrets(&self) -> u32 {
+        self.live_secrets
+    }
+}
+
+/// Tells if the module is still usable, by checking its state and hiding and unveiling a tiny image.
+/// A panic, see `init_panic_hook`, is reported until `reset` or until the module is instantiated anew
+#[wasm_bindgen]
+pub fn health_check() -> Health {
+    let mut problems = Vec::new();
+    if PANICKED.with(Cell::get) {
+        problems.push("A panic aborted an earlier call".to_string());
+    }
+    let live_secrets = secrets::count();
+    if live_secrets.is_none() {
+        problems.push("The secrets registry was left in the middle of a change".to_string());
+    }
+    if let Err(e) = round_trip() {
+        problems.push(format!("Failed to hide and unveil a test image: {}", e));
+    }
+
+    Health {
+        problems,
+        live_secrets: live_secrets.unwrap_or_default() as u32,
+    }
+}
+
+/// Brings the module back to its initial state: all secrets are wiped, see `wipe_secrets`,
+/// the decode limits are back to their defaults, the registered codecs are removed and an earlier panic is forgotten.
+/// Returns the `health_check` afterwards, if it still fails the module has to be instantiated anew
+#[wasm_bindgen]
+pub fn reset() -> Health {
+    if secrets::count().is_some() {
+        wipe_secrets();
+    }
+    decode::reset_limits();
+    clear_codecs();
+    PANICKED.with(|panicked| panicked.set(false));
+
+    health_check()
+}
+
+fn round_trip() -> Result<(), String> {
+    let message = |e: JsValue| e.as_string().unwrap_or_default();
+    let mut carrier = std::io::Cursor::new(Vec::new());
+    image::RgbaImage::from_pixel(64, 64, image::Rgba([128, 128, 128, 255]))
+        .write_to(&mut carrier, image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+
+    let stego = hide_data_with_options(
+        carrier.get_ref(),
+        "health.txt",
+        b"ok".to_vec(),
+        &HideOptions::new(),
+    )
+    .map_err(message)?
+    .into_data();
+    let files = unveil_data(&stego, None).map_err(message)?;
+
+    match files
+        .first()
+        .and_then(|file| file.data.with_bytes(|data| data == b"ok"))
+    {
+        Some(true) => Ok(()),
+        _ => Err("the unveiled data differs".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::Secret;
+
+    #[test]
+    fn should_be_healthy_after_a_reset() {
+        let secret = Secret::new(b"hunter42".to_vec());
+        decode::set_decode_limits(1.0, 1);
+
+        let health = reset();
+        assert!(health.ok(), "{:?}", health.problems());
+        assert_eq!(health.live_secrets(), 0);
+        assert_eq!(secret.with_bytes(|b| b.to_vec()), None);
+    }
+
+    #[test]
+    fn should_report_an_earlier_panic_until_a_reset() {
+        PANICKED.with(|panicked| panicked.set(true));
+
+        let health = health_check();
+        assert!(!health.ok());
+        assert_eq!(health.problems(), ["A panic aborted an earlier call"]);
+
+        let health = reset();
+        assert!(health.ok(), "{:?}", health.problems());
+        assert!(health_check().ok());
+    }
+}
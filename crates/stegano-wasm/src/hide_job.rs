@@ -0,0 +1,254 @@
+use stegano_core::SteganoEncoder;
+use wasm_bindgen::prelude::*;
+
+use crate::options::HideOptions;
+use crate::secrets::Secret;
+use crate::{hide_with, HideResult};
+
+/// Hides several files in one carrier, created in JS with `new HideJob()`.
+/// The files are unveiled in the order they were added, see `unveil_data`.
+///
+/// Like passwords, the file contents are kept with the other secrets and wiped by `wipe_secrets`.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct HideJob {
+    files: Vec<JobFile>,
+}
+
+#[derive(Debug)]
+struct JobFile {
+    name: String,
+    data: Secret,
+    len: usize,
+    comment: Option<String>,
+}
+
+#[wasm_bindgen]
+impl HideJob {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> HideJob {
+        HideJob::default()
+    }
+
+    /// Adds a file to hide, optionally with a comment that labels it.
+    /// Folders are kept, e.g. `notes/todo.txt`, a name can only be added once
+    pub fn add_file(
+        &mut self,
+        name: String,
+        data: Vec<u8>,
+        comment: Option<String>,
+    ) -> Result<(), JsValue> {
+        if name.trim().is_empty() {
+            return Err(JsValue::from_str("Invalid file name: the name is empty"));
+        }
+        if self.files.iter().any(|file| file.name == name) {
+            return Err(JsValue::from_str(&format!(
+                "Invalid file name: {} has been added already",
+                name
+            )));
+        }
+
+        self.files.push(JobFile {
+            name,
+            len: data.len(),
+            data: Secret::new(data),
+            comment,
+        });
+
+        Ok(())
+    }
+
+    /// the number of files added so far
+    #[wasm_bindgen(getter)]
+    pub fn file_count(&self) -> u32 {
+        self.files.len() as u32
+    }
+
+    /// The size of the payload the files take in the carrier with the settings of the options,
+    /// headers included, as if none of them could be compressed
+    pub fn payload_size(&self, options: &HideOptions) -> Result<f64, JsValue> {
+        self.payload_len(options).map(|len| len as f64)
+    }
+
+    /// Hides all files added so far in the carrier, with the settings of the options.
+    /// Autoscale accounts for the combined size of the files, see `HideOptions.resize`
+    pub fn execute(
+        &self,
+        carrier_data: &[u8],
+        options: &HideOptions,
+    ) -> Result<HideResult, JsValue> {
+        if self.files.is_empty() {
+            return Err(JsValue::from_str(
+                "Failed to hide data: no file has been added",
+            ));
+        }
+
+        hide_with(
+            carrier_data,
+            self.encoder(options)?,
+            self.payload_len(options)?,
+            options,
+        )
+    }
+}
+
+impl HideJob {
+    /// builds an encoder that holds all files, set up by the options
+    fn encoder(&self, options: &HideOptions) -> Result<SteganoEncoder, JsValue> {
+        let mut encoder = options.encoder()?;
+        for file in &self.files {
+            file.data
+                .with_bytes(|data| encoder.add_file_from_memory(&file.name, data).map(|_| ()))
+                .ok_or_else(|| {
+                    JsValue::from_str(&format!(
+                        "Failed to hide data: {} has been wiped",
+                        file.name
+                    ))
+                })?
+                .map_err(|e| JsValue::from_str(&format!("Failed to add memory file: {}", e)))?;
+            if let Some(comment) = &file.comment {
+                encoder.with_file_comment(&file.name, comment.as_str());
+            }
+        }
+
+        Ok(encoder)
+    }
+
+    /// the payload size of the files, measured on their headers, see `HideOptions::files_payload_size`
+    fn payload_len(&self, options: &HideOptions) -> Result<usize, JsValue> {
+        options.files_payload_size(
+            self.files
+                .iter()
+                .map(|file| (file.name.as_str(), file.comment.as_deref(), file.len)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unveil_data;
+    use stegano_core::media::image::LsbCodec;
+
+    /// large enough for `job`, with its estimated overhead
+    fn carrier() -> Vec<u8> {
+        carrier_of(128)
+    }
+
+    fn carrier_of(side: u32) -> Vec<u8> {
+        let mut carrier = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::from_pixel(side, side, image::Rgba([90, 120, 150, 255]))
+            .write_to(&mut carrier, image::ImageFormat::Png)
+            .unwrap();
+        carrier.into_inner()
+    }
+
+    fn job() -> HideJob {
+        let mut job = HideJob::new();
+        job.add_file("first.txt".to_string(), b"first".to_vec(), None)
+            .unwrap();
+        job.add_file(
+            "notes/second.txt".to_string(),
+            b"second".to_vec(),
+            Some("the second one".to_string()),
+        )
+        .unwrap();
+        job.add_file("third.bin".to_string(), vec![3; 300], None)
+            .unwrap();
+        job
+    }
+
+    fn unveiled(stego: &[u8], password: Option<String>) -> Vec<(String, Vec<u8>)> {
+        unveil_data(stego, password)
+            .unwrap()
+            .iter()
+            .map(|file| (file.name(), file.data.with_bytes(<[u8]>::to_vec).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn should_hide_and_unveil_several_files() {
+        let job = job();
+        assert_eq!(job.file_count(), 3);
+
+        let stego = job
+            .execute(&carrier(), &HideOptions::new())
+            .unwrap()
+            .into_data();
+
+        assert_eq!(
+            unveiled(&stego, None),
+            [
+                ("first.txt".to_string(), b"first".to_vec()),
+                ("notes/second.txt".to_string(), b"second".to_vec()),
+                ("third.bin".to_string(), vec![3; 300]),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_hide_and_unveil_several_files_with_a_password() {
+        let mut options = HideOptions::new();
+        options.set_password(Some("hunter42".to_string()));
+
+        let stego = job().execute(&carrier(), &options).unwrap().into_data();
+
+        let files = unveiled(&stego, Some("hunter42".to_string()));
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[2], ("third.bin".to_string(), vec![3; 300]));
+    }
+
+    #[test]
+    fn should_upscale_the_carrier_for_the_combined_size() {
+        let mut options = HideOptions::new();
+        options.set_resize(true);
+        let mut job = job();
+        job.add_file("large.bin".to_string(), vec![7; 4000], None)
+            .unwrap();
+
+        let stego = job.execute(&carrier_of(64), &options).unwrap().into_data();
+
+        let img = image::load_from_memory(&stego).unwrap();
+        assert!(img.width() > 64);
+        assert_eq!(unveiled(&stego, None).len(), 4);
+    }
+
+    #[test]
+    fn should_fill_the_carrier_to_the_estimated_payload_size() {
+        let options = HideOptions::new();
+        let mut job = HideJob::new();
+        for (i, len) in [70_000, 1, 5_000].into_iter().enumerate() {
+            let mut data = vec![0; len];
+            getrandom::getrandom(&mut data).unwrap();
+            let comment = format!("file {} of three, each with a comment of its own", i);
+            job.add_file(format!("folder/file-{}.bin", i), data, Some(comment))
+                .unwrap();
+        }
+
+        let estimate = job.payload_len(&options).unwrap();
+        let payload = job.encoder(&options).unwrap().encode_payload().unwrap();
+        assert!(
+            payload.len() <= estimate,
+            "{} > {}",
+            payload.len(),
+            estimate
+        );
+
+        // the smallest carrier that holds the estimate, it cannot be upscaled
+        let codec_options = options.codec_options().unwrap();
+        let side = (64..)
+            .find(|&side| {
+                let img =
+                    image::RgbaImage::from_pixel(side, side, image::Rgba([90, 120, 150, 255]));
+                LsbCodec::capacity(&img, &codec_options) >= estimate
+            })
+            .unwrap();
+        let stego = job
+            .execute(&carrier_of(side), &options)
+            .unwrap()
+            .into_data();
+
+        assert_eq!(image::load_from_memory(&stego).unwrap().width(), side);
+        assert_eq!(unveiled(&stego, None).len(), 3);
+    }
+}
@@ -0,0 +1,318 @@
+use stegano_core::api::inspect::{self, FileEntry, FilePreview, InspectApi};
+use stegano_core::{Metadata, TimestampToken};
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroize;
+
+use crate::key_handle::KeyHandle;
+use crate::load_secret_media;
+use crate::secrets::Secret;
+
+/// Tells what is hidden inside of a carrier, without handing out the file contents
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct Inspection {
+    files: Vec<FileEntry>,
+    metadata: Option<Metadata>,
+    timestamp: Option<TimestampToken>,
+}
+
+#[wasm_bindgen]
+impl Inspection {
+    /// names of the hidden files
+    #[wasm_bindgen(getter)]
+    pub fn file_names(&self) -> Vec<String> {
+        self.files.iter().map(|file| file.name.clone()).collect()
+    }
+
+    /// names of the hidden files as they can be saved on any platform, in the same order as `file_names`,
+    /// see `UnveiledFile.display_name`
+    #[wasm_bindgen(getter)]
+    pub fn display_names(&self) -> Vec<String> {
+        self.files.iter().map(|file| file.display_name()).collect()
+    }
+
+    /// sizes in bytes of the hidden files, in the same order as `file_names`,
+    /// as doubles so that files of 4 GB and more are not cut off
+    #[wasm_bindgen(getter)]
+    pub fn file_sizes(&self) -> Vec<f64> {
+        self.files.iter().map(|file| file.size as f64).collect()
+    }
+
+    /// `true` if a metadata record was hidden along with the files
+    #[wasm_bindgen(getter)]
+    pub fn has_metadata(&self) -> bool {
+        self.metadata.is_some()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn author(&self) -> Option<String> {
+        self.metadata.as_ref()?.author.clone()
+    }
+
+    /// creation time in milliseconds, ready for `new Date(...)`
+    #[wasm_bindgen(getter)]
+    pub fn created_at(&self) -> Option<f64> {
+        self.metadata
+            .as_ref()?
+            .created_at
+            .map(|s| s as f64 * 1000.0)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn app_version(&self) -> Option<String> {
+        self.metadata.as_ref()?.app_version.clone()
+    }
+
+    /// `true` if a RFC 3161 timestamp token was hidden along with the files.
+    /// Its structure is checked, the signature of the TSA is not, e.g. use `openssl ts -verify` on `timestamp_token`
+    #[wasm_bindgen(getter)]
+    pub fn has_timestamp(&self) -> bool {
+        self.timestamp.is_some()
+    }
+
+    /// the time the TSA signed in milliseconds, ready for `new Date(...)`
+    #[wasm_bindgen(getter)]
+    pub fn timestamp_time(&self) -> Option<f64> {
+        Some(self.timestamp.as_ref()?.info().gen_time as f64 * 1000.0)
+    }
+
+    /// the hash algorithm of the timestamped document as dotted OID, `2.16.840.1.101.3.4.2.1` is SHA-256
+    #[wasm_bindgen(getter)]
+    pub fn timestamp_hash_algorithm(&self) -> Option<String> {
+        Some(self.timestamp.as_ref()?.info().hash_algorithm.clone())
+    }
+
+    /// the hash of the timestamped document as lowercase hex,
+    /// for SHA-256 it can be compared with `UnveiledFile.sha256`
+    #[wasm_bindgen(getter)]
+    pub fn timestamp_imprint(&self) -> Option<String> {
+        Some(self.timestamp.as_ref()?.info().hashed_message_hex())
+    }
+
+    /// the token as DER
+    #[wasm_bindgen(getter)]
+    pub fn timestamp_token(&self) -> Option<Vec<u8>> {
+        Some(self.timestamp.as_ref()?.as_der().to_vec())
+    }
+}
+
+/// Inspects the hidden data, including its metadata record
+#[wasm_bindgen]
+pub fn inspect_data(carrier_data: &[u8], password: Option<String>) -> Result<Inspection, JsValue> {
+    inspect_with(inspect::prepare().using_password(password), carrier_data)
+}
+
+/// Same as `inspect_data`, but the password is taken from a `KeyHandle`
+#[wasm_bindgen]
+pub fn inspect_data_with_key(carrier_data: &[u8], key: &KeyHandle) -> Result<Inspection, JsValue> {
+    inspect_with(
+        inspect::prepare().using_password(key.password()?.as_str()),
+        carrier_data,
+    )
+}
+
+fn inspect_with(inspect: InspectApi, carrier_data: &[u8]) -> Result<Inspection, JsValue> {
+    let toc = inspect
+        .from_media(load_secret_media(carrier_data)?)
+        .execute()
+        .map_err(|e| JsValue::from_str(&format!("Failed to inspect: {}", e)))?;
+
+    Ok(Inspection {
+        files: toc.files,
+        metadata: toc.metadata,
+        timestamp: toc.timestamp,
+    })
+}
+
+/// A hidden file as listed by `list_hidden_files`, without its contents
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct HiddenFile {
+    entry: FileEntry,
+}
+
+#[wasm_bindgen]
+impl HiddenFile {
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.entry.name.clone()
+    }
+
+    /// the name as it can be saved on any platform, see `UnveiledFile.display_name`
+    #[wasm_bindgen(getter)]
+    pub fn display_name(&self) -> String {
+        self.entry.display_name()
+    }
+
+    /// the size in bytes once unveiled
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> f64 {
+        self.entry.size as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn comment(&self) -> Option<String> {
+        self.entry.comment.clone()
+    }
+}
+
+/// Lists the hidden files with their sizes, in the order they were hidden in.
+/// Nothing is decompressed or copied to JS, so it stays fast for large archives
+#[wasm_bindgen]
+pub fn list_hidden_files(
+    carrier_data: &[u8],
+    password: Option<String>,
+) -> Result<Vec<HiddenFile>, JsValue> {
+    Ok(hidden_files(inspect_data(carrier_data, password)?))
+}
+
+/// Same as `list_hidden_files`, with the password taken from a `KeyHandle`
+#[wasm_bindgen]
+pub fn list_hidden_files_with_key(
+    carrier_data: &[u8],
+    key: &KeyHandle,
+) -> Result<Vec<HiddenFile>, JsValue> {
+    Ok(hidden_files(inspect_data_with_key(carrier_data, key)?))
+}
+
+fn hidden_files(inspection: Inspection) -> Vec<HiddenFile> {
+    inspection
+        .files
+        .into_iter()
+        .map(|entry| HiddenFile { entry })
+        .collect()
+}
+
+/// Finds the names of the hidden files that match the pattern, without extracting anything.
+/// The pattern is a glob like `*.pdf` or `docs/**`, or a regex wrapped in slashes like `/^report-\d+/`.
+#[wasm_bindgen]
+pub fn find_files(
+    carrier_data: &[u8],
+    password: Option<String>,
+    pattern: &str,
+) -> Result<Vec<String>, JsValue> {
+    find_files_with(
+        inspect::prepare().using_password(password),
+        carrier_data,
+        pattern,
+    )
+}
+
+/// Same as `find_files`, with the password taken from a `KeyHandle`
+#[wasm_bindgen]
+pub fn find_files_with_key(
+    carrier_data: &[u8],
+    key: &KeyHandle,
+    pattern: &str,
+) -> Result<Vec<String>, JsValue> {
+    find_files_with(
+        inspect::prepare().using_password(key.password()?.as_str()),
+        carrier_data,
+        pattern,
+    )
+}
+
+fn find_files_with(
+    inspect: InspectApi,
+    carrier_data: &[u8],
+    pattern: &str,
+) -> Result<Vec<String>, JsValue> {
+    inspect
+        .from_media(load_secret_media(carrier_data)?)
+        .find_files(pattern)
+        .map_err(|e| JsValue::from_str(&format!("Failed to find files: {}", e)))
+}
+
+/// The first bytes of a hidden file from `unveil_preview`, zeroized when freed or by `wipe_secrets`
+#[wasm_bindgen]
+pub struct UnveiledPreview {
+    name: String,
+    size: u64,
+    comment: Option<String>,
+    head: Secret,
+}
+
+#[wasm_bindgen]
+impl UnveiledPreview {
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// the size in bytes of the whole file, not only of its head
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> f64 {
+        self.size as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn comment(&self) -> Option<String> {
+        self.comment.clone()
+    }
+
+    /// the first bytes of the file
+    #[wasm_bindgen(getter)]
+    pub fn head(&self) -> js_sys::Uint8Array {
+        self.head
+            .with_bytes(|bytes| js_sys::Uint8Array::from(bytes))
+            .unwrap_or_else(|| js_sys::Uint8Array::new_with_length(0))
+    }
+}
+
+impl Drop for UnveiledPreview {
+    fn drop(&mut self) {
+        self.name.zeroize();
+        self.comment.zeroize();
+    }
+}
+
+/// Unveils only the first `max_bytes_per_file` bytes of each hidden file, enough for thumbnails or text snippets.
+/// The rest of the files is never decompressed, which keeps the memory low for large archives.
+#[wasm_bindgen]
+pub fn unveil_preview(
+    carrier_data: &[u8],
+    password: Option<String>,
+    max_bytes_per_file: u32,
+) -> Result<Vec<UnveiledPreview>, JsValue> {
+    previews_with(
+        inspect::prepare().using_password(password),
+        carrier_data,
+        max_bytes_per_file,
+    )
+}
+
+/// Same as `unveil_preview`, with the password taken from a `KeyHandle`
+#[wasm_bindgen]
+pub fn unveil_preview_with_key(
+    carrier_data: &[u8],
+    key: &KeyHandle,
+    max_bytes_per_file: u32,
+) -> Result<Vec<UnveiledPreview>, JsValue> {
+    previews_with(
+        inspect::prepare().using_password(key.password()?.as_str()),
+        carrier_data,
+        max_bytes_per_file,
+    )
+}
+
+fn previews_with(
+    inspect: InspectApi,
+    carrier_data: &[u8],
+    max_bytes_per_file: u32,
+) -> Result<Vec<UnveiledPreview>, JsValue> {
+    let previews = inspect
+        .from_media(load_secret_media(carrier_data)?)
+        .previews(max_bytes_per_file as usize)
+        .map_err(|e| JsValue::from_str(&format!("Failed to unveil: {}", e)))?;
+
+    Ok(previews.into_iter().map(unveiled_preview).collect())
+}
+
+fn unveiled_preview(mut preview: FilePreview) -> UnveiledPreview {
+    UnveiledPreview {
+        name: std::mem::take(&mut preview.entry.name),
+        size: preview.entry.size,
+        comment: preview.entry.comment.take(),
+        head: Secret::new(std::mem::take(&mut preview.head)),
+    }
+}
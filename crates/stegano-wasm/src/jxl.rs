@@ -0,0 +1,127 @@
+//! JPEG XL carriers, decoded by jxl-oxide, that the `image` crate does not know
+//! and has no integration for in the version at hand.
+
+use std::io::Cursor;
+
+use image::RgbaImage;
+use jxl_oxide::{JxlImage, RenderResult};
+use wasm_bindgen::prelude::*;
+
+use crate::decode;
+
+/// a bare codestream starts with `FF 0A`, the ISO BMFF container with a `JXL ` box
+const CODESTREAM_SIGNATURE: &[u8] = &[0xff, 0x0a];
+const CONTAINER_SIGNATURE: &[u8] = &[
+    0x00, 0x00, 0x00, 0x0c, b'J', b'X', b'L', b' ', 0x0d, 0x0a, 0x87, 0x0a,
+];
+
+pub(crate) fn is_jxl(data: &[u8]) -> bool {
+    data.starts_with(CODESTREAM_SIGNATURE) || data.starts_with(CONTAINER_SIGNATURE)
+}
+
+/// Decodes the first frame of a JPEG XL image, within the decode limits.
+/// Lossless images come out with their exact pixels, as hidden data needs it
+pub(crate) fn decode(data: &[u8]) -> Result<RgbaImage, JsValue> {
+    let failed = |e: &dyn std::fmt::Display| {
+        JsValue::from_str(&format!("Failed to load JPEG XL image: {}", e))
+    };
+    let mut image = JxlImage::from_reader(Cursor::new(data)).map_err(|e| failed(&e))?;
+    let size = &image.image_header().size;
+    decode::check_dimensions(size.width, size.height)?;
+
+    let render = match image.render_next_frame().map_err(|e| failed(&e))? {
+        RenderResult::Done(render) => render,
+        RenderResult::NeedMoreData => return Err(failed(&"the file is truncated")),
+        RenderResult::NoMoreFrames => return Err(failed(&"the image has no frame")),
+    };
+    let frame = render.image();
+
+    to_rgba(
+        frame.width() as u32,
+        frame.height() as u32,
+        frame.channels(),
+        frame.buf(),
+    )
+    .ok_or_else(|| failed(&format!("{} channels are not supported", frame.channels())))
+}
+
+/// interleaved samples from 0.0 to 1.0 of gray, gray and alpha, RGB or RGBA as 8 bit RGBA
+fn to_rgba(width: u32, height: u32, channels: usize, samples: &[f32]) -> Option<RgbaImage> {
+    if !(1..=4).contains(&channels) {
+        return None;
+    }
+    let to_u8 = |sample: f32| (sample.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let pixels = samples
+        .chunks_exact(channels)
+        .flat_map(|pixel| match *pixel {
+            [gray] => [gray, gray, gray, 1.0],
+            [gray, alpha] => [gray, gray, gray, alpha],
+            [r, g, b] => [r, g, b, 1.0],
+            [r, g, b, alpha] => [r, g, b, alpha],
+            _ => unreachable!("1 to 4 channels"),
+        })
+        .map(to_u8)
+        .collect();
+
+    RgbaImage::from_raw(width, height, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::OutputFormat;
+    use crate::{hide_data, unveil_data};
+    use stegano_core::media::Media;
+
+    #[test]
+    fn should_expand_gray_and_rgb_samples() {
+        let gray = to_rgba(2, 1, 2, &[0.0, 1.0, 1.0, 0.5]).unwrap();
+        assert_eq!(gray.as_raw(), &[0, 0, 0, 255, 255, 255, 255, 128]);
+
+        let rgb = to_rgba(1, 1, 3, &[1.0, 0.2, 0.0]).unwrap();
+        assert_eq!(rgb.as_raw(), &[255, 51, 0, 255]);
+
+        assert!(to_rgba(1, 1, 5, &[0.0; 5]).is_none());
+    }
+
+    #[test]
+    fn should_decode_the_exact_pixels_of_a_lossless_jxl() {
+        let img = image::RgbaImage::from_fn(40, 24, |x, y| {
+            image::Rgba([x as u8 * 6, y as u8 * 10, (x ^ y) as u8, 200 + x as u8])
+        });
+        let jxl = OutputFormat::Jxl { effort: 4 }
+            .encode(&mut Media::from_image(img.clone()), None)
+            .unwrap();
+
+        assert_eq!(decode(&jxl).unwrap(), img);
+    }
+
+    #[test]
+    fn should_unveil_from_a_jxl_stego_image() {
+        let mut carrier = Cursor::new(Vec::new());
+        image::RgbaImage::from_fn(64, 64, |x, y| {
+            image::Rgba([x as u8 * 4, y as u8 * 4, 90, 255])
+        })
+        .write_to(&mut carrier, image::ImageFormat::Png)
+        .unwrap();
+
+        let stego = hide_data(
+            carrier.get_ref(),
+            "secret.txt",
+            b"hidden in JPEG XL".to_vec(),
+            None,
+            false,
+            Some("jxl".to_string()),
+        )
+        .unwrap()
+        .into_data();
+        assert!(is_jxl(&stego));
+
+        let files = unveil_data(&stego, None).unwrap();
+        assert_eq!(files[0].name(), "secret.txt");
+        assert_eq!(
+            files[0].data.with_bytes(<[u8]>::to_vec),
+            Some(b"hidden in JPEG XL".to_vec())
+        );
+    }
+}
@@ -0,0 +1,31 @@
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+
+use crate::secrets::Secret;
+
+/// An opaque handle to a passphrase that lives inside of the wasm memory.
+///
+/// JS only ever holds a reference to the handle, so the plaintext passphrase crosses
+/// the JS boundary once, when calling `derive_key`. The actual encryption key is derived
+/// per payload, because every payload carries its own salt.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct KeyHandle {
+    password: Secret,
+}
+
+impl KeyHandle {
+    pub(crate) fn password(&self) -> Result<Zeroizing<String>, JsValue> {
+        self.password
+            .to_zeroizing_string()
+            .ok_or_else(|| JsValue::from_str("KeyHandle has been wiped"))
+    }
+}
+
+/// Creates a `KeyHandle` for the given passphrase, to be passed to the `*_with_key` calls
+#[wasm_bindgen]
+pub fn derive_key(password: String) -> KeyHandle {
+    KeyHandle {
+        password: Secret::new(password.into_bytes()),
+    }
+}
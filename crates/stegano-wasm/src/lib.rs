@@ -1,92 +1,410 @@
-use stegano_core::api::unveil;
+use stegano_core::api::unveil::UnveilApi;
+use stegano_core::api::{rekey as rekey_api, unveil};
+use stegano_core::media::image::LsbCodec;
 use stegano_core::media::Media;
 use stegano_core::SteganoEncoder;
 use wasm_bindgen::prelude::*;
+use zeroize::{Zeroize, Zeroizing};
 
-use image::ImageFormat;
+use image::{ImageFormat, RgbaImage};
+
+mod batch;
+mod canvas;
+mod carrier_info;
+mod codecs;
+mod color;
+mod decode;
+#[cfg(target_arch = "wasm32")]
+mod fetch;
+#[cfg(all(feature = "webgpu", target_arch = "wasm32"))]
+mod gpu;
+mod health;
+mod hide_job;
+mod inspection;
+mod jxl;
+mod key_handle;
+mod migrate;
+mod options;
+mod output;
+mod passphrase;
+mod paste;
+mod pixels;
+mod preview;
+mod resumable;
+mod risk;
+mod secrets;
+mod session;
+mod signing;
+mod strips;
+mod verify;
+mod wav;
+
+pub use batch::{unveil_batch, BatchItem, BatchResult};
+pub use carrier_info::{
+    estimate_required_dimensions, inspect_carrier, inspect_carrier_with_options, CarrierInfo,
+};
+#[cfg(target_arch = "wasm32")]
+pub use codecs::decode_carrier;
+pub use codecs::{clear_codecs, register_decoder, register_encoder};
+pub use decode::{set_decode_limits, ImageTooLarge};
+#[cfg(target_arch = "wasm32")]
+pub use fetch::{hide_from_url, unveil_from_url};
+#[cfg(all(feature = "webgpu", target_arch = "wasm32"))]
+pub use gpu::hide_data_gpu;
+pub use health::{health_check, reset, Health};
+pub use hide_job::HideJob;
+pub use inspection::{
+    find_files, inspect_data, inspect_data_with_key, list_hidden_files, unveil_preview, HiddenFile,
+    Inspection, UnveiledPreview,
+};
+pub use key_handle::{derive_key, KeyHandle};
+pub use migrate::migrate;
+pub use options::{HideOptions, UnveilOptions};
+pub use passphrase::generate_passphrase;
+pub use pixels::{
+    hide_data_into_pixels, hide_data_with_bitmap, hide_data_with_pixels, unveil_data_with_pixels,
+};
+pub use preview::hide_data_with_preview;
+pub use resumable::ResumableHide;
+pub use risk::{assess_channel_risk, ChannelRisk};
+pub use secrets::wipe_secrets;
+pub use session::{HideSession, UnveilSession};
+pub use signing::{verify_manifest, ManifestCheck};
+pub use verify::{hide_data_verified, HideResult};
+
+use output::OutputFormat;
+use secrets::Secret;
 
 #[wasm_bindgen]
 pub fn init_panic_hook() {
-    console_error_panic_hook::set_once();
+    health::set_panic_hook();
 }
 
 #[wasm_bindgen]
 pub fn hide_data(
     carrier_data: &[u8],
     secret_name: &str,
-    secret_data: &[u8],
+    secret_data: Vec<u8>,
     password: Option<String>,
     should_resize: bool,
     output_format_str: Option<String>,
-) -> Result<Vec<u8>, JsValue> {
-    let mut img = image::load_from_memory(carrier_data)
-        .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?
-        .to_rgba8();
+) -> Result<HideResult, JsValue> {
+    let mut options = HideOptions::with_resize_and_format(should_resize, output_format_str);
+    options.set_password(password);
 
-    // Auto-Resize Logic
-    // Capacity in bytes = (width * height * 3) / 8
-    // We compare against secret_data.len() + estimated overhead (e.g. 1KB for header)
-    let overhead = 1024;
-    let payload_size = secret_data.len() + overhead;
-    let capacity = (img.width() as usize * img.height() as usize * 3) / 8;
-
-    if payload_size > capacity {
-        if !should_resize {
-            return Err(JsValue::from_str(&format!(
-                "Image too small! Capacity: {} bytes, Payload: {} bytes. Enable 'Autoscale' or choose a larger image.", 
-                capacity, payload_size
-            )));
-        }
+    hide_data_with_options(carrier_data, secret_name, secret_data, &options)
+}
 
-        // Calculate new dimensions
-        // required_pixels = (payload_size * 8) / 3
-        let required_pixels = (payload_size as f64 * 8.0) / 3.0;
-        let current_pixels = (img.width() * img.height()) as f64;
-        let scale_factor = (required_pixels / current_pixels).sqrt() * 1.02; // Reduced buffer to 2% from 10%
+/// Same as `hide_data`, but the password is taken from a `KeyHandle`
+#[wasm_bindgen]
+pub fn hide_data_with_key(
+    carrier_data: &[u8],
+    secret_name: &str,
+    secret_data: Vec<u8>,
+    key: &KeyHandle,
+    should_resize: bool,
+    output_format_str: Option<String>,
+) -> Result<HideResult, JsValue> {
+    let mut options = HideOptions::with_resize_and_format(should_resize, output_format_str);
+    options.use_key(key)?;
 
-        let new_width = (img.width() as f64 * scale_factor).ceil() as u32;
-        let new_height = (img.height() as f64 * scale_factor).ceil() as u32;
+    hide_data_with_options(carrier_data, secret_name, secret_data, &options)
+}
 
-        img = image::imageops::resize(
-            &img,
-            new_width,
-            new_height,
-            image::imageops::FilterType::Lanczos3,
-        );
-    }
+/// Hides the secret so that any `threshold` of the given passwords can unveil it again,
+/// for example any 2 of 3 custodians.
+#[wasm_bindgen]
+pub fn hide_data_threshold(
+    carrier_data: &[u8],
+    secret_name: &str,
+    secret_data: Vec<u8>,
+    passwords: Vec<String>,
+    threshold: u8,
+    should_resize: bool,
+    output_format_str: Option<String>,
+) -> Result<HideResult, JsValue> {
+    let mut options = HideOptions::with_resize_and_format(should_resize, output_format_str);
+    options.set_threshold_passwords(passwords, threshold);
 
-    let media = Media::from_image(img);
+    hide_data_with_options(carrier_data, secret_name, secret_data, &options)
+}
 
-    let mut encoder = SteganoEncoder::default();
-    if let Some(pwd) = password {
-        encoder.with_encryption(pwd);
-    }
+/// Hides the secret with all settings taken from the given `HideOptions`
+/// A WAV carrier is hidden in its samples and returned as WAV, it is never resized
+#[wasm_bindgen]
+pub fn hide_data_with_options(
+    carrier_data: &[u8],
+    secret_name: &str,
+    secret_data: Vec<u8>,
+    options: &HideOptions,
+) -> Result<HideResult, JsValue> {
+    let secret_data = Zeroizing::new(secret_data);
+    let encoder = encoder_for(secret_name, &secret_data, options)?;
 
-    if let Some(fmt_str) = output_format_str {
-        let fmt = match fmt_str.to_lowercase().as_str() {
-            "webp" => ImageFormat::WebP,
-            "avif" => ImageFormat::Avif,
-            _ => ImageFormat::Png,
-        };
-        encoder.with_output_format(fmt);
+    hide_with(
+        carrier_data,
+        encoder,
+        options.payload_size(secret_name, secret_data.len())?,
+        options,
+    )
+}
+
+/// Hides what the encoder holds, a payload of about `payload_size` bytes, in the carrier
+pub(crate) fn hide_with(
+    carrier_data: &[u8],
+    mut encoder: SteganoEncoder,
+    payload_size: usize,
+    options: &HideOptions,
+) -> Result<HideResult, JsValue> {
+    if let Some(result) = strips::hide_in_strips(carrier_data, &encoder, options)? {
+        return Ok(result);
     }
+    let carrier = carrier_for(carrier_data, payload_size, options)?;
+    encoder.use_media_from_media(carrier.media);
+
+    let mut media = encoder
+        .hide_to_media()
+        .map_err(|e| JsValue::from_str(&format!("Failed to hide data: {}", e)))?;
+
+    let data = options
+        .output()?
+        .encode(&mut media, carrier.icc_profile.as_deref())?;
 
-    encoder.use_media_from_media(media);
+    Ok(HideResult::new(data, carrier.warnings))
+}
+
+/// Builds an encoder that holds the secret, set up by the options
+pub(crate) fn encoder_for(
+    secret_name: &str,
+    secret_data: &[u8],
+    options: &HideOptions,
+) -> Result<SteganoEncoder, JsValue> {
+    let mut encoder = options.encoder()?;
     encoder
         .add_file_from_memory(secret_name, secret_data)
         .map_err(|e| JsValue::from_str(&format!("Failed to add memory file: {}", e)))?;
+    if let Some(comment) = options.comment() {
+        encoder.with_file_comment(secret_name, comment);
+    }
 
-    let result = encoder
-        .hide_to_vec()
-        .map_err(|e| JsValue::from_str(&format!("Failed to hide data: {}", e)))?;
+    Ok(encoder)
+}
+
+/// A carrier to hide in, together with what happened to its color profile
+pub(crate) struct Carrier {
+    pub(crate) media: Media,
+    /// the ICC color profile to write into the output
+    pub(crate) icc_profile: Option<Vec<u8>>,
+    pub(crate) warnings: Vec<String>,
+}
+
+/// Loads the carrier image, or WAV, and makes sure it can hold a payload of `payload_size` bytes
+pub(crate) fn carrier_for(
+    carrier_data: &[u8],
+    payload_size: usize,
+    options: &HideOptions,
+) -> Result<Carrier, JsValue> {
+    if wav::is_wav(carrier_data) {
+        return wav::carrier(carrier_data, payload_size, options);
+    }
+    let (img, icc_profile) = decode::load_image_with_profile(carrier_data)?;
+
+    carrier_from_decoded(img.to_rgba8(), icc_profile, payload_size, options)
+}
+
+/// Same as `carrier_for`, with the carrier decoded already
+pub(crate) fn carrier_from_decoded(
+    mut img: RgbaImage,
+    icc_profile: Option<Vec<u8>>,
+    payload_size: usize,
+    options: &HideOptions,
+) -> Result<Carrier, JsValue> {
+    let mut warnings = Vec::new();
+    let icc_profile = options.color_profile_policy()?.apply(
+        &mut img,
+        icc_profile,
+        options.output()?,
+        &mut warnings,
+    );
+
+    Ok(Carrier {
+        media: carrier_from_image(img, payload_size, options)?,
+        icc_profile,
+        warnings,
+    })
+}
 
-    Ok(result)
+/// Makes sure the decoded carrier can hold a payload of `payload_size` bytes
+pub(crate) fn carrier_from_image(
+    img: RgbaImage,
+    payload_size: usize,
+    options: &HideOptions,
+) -> Result<Media, JsValue> {
+    let img = fit_to_payload(img, payload_size, options)?;
+
+    Ok(Media::from_image(img))
 }
 
+/// how often autoscale upscales further when the pixels left for data grew less than the image
+const MAX_UPSCALES: usize = 4;
+
+/// Ensures the carrier can hold `payload_size` bytes, upscaling it if allowed
+fn fit_to_payload(
+    img: RgbaImage,
+    payload_size: usize,
+    options: &HideOptions,
+) -> Result<RgbaImage, JsValue> {
+    let codec_options = options.codec_options()?;
+    let capacity = LsbCodec::capacity(&img, &codec_options);
+
+    if payload_size <= capacity {
+        return Ok(img);
+    }
+
+    if !options.resize() {
+        return Err(JsValue::from_str(&format!(
+            "Image too small! Capacity: {} bytes, Payload: {} bytes. Enable 'Autoscale' or choose a larger image.",
+            capacity, payload_size
+        )));
+    }
+
+    // the pixels the options leave for data scale with the image only roughly, e.g. the opaque ones
+    let (mut width, mut height) = (img.width(), img.height());
+    let mut scaled_capacity = capacity;
+    for _ in 0..MAX_UPSCALES {
+        if scaled_capacity == 0 {
+            break;
+        }
+        (width, height) = scaled_to_fit(width, height, scaled_capacity, payload_size);
+        let resized = resize(&img, width, height, options)?;
+        scaled_capacity = LsbCodec::capacity(&resized, &codec_options);
+        if payload_size <= scaled_capacity {
+            return Ok(resized);
+        }
+    }
+
+    Err(JsValue::from_str(&format!(
+        "Image cannot be upscaled to fit! Capacity: {} bytes, Payload: {} bytes. Choose a larger image.",
+        capacity, payload_size
+    )))
+}
+
+/// Upscales on a canvas if the options ask for it and one is available, with Lanczos3 otherwise
+fn resize(
+    img: &RgbaImage,
+    width: u32,
+    height: u32,
+    options: &HideOptions,
+) -> Result<RgbaImage, JsValue> {
+    if options.browser_resize() {
+        if let Some(resized) = canvas::resize_with_canvas(img, width, height) {
+            return Ok(resized);
+        }
+    }
+
+    resize_lanczos3(img, width, height)
+}
+
+/// Bytes that fit into the LSBs of an opaque image of the given dimensions, with the redundancy of the options.
+/// The pixels the decoder skips, the last row and column, are not counted
+pub(crate) fn lsb_capacity(width: u32, height: u32, options: &HideOptions) -> usize {
+    let pixels = width.saturating_sub(1) as usize * height.saturating_sub(1) as usize;
+
+    pixels * 3 / 8 / options.redundancy().max(1) as usize
+}
+
+/// The dimensions autoscale upscales to, so that `payload_size` bytes fit where `capacity` bytes do now, keeping the ratio
+pub(crate) fn scaled_to_fit(
+    width: u32,
+    height: u32,
+    capacity: usize,
+    payload_size: usize,
+) -> (u32, u32) {
+    let scale_factor = (payload_size as f64 / capacity as f64).sqrt() * 1.02; // Reduced buffer to 2% from 10%
+
+    (
+        (width as f64 * scale_factor).ceil() as u32,
+        (height as f64 * scale_factor).ceil() as u32,
+    )
+}
+
+/// Lanczos3 resize, with SIMD where the target offers it
+fn resize_lanczos3(img: &RgbaImage, width: u32, height: u32) -> Result<RgbaImage, JsValue> {
+    let mut resized = RgbaImage::new(width, height);
+    let options = fast_image_resize::ResizeOptions::new().resize_alg(
+        fast_image_resize::ResizeAlg::Convolution(fast_image_resize::FilterType::Lanczos3),
+    );
+    fast_image_resize::Resizer::new()
+        .resize(img, &mut resized, &options)
+        .map_err(|e| JsValue::from_str(&format!("Failed to resize image: {}", e)))?;
+
+    Ok(resized)
+}
+
+/// Swaps the password of the data hidden in `stego_bytes` without the original carrier.
+/// Only the pixels, or samples of a WAV, holding the payload are rewritten, the output keeps the input format.
+/// PNG, WebP, BMP, JPEG XL, WAV and formats with a registered encoder are supported, others are an error
+#[wasm_bindgen]
+pub fn rekey(
+    stego_bytes: &[u8],
+    old_password: Option<String>,
+    new_password: Option<String>,
+) -> Result<Vec<u8>, JsValue> {
+    rekey_with(stego_bytes, old_password, new_password)
+}
+
+/// Same as `rekey`, but both passwords are taken from `KeyHandle`s
+#[wasm_bindgen]
+pub fn rekey_with_keys(
+    stego_bytes: &[u8],
+    old_key: &KeyHandle,
+    new_key: &KeyHandle,
+) -> Result<Vec<u8>, JsValue> {
+    rekey_with(
+        stego_bytes,
+        Some(old_key.password()?.to_string()),
+        Some(new_key.password()?.to_string()),
+    )
+}
+
+fn rekey_with(
+    stego_bytes: &[u8],
+    old_password: Option<String>,
+    new_password: Option<String>,
+) -> Result<Vec<u8>, JsValue> {
+    let (media, output) = if wav::is_wav(stego_bytes) {
+        // audio is written as WAV, whatever the image format
+        (
+            wav::load(stego_bytes)?,
+            OutputFormat::Image(ImageFormat::Png),
+        )
+    } else {
+        let output = OutputFormat::of(stego_bytes)
+            .map_err(|e| JsValue::from_str(&format!("Failed to rekey: {}", e)))?;
+        let img = decode::load_image(stego_bytes)?.to_rgba8();
+        (Media::from_image(img), output)
+    };
+
+    let mut media = rekey_api::prepare()
+        .from_media(media)
+        .using_password(old_password)
+        .using_new_password(new_password)
+        .execute_to_media()
+        .map_err(|e| JsValue::from_str(&format!("Failed to rekey: {}", e)))?;
+
+    output.encode(&mut media, None)
+}
+
+/// A file unveiled from a carrier, its content is zeroized when freed or by `wipe_secrets`
 #[wasm_bindgen]
 pub struct UnveiledFile {
+    index: usize,
     name: String,
-    data: Vec<u8>,
+    display_name: String,
+    sha256: String,
+    data: Secret,
+    comment: Option<String>,
+    link_target: Option<String>,
 }
 
 #[wasm_bindgen]
@@ -96,48 +414,308 @@ impl UnveiledFile {
         self.name.clone()
     }
 
+    /// The path as it can be saved on any platform, `name` stays the one stored in the container.
+    /// Characters that some platform does not allow become `_`, e.g. `a:b?.txt` becomes `a_b_.txt`
+    #[wasm_bindgen(getter)]
+    pub fn display_name(&self) -> String {
+        self.display_name.clone()
+    }
+
+    /// The position in the order the files were hidden in, the hidden text comes last
+    #[wasm_bindgen(getter)]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The SHA-256 of the content as lowercase hex, to compare with a hash shared on another way
+    #[wasm_bindgen(getter)]
+    pub fn sha256(&self) -> String {
+        self.sha256.clone()
+    }
+
+    /// The directories and the file name of the path inside of the container,
+    /// e.g. `["docs", "2024", "report.pdf"]` for `docs/2024/report.pdf`
     #[wasm_bindgen(getter)]
-    pub fn data(&self) -> Vec<u8> {
-        self.data.clone()
+    pub fn path_components(&self) -> Vec<String> {
+        self.name.split('/').map(str::to_string).collect()
+    }
+
+    /// The file name without any directories
+    #[wasm_bindgen(getter)]
+    pub fn file_name(&self) -> String {
+        self.name.rsplit('/').next().unwrap_or_default().to_string()
+    }
+
+    /// The comment that was given at hide time, if any
+    #[wasm_bindgen(getter)]
+    pub fn comment(&self) -> Option<String> {
+        self.comment.clone()
+    }
+
+    /// Where the file pointed to, if it was a symlink unveiled with the `"link_target"` policy
+    #[wasm_bindgen(getter)]
+    pub fn link_target(&self) -> Option<String> {
+        self.link_target.clone()
+    }
+
+    /// The content is copied straight into a JS array, so that no copy is left behind in wasm memory
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> js_sys::Uint8Array {
+        self.data
+            .with_bytes(|bytes| js_sys::Uint8Array::from(bytes))
+            .unwrap_or_else(|| js_sys::Uint8Array::new_with_length(0))
+    }
+}
+
+impl Drop for UnveiledFile {
+    fn drop(&mut self) {
+        self.name.zeroize();
+        self.display_name.zeroize();
+        self.comment.zeroize();
     }
 }
 
+/// Unveils the files hidden in `carrier_data` in the order they were hidden in, the hidden text comes last.
+/// Containers of older stegano-rs versions are detected and read as well,
+/// see `UnveilOptions.legacy_formats` to select them explicitly
 #[wasm_bindgen]
 pub fn unveil_data(
     carrier_data: &[u8],
     password: Option<String>,
 ) -> Result<Vec<UnveiledFile>, JsValue> {
-    let img = match image::load_from_memory(carrier_data) {
-        Ok(i) => i.to_rgba8(),
-        Err(_) => {
-            // use jxl_oxide::JxlImage; // Commented out to fix build (API mismatch?)
-            // use std::io::Cursor;
-
-            // let mut cursor = Cursor::new(carrier_data);
-            // let _image = JxlImage::builder().read(&mut cursor)
-            //     .map_err(|e| JsValue::from_str(&format!("Failed to load image (and JXL failed: {})", e)))?;
-
-            // Note: Manual conversion from JXL FrameBuffer to Image crate DynamicImage
-            // requires complex logic or 'image' feature which is missing in current crates.
-            // Returning error for now to allow build to pass.
-            return Err(JsValue::from_str("JXL file detected but decoding implementation is pending (jxl-oxide integration issue)."));
-        }
-    };
-
-    let media = Media::from_image(img);
-
     let mut unveil = unveil::prepare();
     if let Some(pwd) = password {
         unveil = unveil.using_password(Some(pwd));
     }
 
-    let results = unveil
+    unveil_with(unveil, carrier_data)
+}
+
+/// Same as `unveil_data`, but the password is taken from a `KeyHandle`
+#[wasm_bindgen]
+pub fn unveil_data_with_key(
+    carrier_data: &[u8],
+    key: &KeyHandle,
+) -> Result<Vec<UnveiledFile>, JsValue> {
+    unveil_with(
+        unveil::prepare().using_password(key.password()?.as_str()),
+        carrier_data,
+    )
+}
+
+/// Unveils a secret that was hidden with `hide_data_threshold`,
+/// at least `threshold` of the original passwords must be given, in any order.
+#[wasm_bindgen]
+pub fn unveil_data_threshold(
+    carrier_data: &[u8],
+    passwords: Vec<String>,
+) -> Result<Vec<UnveiledFile>, JsValue> {
+    unveil_with(
+        unveil::prepare().using_threshold_passwords(passwords),
+        carrier_data,
+    )
+}
+
+fn unveil_with(unveil: UnveilApi, carrier_data: &[u8]) -> Result<Vec<UnveiledFile>, JsValue> {
+    Ok(unveil_report(unveil, load_secret_media(carrier_data)?)?.files)
+}
+
+/// The unveiled files, together with what happened to symlinks and special files
+#[wasm_bindgen]
+pub struct UnveilResult {
+    files: Vec<UnveiledFile>,
+    warnings: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl UnveilResult {
+    /// One message per symlink or special file that was not unveiled as it is
+    #[wasm_bindgen(getter)]
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.clone()
+    }
+
+    /// Hands out the unveiled files, a second call returns none
+    pub fn take_files(&mut self) -> Vec<UnveiledFile> {
+        std::mem::take(&mut self.files)
+    }
+}
+
+/// Unveils with the given options, see `UnveilOptions`
+#[wasm_bindgen]
+pub fn unveil_data_with_options(
+    carrier_data: &[u8],
+    options: &UnveilOptions,
+) -> Result<UnveilResult, JsValue> {
+    unveil_report(options.unveil()?, load_secret_media(carrier_data)?)
+}
+
+pub(crate) fn unveil_report(unveil: UnveilApi, media: Media) -> Result<UnveilResult, JsValue> {
+    let mut report = unveil
         .from_media(media)
-        .execute_to_memory()
+        .execute_to_report()
         .map_err(|e| JsValue::from_str(&format!("Failed to unveil: {}", e)))?;
 
-    Ok(results
-        .into_iter()
-        .map(|(name, data)| UnveiledFile { name, data })
-        .collect())
+    let files = report
+        .files
+        .iter_mut()
+        .map(|file| UnveiledFile {
+            index: file.index,
+            sha256: file.sha256_hex(),
+            display_name: file.display_name(),
+            name: std::mem::take(&mut file.name),
+            data: Secret::new(std::mem::take(&mut file.data)),
+            comment: file.comment.take(),
+            link_target: file.link_target.take(),
+        })
+        .collect();
+
+    Ok(UnveilResult {
+        files,
+        warnings: report.warnings,
+    })
+}
+
+/// Loads an image, or a WAV, that has data hidden inside
+pub(crate) fn load_secret_media(carrier_data: &[u8]) -> Result<Media, JsValue> {
+    let carrier_data = paste::normalize(carrier_data);
+    let carrier_data = carrier_data.as_ref();
+    if let Some(img) = codecs::decode(carrier_data) {
+        return Ok(Media::from_image(img?));
+    }
+    if jxl::is_jxl(carrier_data) {
+        return Ok(Media::from_image(jxl::decode(carrier_data)?));
+    }
+    if wav::is_wav(carrier_data) {
+        return wav::load(carrier_data);
+    }
+    decode::check_limits(carrier_data)?;
+    let img = image::load_from_memory(carrier_data)
+        .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?
+        .to_rgba8();
+
+    Ok(Media::from_image(img))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_upscale_the_carrier_to_fit_the_payload() {
+        let img = RgbaImage::from_pixel(100, 50, image::Rgba([200, 100, 50, 255]));
+        let mut options = HideOptions::new();
+        options.set_resize(true);
+
+        let resized = fit_to_payload(img, 10_000, &options).unwrap();
+        let capacity = resized.width() as usize * resized.height() as usize * 3 / 8;
+        assert!(capacity >= 10_000);
+        assert_eq!(
+            resized.width() / resized.height(),
+            2,
+            "it should keep the ratio"
+        );
+        assert_eq!(resized.get_pixel(10, 10), &image::Rgba([200, 100, 50, 255]));
+    }
+
+    #[test]
+    fn should_return_the_carrier_warnings_with_the_stego_image() {
+        use image::ImageEncoder;
+
+        let img = RgbaImage::from_pixel(128, 128, image::Rgba([90, 120, 150, 255]));
+        let mut carrier = Vec::new();
+        let mut encoder = image::codecs::png::PngEncoder::new(&mut carrier);
+        encoder.set_icc_profile(b"icc".to_vec()).unwrap();
+        encoder
+            .write_image(img.as_raw(), 128, 128, image::ExtendedColorType::Rgba8)
+            .unwrap();
+
+        let result = hide_data_with_options(
+            &carrier,
+            "secret.txt",
+            b"hidden".to_vec(),
+            &HideOptions::new(),
+        )
+        .unwrap();
+        assert_eq!(result.warnings(), vec![color::PROFILE_DROPPED.to_string()]);
+        assert_eq!(result.verified(), None);
+
+        let files = unveil_data(&result.into_data(), None).unwrap();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn should_rekey_a_wav() {
+        let samples: Vec<i16> = (0..16_000).map(|i| (i % 512) as i16).collect();
+        let mut options = HideOptions::new();
+        options.set_password(Some("old-secret".to_string()));
+        let stego = hide_data_with_options(
+            &wav::tests::wav(&samples),
+            "secret.txt",
+            b"hidden".to_vec(),
+            &options,
+        )
+        .unwrap()
+        .into_data();
+
+        let rekeyed = rekey(
+            &stego,
+            Some("old-secret".to_string()),
+            Some("new-secret".to_string()),
+        )
+        .unwrap();
+        assert!(wav::is_wav(&rekeyed), "it should stay a WAV");
+        let files = unveil_data(&rekeyed, Some("new-secret".to_string())).unwrap();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn should_unveil_with_the_config_of_a_preset() {
+        let mut carrier = std::io::Cursor::new(Vec::new());
+        RgbaImage::from_pixel(128, 128, image::Rgba([90, 120, 150, 255]))
+            .write_to(&mut carrier, ImageFormat::Png)
+            .unwrap();
+        let mut options = HideOptions::new();
+        options.use_preset("discord").unwrap();
+
+        let stego = hide_data_with_options(
+            carrier.get_ref(),
+            "secret.txt",
+            b"hidden".to_vec(),
+            &options,
+        )
+        .unwrap()
+        .into_data();
+        let unveil_options = UnveilOptions::from_config(&options.unveil_config().unwrap()).unwrap();
+        let files = unveil_data_with_options(&stego, &unveil_options)
+            .unwrap()
+            .take_files();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name(), "secret.txt");
+    }
+
+    #[test]
+    fn should_count_a_long_secret_name_in_the_payload_size() {
+        let name = format!("{}/secret.bin", "a-long-folder-name".repeat(30));
+        let mut secret = vec![0; 2_000];
+        getrandom::getrandom(&mut secret).unwrap();
+        let mut carrier = std::io::Cursor::new(Vec::new());
+        RgbaImage::from_pixel(16, 16, image::Rgba([90, 120, 150, 255]))
+            .write_to(&mut carrier, ImageFormat::Png)
+            .unwrap();
+        let mut options = HideOptions::new();
+        options.set_resize(true);
+
+        let payload = encoder_for(&name, &secret, &options)
+            .unwrap()
+            .encode_payload()
+            .unwrap();
+        assert!(options.payload_size(&name, secret.len()).unwrap() >= payload.len());
+
+        let stego = hide_data_with_options(carrier.get_ref(), &name, secret, &options)
+            .unwrap()
+            .into_data();
+        let files = unveil_data(&stego, None).unwrap();
+        assert_eq!(files[0].name(), name);
+    }
 }
@@ -0,0 +1,81 @@
+use stegano_core::CodecOptions;
+use wasm_bindgen::prelude::*;
+
+use crate::options::{HideOptions, UnveilOptions};
+use crate::{carrier_for, load_secret_media, HideResult};
+
+/// Upgrades the data hidden in `stego_bytes` in one go: the files are unveiled with `old_options`,
+/// containers of older stegano-rs versions included, and hidden again in the same image with `new_options`,
+/// e.g. with a new password, padding or output format.
+///
+/// The whole LSB plane is randomized before hiding again, so that nothing of the old container survives.
+/// File comments are kept, metadata is taken from `new_options`.
+#[wasm_bindgen]
+pub fn migrate(
+    stego_bytes: &[u8],
+    old_options: &UnveilOptions,
+    new_options: &HideOptions,
+) -> Result<HideResult, JsValue> {
+    let report = old_options
+        .unveil()?
+        .from_media(load_secret_media(stego_bytes)?)
+        .execute_to_report()
+        .map_err(|e| JsValue::from_str(&format!("Failed to unveil: {}", e)))?;
+
+    let mut encoder = new_options.encoder_with(CodecOptions {
+        sanitize_lsb_plane: true,
+        ..new_options.codec_options()?
+    })?;
+    for file in report.files.iter() {
+        encoder
+            .add_file_from_memory(&file.name, &file.data)
+            .map_err(|e| JsValue::from_str(&format!("Failed to add memory file: {}", e)))?;
+        if let Some(comment) = &file.comment {
+            encoder.with_file_comment(&file.name, comment.clone());
+        }
+    }
+    let payload_size = new_options.files_payload_size(
+        report
+            .files
+            .iter()
+            .map(|file| (file.name.as_str(), file.comment.as_deref(), file.data.len())),
+    )?;
+
+    let carrier = carrier_for(stego_bytes, payload_size, new_options)?;
+    encoder.use_media_from_media(carrier.media);
+    let mut media = encoder
+        .hide_to_media()
+        .map_err(|e| JsValue::from_str(&format!("Failed to hide data: {}", e)))?;
+
+    let data = new_options
+        .output()?
+        .encode(&mut media, carrier.icc_profile.as_deref())?;
+    Ok(HideResult::new(data, carrier.warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unveil_data;
+
+    #[test]
+    fn should_migrate_an_old_container_to_a_password() {
+        let old = std::fs::read(
+            "../stegano-core/tests/demo-secrets/message-version-1/text-only-without-passwd-v2.1.1.9.PNG",
+        )
+        .unwrap();
+        let mut new_options = HideOptions::new();
+        new_options.set_password(Some("Secret42".to_string()));
+
+        let migrated = migrate(&old, &UnveilOptions::new(), &new_options)
+            .unwrap()
+            .into_data();
+        let files = unveil_data(&migrated, Some("Secret42".to_string())).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name(), "secret-message.txt");
+        assert_eq!(
+            files[0].data.with_bytes(|bytes| bytes.to_vec()).unwrap(),
+            b"Welcome to a Text Only Secret Message"
+        );
+    }
+}
@@ -0,0 +1,963 @@
+use image::ImageFormat;
+use stegano_core::api::unveil::{
+    self, CollisionPolicy, FileOrder, LegacyFormats, SpecialEntryPolicy, UnveilApi,
+};
+use stegano_core::media::image::perceptual::MAX_STEALTH_LEVEL;
+use stegano_core::media::payload::PaddingBuckets;
+use stegano_core::{AlphaPolicy, CodecOptions, Metadata, SteganoEncoder, TimestampToken};
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+
+use crate::codecs;
+use crate::color::ColorProfilePolicy;
+use crate::key_handle::KeyHandle;
+use crate::output::OutputFormat;
+use crate::pixels::ChannelOrder;
+use crate::secrets::Secret;
+use crate::signing::ed25519_key;
+
+/// effort of the JPEG XL encoder, if not set
+pub(crate) const DEFAULT_JXL_EFFORT: u8 = 4;
+
+/// marks a config string of `UnveilOptions.to_config`, the number is its version
+const CONFIG_PREFIX: &str = "stegano1:";
+
+/// Options for `hide_data_with_options`, created in JS with `new HideOptions()`.
+///
+/// Passwords can be set, but never read back, they are kept with the other secrets
+/// and wiped by `wipe_secrets`.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct HideOptions {
+    resize: bool,
+    browser_resize: bool,
+    output_format: Option<String>,
+    jxl_effort: Option<u8>,
+    sanitize_lsb: bool,
+    alpha_policy: Option<String>,
+    stealth: u8,
+    banding_aware: bool,
+    redundancy: u8,
+    color_profile: Option<String>,
+    channel_order: Option<String>,
+    padding: Option<String>,
+    comment: Option<String>,
+    author: Option<String>,
+    created_at: Option<f64>,
+    app_version: Option<String>,
+    password: Option<Secret>,
+    threshold_passwords: Option<(Vec<Secret>, u8)>,
+    signing_key: Option<Secret>,
+    timestamp_token: Option<TimestampToken>,
+}
+
+#[wasm_bindgen]
+impl HideOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> HideOptions {
+        HideOptions::default()
+    }
+
+    /// Upscale the carrier if it is too small for the payload
+    #[wasm_bindgen(getter)]
+    pub fn resize(&self) -> bool {
+        self.resize
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_resize(&mut self, resize: bool) {
+        self.resize = resize;
+    }
+
+    /// Let the browser upscale the carrier on an `OffscreenCanvas`, which is much faster.
+    /// Falls back to Lanczos3 in wasm, if there is no `OffscreenCanvas`
+    #[wasm_bindgen(getter)]
+    pub fn browser_resize(&self) -> bool {
+        self.browser_resize
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_browser_resize(&mut self, browser_resize: bool) {
+        self.browser_resize = browser_resize;
+    }
+
+    /// `"png"` (default), `"webp"`, `"avif"` or `"jxl"`
+    #[wasm_bindgen(getter)]
+    pub fn output_format(&self) -> Option<String> {
+        self.output_format.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_output_format(&mut self, output_format: Option<String>) {
+        self.output_format = output_format;
+    }
+
+    /// Effort of the JPEG XL output, from 1 (fastest) to 9 (smallest), defaults to 4.
+    /// JPEG XL is always encoded lossless, so that the hidden data survives
+    #[wasm_bindgen(getter)]
+    pub fn jxl_effort(&self) -> Option<u8> {
+        self.jxl_effort
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_jxl_effort(&mut self, jxl_effort: Option<u8>) {
+        self.jxl_effort = jxl_effort;
+    }
+
+    /// Randomize the whole LSB plane of the carrier before hiding,
+    /// so that nothing of previously hidden data survives in a reused carrier.
+    /// The noise is random, the password plays no part in it
+    #[wasm_bindgen(getter)]
+    pub fn sanitize_lsb(&self) -> bool {
+        self.sanitize_lsb
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_sanitize_lsb(&mut self, sanitize_lsb: bool) {
+        self.sanitize_lsb = sanitize_lsb;
+    }
+
+    /// Which pixels carry data, depending on their alpha: `"all"` (default),
+    /// `"skip_transparent"` to leave fully transparent pixels alone, as other tools may normalize their colors,
+    /// or `"opaque_only"` to use only fully opaque pixels, which survive premultiplied alpha.
+    /// The same policy has to be given to `UnveilOptions`
+    #[wasm_bindgen(getter)]
+    pub fn alpha_policy(&self) -> Option<String> {
+        self.alpha_policy.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_alpha_policy(&mut self, alpha_policy: Option<String>) {
+        self.alpha_policy = alpha_policy;
+    }
+
+    /// Stealth quality from 0 (default, all pixels) to 3, higher levels hide only in ever busier areas
+    /// of the carrier where changes are not visible, this costs capacity.
+    /// The same level has to be given to `UnveilOptions`
+    #[wasm_bindgen(getter)]
+    pub fn stealth(&self) -> u8 {
+        self.stealth
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_stealth(&mut self, stealth: u8) {
+        self.stealth = stealth;
+    }
+
+    /// Only use the blue channel in smooth gradients like skies, where changes in all channels
+    /// can show up as banding. Costs capacity, the same has to be set in `UnveilOptions`
+    #[wasm_bindgen(getter)]
+    pub fn banding_aware(&self) -> bool {
+        self.banding_aware
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_banding_aware(&mut self, banding_aware: bool) {
+        self.banding_aware = banding_aware;
+    }
+
+    /// How many times each byte of the payload is hidden in an image, `1` (default) hides it once.
+    /// Unveiling takes the majority of each bit, so a few changed pixels are outvoted,
+    /// at the cost of as many times the capacity. The same has to be set in `UnveilOptions`
+    #[wasm_bindgen(getter)]
+    pub fn redundancy(&self) -> u8 {
+        self.redundancy.max(1)
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_redundancy(&mut self, redundancy: u8) {
+        self.redundancy = redundancy;
+    }
+
+    /// What happens to the ICC color profile of a carrier: `"ignore"` (default) takes the pixels as they are
+    /// and drops the profile, `"srgb"` converts the pixels to sRGB before hiding,
+    /// `"preserve"` keeps the profile in PNG and WebP outputs. The decision is reported in the warnings
+    #[wasm_bindgen(getter)]
+    pub fn color_profile(&self) -> Option<String> {
+        self.color_profile.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_color_profile(&mut self, color_profile: Option<String>) {
+        self.color_profile = color_profile;
+    }
+
+    /// The byte order of raw pixels given to `hide_data_with_pixels` or taken from `hide_data_into_pixels`:
+    /// `"rgba"` (default), `"bgra"`, `"rgb"` or `"bgr"`, e.g. `"bgra"` for Windows DIB clipboard data
+    #[wasm_bindgen(getter)]
+    pub fn channel_order(&self) -> Option<String> {
+        self.channel_order.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_channel_order(&mut self, channel_order: Option<String>) {
+        self.channel_order = channel_order;
+    }
+
+    /// Pad the payload up to a size bucket, so that the exact size of the secret cannot be inferred:
+    /// `"pow2"` for powers of two, or a bucket size in bytes like `"65536"`
+    #[wasm_bindgen(getter)]
+    pub fn padding(&self) -> Option<String> {
+        self.padding.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_padding(&mut self, padding: Option<String>) {
+        self.padding = padding;
+    }
+
+    /// Comment stored along with the hidden file, to label what it is
+    #[wasm_bindgen(getter)]
+    pub fn comment(&self) -> Option<String> {
+        self.comment.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_comment(&mut self, comment: Option<String>) {
+        self.comment = comment;
+    }
+
+    /// Author recorded in the hidden metadata, it is encrypted along with the data
+    #[wasm_bindgen(getter)]
+    pub fn author(&self) -> Option<String> {
+        self.author.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_author(&mut self, author: Option<String>) {
+        self.author = author;
+    }
+
+    /// Creation time recorded in the hidden metadata, in milliseconds like `Date.now()`
+    #[wasm_bindgen(getter)]
+    pub fn created_at(&self) -> Option<f64> {
+        self.created_at
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_created_at(&mut self, created_at: Option<f64>) {
+        self.created_at = created_at;
+    }
+
+    /// App version recorded in the hidden metadata, defaults to the version of this library
+    #[wasm_bindgen(getter)]
+    pub fn app_version(&self) -> Option<String> {
+        self.app_version.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_app_version(&mut self, app_version: Option<String>) {
+        self.app_version = app_version;
+    }
+
+    /// The config string of the `UnveilOptions` that match these options, see `UnveilOptions.to_config`
+    pub fn unveil_config(&self) -> Result<String, JsValue> {
+        UnveilOptions {
+            alpha_policy: self.alpha_policy.clone(),
+            stealth: self.stealth,
+            banding_aware: self.banding_aware,
+            redundancy: self.redundancy,
+            channel_order: self.channel_order.clone(),
+            ..UnveilOptions::default()
+        }
+        .to_config()
+    }
+
+    /// Sets the output format, color profile, embedding and redundancy that survive a sharing channel,
+    /// options set afterwards take precedence:
+    /// - `"discord"`, attachments are kept but lose their color profile, so the colors are converted to sRGB.
+    ///   Only opaque pixels are used and each byte is hidden 3 times, in case a pixel gets touched
+    /// - `"telegram_file"`, images sent as file are kept byte by byte, including their color profile,
+    ///   all pixels are used and each byte is hidden once
+    /// - `"email"`, attachments are kept, lossless WebP keeps them small and the colors are converted to sRGB,
+    ///   all pixels are used and each byte is hidden once
+    ///
+    /// None of them uses stealth levels or banding aware embedding, they cost capacity.
+    ///
+    /// `"print_and_scan"` is refused, printing changes every pixel and nothing hidden in them survives.
+    /// Images sent as photo through messengers are re-encoded, see `assess_channel_risk`
+    pub fn use_preset(&mut self, preset: &str) -> Result<(), JsValue> {
+        let (output_format, color_profile, alpha_policy, redundancy) = match preset {
+            "discord" => ("png", "srgb", "opaque_only", 3),
+            "telegram_file" => ("png", "preserve", "all", 1),
+            "email" => ("webp", "srgb", "all", 1),
+            "print_and_scan" => {
+                return Err(JsValue::from_str(
+                    "Invalid preset: print_and_scan, printing changes every pixel and nothing hidden in them survives",
+                ))
+            }
+            other => {
+                return Err(JsValue::from_str(&format!("Invalid preset: {}", other)));
+            }
+        };
+        self.output_format = Some(output_format.to_string());
+        self.color_profile = Some(color_profile.to_string());
+        self.alpha_policy = Some(alpha_policy.to_string());
+        self.stealth = 0;
+        self.banding_aware = false;
+        self.redundancy = redundancy;
+
+        Ok(())
+    }
+
+    /// Encrypt with the given password, `undefined` hides without encryption
+    pub fn set_password(&mut self, password: Option<String>) {
+        self.password = password.map(|p| Secret::new(p.into_bytes()));
+    }
+
+    /// Encrypt with the password of the given `KeyHandle`
+    pub fn use_key(&mut self, key: &KeyHandle) -> Result<(), JsValue> {
+        self.password = Some(Secret::new(key.password()?.as_bytes().to_vec()));
+        Ok(())
+    }
+
+    /// Encrypt so that any `threshold` of the given passwords can unveil the data,
+    /// this takes precedence over a single password
+    pub fn set_threshold_passwords(&mut self, passwords: Vec<String>, threshold: u8) {
+        let passwords = passwords
+            .into_iter()
+            .map(|p| Secret::new(p.into_bytes()))
+            .collect();
+        self.threshold_passwords = Some((passwords, threshold));
+    }
+
+    /// Adds the password of the given `KeyHandle` to the threshold passwords,
+    /// any `threshold` of all added keys can unveil the data
+    pub fn add_threshold_key(&mut self, key: &KeyHandle, threshold: u8) -> Result<(), JsValue> {
+        let password = Secret::new(key.password()?.as_bytes().to_vec());
+        let (passwords, current) = self
+            .threshold_passwords
+            .get_or_insert_with(|| (Vec::new(), threshold));
+        passwords.push(password);
+        *current = threshold;
+        Ok(())
+    }
+
+    /// Hide the given RFC 3161 timestamp token (DER, e.g. a `.tsr` from a TSA) along with the files,
+    /// fails if it does not have the structure of one. `undefined` hides no token
+    pub fn set_timestamp_token(&mut self, token: Option<Vec<u8>>) -> Result<(), JsValue> {
+        self.timestamp_token = token
+            .map(TimestampToken::from_der)
+            .transpose()
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(())
+    }
+
+    /// Hide a manifest of all files, signed with the given 32 bytes Ed25519 secret key,
+    /// so that the recipient can check them with `verify_manifest`. `undefined` hides no manifest
+    pub fn set_signing_key(&mut self, secret_key: Option<Vec<u8>>) -> Result<(), JsValue> {
+        if let Some(key) = secret_key.as_deref() {
+            ed25519_key("signing", key).map_err(|e| JsValue::from_str(&e))?;
+        }
+        self.signing_key = secret_key.map(Secret::new);
+        Ok(())
+    }
+}
+
+impl HideOptions {
+    pub(crate) fn with_resize_and_format(resize: bool, output_format: Option<String>) -> Self {
+        Self {
+            resize,
+            output_format,
+            ..Self::default()
+        }
+    }
+
+    /// Size of the payload for a secret of `secret_len` bytes that cannot be compressed, see `files_payload_size`
+    pub(crate) fn payload_size(
+        &self,
+        secret_name: &str,
+        secret_len: usize,
+    ) -> Result<usize, JsValue> {
+        self.files_payload_size([(secret_name, self.comment.as_deref(), secret_len)])
+    }
+
+    /// Size of the payload for files of the given names, comments and lengths that cannot be compressed,
+    /// measured on the headers of empty files with these options, without encrypting anything
+    pub(crate) fn files_payload_size<'f>(
+        &self,
+        files: impl IntoIterator<Item = (&'f str, Option<&'f str>, usize)>,
+    ) -> Result<usize, JsValue> {
+        let mut probe = self.encoder_with(CodecOptions::default())?;
+        let mut lens = Vec::new();
+        for (name, comment, len) in files {
+            probe
+                .add_file_from_memory(name, &[])
+                .map_err(|e| JsValue::from_str(&format!("Failed to add memory file: {}", e)))?;
+            if let Some(comment) = comment {
+                probe.with_file_comment(name, comment);
+            }
+            lens.push(len);
+        }
+
+        probe
+            .estimate_payload_len(&lens)
+            .map_err(|e| JsValue::from_str(&format!("Failed to estimate the payload: {}", e)))
+    }
+
+    fn padding_buckets(&self) -> Result<Option<PaddingBuckets>, JsValue> {
+        match self.padding.as_deref() {
+            None | Some("") => Ok(None),
+            Some("pow2") => Ok(Some(PaddingBuckets::PowersOfTwo)),
+            Some(size) => size
+                .parse()
+                .map(|size| Some(PaddingBuckets::MultipleOf(size)))
+                .map_err(|_| JsValue::from_str(&format!("Invalid padding: {}", size))),
+        }
+    }
+
+    pub(crate) fn image_format(&self) -> ImageFormat {
+        match self
+            .output_format
+            .as_deref()
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("webp") => ImageFormat::WebP,
+            Some("avif") => ImageFormat::Avif,
+            _ => ImageFormat::Png,
+        }
+    }
+
+    pub(crate) fn color_profile_policy(&self) -> Result<ColorProfilePolicy, JsValue> {
+        ColorProfilePolicy::parse(self.color_profile.as_deref())
+    }
+
+    pub(crate) fn pixel_channel_order(&self) -> Result<ChannelOrder, JsValue> {
+        ChannelOrder::parse(self.channel_order.as_deref())
+    }
+
+    /// the format of the stego image, validates the JPEG XL effort
+    pub(crate) fn output(&self) -> Result<OutputFormat, JsValue> {
+        if let Some(codec) = self.output_format.as_deref().and_then(codecs::encoder_id) {
+            return Ok(OutputFormat::External { codec });
+        }
+        let is_jxl = self
+            .output_format
+            .as_deref()
+            .is_some_and(|format| format.eq_ignore_ascii_case("jxl"));
+        if !is_jxl {
+            return Ok(OutputFormat::Image(self.image_format()));
+        }
+
+        jxl_output(self.jxl_effort.unwrap_or(DEFAULT_JXL_EFFORT)).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// the metadata record, only if any of its fields was set
+    fn metadata(&self) -> Option<Metadata> {
+        if self.author.is_none() && self.created_at.is_none() && self.app_version.is_none() {
+            return None;
+        }
+
+        Some(Metadata {
+            author: self.author.clone(),
+            created_at: self.created_at.map(|ms| (ms / 1000.0) as u64),
+            app_version: self
+                .app_version
+                .clone()
+                .or_else(|| Some(env!("CARGO_PKG_VERSION").to_string())),
+        })
+    }
+
+    pub(crate) fn codec_options(&self) -> Result<CodecOptions, JsValue> {
+        Ok(CodecOptions {
+            sanitize_lsb_plane: self.sanitize_lsb,
+            alpha_policy: parse_alpha_policy(self.alpha_policy.as_deref())?,
+            stealth_level: check_stealth(self.stealth)?,
+            banding_aware: self.banding_aware,
+            redundancy: self.redundancy(),
+            ..CodecOptions::default()
+        })
+    }
+
+    /// builds an encoder with the codec options and encryption set up
+    pub(crate) fn encoder(&self) -> Result<SteganoEncoder, JsValue> {
+        self.encoder_with(self.codec_options()?)
+    }
+
+    /// same as `encoder`, but with the given codec options
+    pub(crate) fn encoder_with(
+        &self,
+        codec_options: CodecOptions,
+    ) -> Result<SteganoEncoder, JsValue> {
+        let mut encoder = SteganoEncoder::with_options(codec_options);
+        encoder.with_output_format(self.image_format());
+        if let Some(buckets) = self.padding_buckets()? {
+            encoder.with_padding(buckets);
+        }
+        if let Some(metadata) = self.metadata() {
+            encoder.with_metadata(metadata);
+        }
+
+        if let Some((passwords, threshold)) = &self.threshold_passwords {
+            let threshold = *threshold;
+            if threshold == 0 || threshold as usize > passwords.len() {
+                return Err(JsValue::from_str(&format!(
+                    "Invalid threshold: {} of {} passwords",
+                    threshold,
+                    passwords.len()
+                )));
+            }
+            let passwords = passwords
+                .iter()
+                .map(reveal)
+                .collect::<Result<Vec<_>, _>>()?;
+            encoder.with_threshold_encryption(passwords.iter().map(|p| p.as_str()), threshold);
+        } else if let Some(password) = &self.password {
+            encoder.with_encryption(reveal(password)?.as_str());
+        }
+
+        if let Some(token) = &self.timestamp_token {
+            encoder.with_timestamp_token(token.clone());
+        }
+
+        if let Some(key) = &self.signing_key {
+            let key = key
+                .with_bytes(|b| Zeroizing::new(<[u8; 32]>::try_from(b).unwrap_or_default()))
+                .ok_or_else(|| JsValue::from_str("Signing key has been wiped"))?;
+            encoder.with_signing_key(&key);
+        }
+
+        Ok(encoder)
+    }
+}
+
+/// Options for `unveil_data_with_options`, created in JS with `new UnveilOptions()`.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct UnveilOptions {
+    special_entries: Option<String>,
+    collisions: Option<String>,
+    order: Option<String>,
+    alpha_policy: Option<String>,
+    stealth: u8,
+    banding_aware: bool,
+    redundancy: u8,
+    channel_order: Option<String>,
+    legacy_formats: Option<String>,
+    password: Option<Secret>,
+    threshold_passwords: Option<Vec<Secret>>,
+}
+
+#[wasm_bindgen]
+impl UnveilOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> UnveilOptions {
+        UnveilOptions::default()
+    }
+
+    /// What happens to symlinks and special files found in the hidden container:
+    /// `"skip"` (default), `"regular"` to unveil them as regular files,
+    /// or `"link_target"` to unveil symlinks as empty files that carry their target
+    #[wasm_bindgen(getter)]
+    pub fn special_entries(&self) -> Option<String> {
+        self.special_entries.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_special_entries(&mut self, special_entries: Option<String>) {
+        self.special_entries = special_entries;
+    }
+
+    /// What happens to files with the same name inside of the container: `"rename"` (default)
+    /// gives the later ones a suffix like `report (1).pdf`, `"keep_first"` unveils only the first one,
+    /// `"error"` fails. Renamed and skipped files are reported in the warnings
+    #[wasm_bindgen(getter)]
+    pub fn collisions(&self) -> Option<String> {
+        self.collisions.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_collisions(&mut self, collisions: Option<String>) {
+        self.collisions = collisions;
+    }
+
+    /// The order of the unveiled files: `"embedded"` (default) as they were hidden, the hidden text comes last,
+    /// `"name"` or `"size"` (smallest first). `UnveiledFile.index` always tells the embed order
+    #[wasm_bindgen(getter)]
+    pub fn order(&self) -> Option<String> {
+        self.order.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_order(&mut self, order: Option<String>) {
+        self.order = order;
+    }
+
+    /// The alpha policy the data was hidden with, see `HideOptions.alpha_policy`
+    #[wasm_bindgen(getter)]
+    pub fn alpha_policy(&self) -> Option<String> {
+        self.alpha_policy.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_alpha_policy(&mut self, alpha_policy: Option<String>) {
+        self.alpha_policy = alpha_policy;
+    }
+
+    /// The stealth level the data was hidden with, see `HideOptions.stealth`
+    #[wasm_bindgen(getter)]
+    pub fn stealth(&self) -> u8 {
+        self.stealth
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_stealth(&mut self, stealth: u8) {
+        self.stealth = stealth;
+    }
+
+    /// If the data was hidden banding aware, see `HideOptions.banding_aware`
+    #[wasm_bindgen(getter)]
+    pub fn banding_aware(&self) -> bool {
+        self.banding_aware
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_banding_aware(&mut self, banding_aware: bool) {
+        self.banding_aware = banding_aware;
+    }
+
+    /// The redundancy the data was hidden with, see `HideOptions.redundancy`
+    #[wasm_bindgen(getter)]
+    pub fn redundancy(&self) -> u8 {
+        self.redundancy.max(1)
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_redundancy(&mut self, redundancy: u8) {
+        self.redundancy = redundancy;
+    }
+
+    /// The byte order of raw pixels given to `unveil_data_with_pixels`, see `HideOptions.channel_order`
+    #[wasm_bindgen(getter)]
+    pub fn channel_order(&self) -> Option<String> {
+        self.channel_order.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_channel_order(&mut self, channel_order: Option<String>) {
+        self.channel_order = channel_order;
+    }
+
+    /// Which containers of older stegano-rs versions are read: `"detect"` (default) tells them by their header,
+    /// `"reject"` refuses them, `"v1"` or `"v2"` reads only that old layout
+    #[wasm_bindgen(getter)]
+    pub fn legacy_formats(&self) -> Option<String> {
+        self.legacy_formats.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_legacy_formats(&mut self, legacy_formats: Option<String>) {
+        self.legacy_formats = legacy_formats;
+    }
+
+    /// Encodes all options but the passwords into a short string like `stegano1:a=opaque_only;s=2`,
+    /// to be shared along with a carrier, e.g. as QR code. Options left at their default are left out
+    pub fn to_config(&self) -> Result<String, JsValue> {
+        self.validate()?;
+        let text = |key: &str, value: &Option<String>| match value.as_deref() {
+            None | Some("") => None,
+            Some(value) => Some(format!("{key}={value}")),
+        };
+        let pairs: Vec<_> = [
+            text("a", &self.alpha_policy),
+            (self.stealth > 0).then(|| format!("s={}", self.stealth)),
+            self.banding_aware.then(|| "b=1".to_string()),
+            (self.redundancy > 1).then(|| format!("r={}", self.redundancy)),
+            text("c", &self.channel_order),
+            text("l", &self.legacy_formats),
+            text("e", &self.special_entries),
+            text("x", &self.collisions),
+            text("o", &self.order),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        Ok(format!("{}{}", CONFIG_PREFIX, pairs.join(";")))
+    }
+
+    /// Takes the options back from a string of `to_config`, unknown keys of later versions are ignored.
+    /// The passwords still have to be set
+    pub fn from_config(config: &str) -> Result<UnveilOptions, JsValue> {
+        let invalid = |detail: &str| JsValue::from_str(&format!("Invalid config: {}", detail));
+        let pairs = config
+            .trim()
+            .strip_prefix(CONFIG_PREFIX)
+            .ok_or_else(|| invalid("it does not start with `stegano1:`"))?;
+
+        let mut options = UnveilOptions::default();
+        for pair in pairs.split(';').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').ok_or_else(|| invalid(pair))?;
+            let text = Some(value.to_string());
+            match key {
+                "a" => options.alpha_policy = text,
+                "s" => options.stealth = value.parse().map_err(|_| invalid(pair))?,
+                "b" => options.banding_aware = value == "1",
+                "r" => options.redundancy = value.parse().map_err(|_| invalid(pair))?,
+                "c" => options.channel_order = text,
+                "l" => options.legacy_formats = text,
+                "e" => options.special_entries = text,
+                "x" => options.collisions = text,
+                "o" => options.order = text,
+                _ => {}
+            }
+        }
+        options.validate()?;
+
+        Ok(options)
+    }
+
+    /// Decrypt with the given password, `undefined` expects the data to be not encrypted
+    pub fn set_password(&mut self, password: Option<String>) {
+        self.password = password.map(|p| Secret::new(p.into_bytes()));
+    }
+
+    /// Decrypt with the password of the given `KeyHandle`
+    pub fn use_key(&mut self, key: &KeyHandle) -> Result<(), JsValue> {
+        self.password = Some(Secret::new(key.password()?.as_bytes().to_vec()));
+        Ok(())
+    }
+
+    /// Decrypt data that was hidden with a threshold of passwords,
+    /// this takes precedence over a single password
+    pub fn set_threshold_passwords(&mut self, passwords: Vec<String>) {
+        let passwords = passwords
+            .into_iter()
+            .map(|p| Secret::new(p.into_bytes()))
+            .collect();
+        self.threshold_passwords = Some(passwords);
+    }
+
+    /// Adds the password of the given `KeyHandle` to the threshold passwords
+    pub fn add_threshold_key(&mut self, key: &KeyHandle) -> Result<(), JsValue> {
+        let password = Secret::new(key.password()?.as_bytes().to_vec());
+        self.threshold_passwords
+            .get_or_insert_with(Vec::new)
+            .push(password);
+        Ok(())
+    }
+}
+
+impl UnveilOptions {
+    pub(crate) fn pixel_channel_order(&self) -> Result<ChannelOrder, JsValue> {
+        ChannelOrder::parse(self.channel_order.as_deref())
+    }
+
+    fn special_entry_policy(&self) -> Result<SpecialEntryPolicy, JsValue> {
+        match self.special_entries.as_deref() {
+            None | Some("") | Some("skip") => Ok(SpecialEntryPolicy::Skip),
+            Some("regular") => Ok(SpecialEntryPolicy::AsRegularFile),
+            Some("link_target") => Ok(SpecialEntryPolicy::LinkTargetAsMetadata),
+            Some(other) => Err(JsValue::from_str(&format!(
+                "Invalid special entry policy: {}",
+                other
+            ))),
+        }
+    }
+
+    fn collision_policy(&self) -> Result<CollisionPolicy, JsValue> {
+        match self.collisions.as_deref() {
+            None | Some("") | Some("rename") => Ok(CollisionPolicy::Rename),
+            Some("keep_first") => Ok(CollisionPolicy::KeepFirst),
+            Some("error") => Ok(CollisionPolicy::Error),
+            Some(other) => Err(JsValue::from_str(&format!(
+                "Invalid collision policy: {}",
+                other
+            ))),
+        }
+    }
+
+    fn file_order(&self) -> Result<FileOrder, JsValue> {
+        match self.order.as_deref() {
+            None | Some("") | Some("embedded") => Ok(FileOrder::Embedded),
+            Some("name") => Ok(FileOrder::Name),
+            Some("size") => Ok(FileOrder::Size),
+            Some(other) => Err(JsValue::from_str(&format!("Invalid file order: {}", other))),
+        }
+    }
+
+    fn legacy_format_selection(&self) -> Result<LegacyFormats, JsValue> {
+        match self.legacy_formats.as_deref() {
+            None | Some("") | Some("detect") => Ok(LegacyFormats::Detect),
+            Some("reject") => Ok(LegacyFormats::Reject),
+            Some("v1") => Ok(LegacyFormats::OnlyV1),
+            Some("v2") => Ok(LegacyFormats::OnlyV2),
+            Some(other) => Err(JsValue::from_str(&format!(
+                "Invalid legacy formats: {}",
+                other
+            ))),
+        }
+    }
+
+    /// checks all options but the passwords
+    fn validate(&self) -> Result<(), JsValue> {
+        parse_alpha_policy(self.alpha_policy.as_deref())?;
+        check_stealth(self.stealth)?;
+        self.pixel_channel_order()?;
+        self.legacy_format_selection()?;
+        self.special_entry_policy()?;
+        self.collision_policy()?;
+        self.file_order()?;
+
+        Ok(())
+    }
+
+    /// prepares the unveil API with the policy and decryption set up
+    pub(crate) fn unveil(&self) -> Result<UnveilApi, JsValue> {
+        let mut unveil = unveil::prepare()
+            .with_special_entry_policy(self.special_entry_policy()?)
+            .with_legacy_formats(self.legacy_format_selection()?)
+            .with_collision_policy(self.collision_policy()?)
+            .with_file_order(self.file_order()?)
+            .with_options(CodecOptions {
+                alpha_policy: parse_alpha_policy(self.alpha_policy.as_deref())?,
+                stealth_level: check_stealth(self.stealth)?,
+                banding_aware: self.banding_aware,
+                redundancy: self.redundancy(),
+                ..CodecOptions::default()
+            });
+
+        if let Some(passwords) = &self.threshold_passwords {
+            let passwords = passwords
+                .iter()
+                .map(reveal)
+                .collect::<Result<Vec<_>, _>>()?;
+            unveil = unveil.using_threshold_passwords(passwords.iter().map(|p| p.as_str()));
+        } else if let Some(password) = &self.password {
+            unveil = unveil.using_password(reveal(password)?.as_str());
+        }
+
+        Ok(unveil)
+    }
+}
+
+fn parse_alpha_policy(policy: Option<&str>) -> Result<AlphaPolicy, JsValue> {
+    match policy {
+        None | Some("") | Some("all") => Ok(AlphaPolicy::AllPixels),
+        Some("skip_transparent") => Ok(AlphaPolicy::SkipTransparent),
+        Some("opaque_only") => Ok(AlphaPolicy::OpaqueOnly),
+        Some(other) => Err(JsValue::from_str(&format!(
+            "Invalid alpha policy: {}",
+            other
+        ))),
+    }
+}
+
+fn check_stealth(stealth: u8) -> Result<u8, JsValue> {
+    if stealth > MAX_STEALTH_LEVEL {
+        return Err(JsValue::from_str(&format!(
+            "Invalid stealth level: {}, expected 0 to {}",
+            stealth, MAX_STEALTH_LEVEL
+        )));
+    }
+
+    Ok(stealth)
+}
+
+fn reveal(secret: &Secret) -> Result<Zeroizing<String>, JsValue> {
+    secret
+        .to_zeroizing_string()
+        .ok_or_else(|| JsValue::from_str("Password has been wiped"))
+}
+
+/// the JPEG XL output, if the effort is in range
+fn jxl_output(effort: u8) -> Result<OutputFormat, String> {
+    if !(1..=9).contains(&effort) {
+        return Err(format!(
+            "Invalid JPEG XL effort: {}, expected 1 to 9",
+            effort
+        ));
+    }
+
+    Ok(OutputFormat::Jxl { effort })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_handle::derive_key;
+
+    #[test]
+    fn should_apply_the_preset_of_a_channel() {
+        let mut options = HideOptions::new();
+        options.use_preset("email").unwrap();
+        assert_eq!(options.output_format(), Some("webp".to_string()));
+        assert_eq!(options.color_profile(), Some("srgb".to_string()));
+
+        options.set_output_format(Some("png".to_string()));
+        assert_eq!(options.output_format(), Some("png".to_string()));
+
+        options.set_stealth(2);
+        options.use_preset("discord").unwrap();
+        let codec_options = options.codec_options().unwrap();
+        assert_eq!(codec_options.alpha_policy, AlphaPolicy::OpaqueOnly);
+        assert_eq!(
+            codec_options.stealth_level, 0,
+            "the preset decides the embedding"
+        );
+        assert_eq!(codec_options.redundancy, 3);
+        assert_eq!(
+            options.unveil_config().unwrap(),
+            "stegano1:a=opaque_only;r=3"
+        );
+    }
+
+    #[test]
+    fn should_collect_the_threshold_keys() {
+        let mut options = HideOptions::new();
+        options
+            .add_threshold_key(&derive_key("Secret42".to_string()), 1)
+            .unwrap();
+        options
+            .add_threshold_key(&derive_key("Secret43".to_string()), 2)
+            .unwrap();
+        let (passwords, threshold) = options.threshold_passwords.as_ref().unwrap();
+        assert_eq!(passwords.len(), 2);
+        assert_eq!(*threshold, 2);
+
+        let mut options = UnveilOptions::new();
+        options
+            .add_threshold_key(&derive_key("Secret42".to_string()))
+            .unwrap();
+        assert_eq!(options.threshold_passwords.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn should_take_the_options_back_from_the_config() {
+        let mut hide_options = HideOptions::new();
+        hide_options.set_alpha_policy(Some("opaque_only".to_string()));
+        hide_options.set_stealth(2);
+        hide_options.set_banding_aware(true);
+        let config = hide_options.unveil_config().unwrap();
+        assert_eq!(config, "stegano1:a=opaque_only;s=2;b=1");
+
+        let mut options = UnveilOptions::from_config(&config).unwrap();
+        assert_eq!(options.alpha_policy(), Some("opaque_only".to_string()));
+        assert_eq!(options.stealth(), 2);
+        assert!(options.banding_aware());
+
+        options.set_order(Some("name".to_string()));
+        options.set_password(Some("not shared".to_string()));
+        let config = options.to_config().unwrap();
+        assert_eq!(config, "stegano1:a=opaque_only;s=2;b=1;o=name");
+        let options = UnveilOptions::from_config(&format!("{config};z=later")).unwrap();
+        assert_eq!(options.order(), Some("name".to_string()));
+        assert!(options.password.is_none());
+        let options = UnveilOptions::from_config("stegano1:r=3").unwrap();
+        assert_eq!(options.redundancy(), 3);
+
+        assert_eq!(
+            UnveilOptions::new().to_config().unwrap(),
+            "stegano1:",
+            "defaults are left out"
+        );
+    }
+
+    #[test]
+    fn should_validate_the_jxl_effort() {
+        assert_eq!(jxl_output(9), Ok(OutputFormat::Jxl { effort: 9 }));
+        assert!(jxl_output(0).is_err(), "the effort starts at 1");
+        assert!(jxl_output(10).is_err(), "the effort ends at 9");
+    }
+}
@@ -0,0 +1,177 @@
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{ExtendedColorType, ImageEncoder, ImageFormat, RgbaImage};
+use stegano_core::media::Media;
+use wasm_bindgen::prelude::*;
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::EncoderOptions;
+use zune_jpegxl::JxlSimpleEncoder;
+
+use crate::options::DEFAULT_JXL_EFFORT;
+use crate::{codecs, jxl};
+
+/// The format the stego image is encoded in, see `HideOptions.output_format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    Image(ImageFormat),
+    /// always lossless, the effort goes from 1 (fastest) to 9 (smallest)
+    Jxl {
+        effort: u8,
+    },
+    /// an encoder registered from JS, see `register_encoder`
+    External {
+        codec: u32,
+    },
+}
+
+impl OutputFormat {
+    /// The format of the stego image in `data`, to write it back as it came, e.g. by `rekey`.
+    /// Formats without an encoder here, or lossy ones that would lose the hidden data, are an error
+    pub(crate) fn of(data: &[u8]) -> Result<OutputFormat, String> {
+        if let Some(name) = codecs::decoder_name(data) {
+            return codecs::encoder_id(&name)
+                .map(|codec| OutputFormat::External { codec })
+                .ok_or_else(|| format!("no encoder is registered for {}", name));
+        }
+        if jxl::is_jxl(data) {
+            return Ok(OutputFormat::Jxl {
+                effort: DEFAULT_JXL_EFFORT,
+            });
+        }
+
+        match image::guess_format(data) {
+            Ok(format @ (ImageFormat::Png | ImageFormat::WebP | ImageFormat::Bmp)) => {
+                Ok(OutputFormat::Image(format))
+            }
+            Ok(format) => Err(format!("{:?} images cannot be written back", format)),
+            Err(_) => Err("the image format is unknown".to_string()),
+        }
+    }
+
+    /// Encodes the stego image, with the given ICC color profile if the format can hold one
+    pub(crate) fn encode(
+        self,
+        media: &mut Media,
+        icc_profile: Option<&[u8]>,
+    ) -> Result<Vec<u8>, JsValue> {
+        match (self, media) {
+            (OutputFormat::Image(format), Media::Image(img)) if icc_profile.is_some() => {
+                encode_with_icc_profile(img, format, icc_profile.unwrap_or_default())
+            }
+            (OutputFormat::Image(format), media) => {
+                let mut buf = std::io::Cursor::new(Vec::new());
+                media
+                    .save_to_writer(&mut buf, format)
+                    .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
+
+                Ok(buf.into_inner())
+            }
+            (OutputFormat::Jxl { effort }, Media::Image(img)) => {
+                let options = EncoderOptions::new(
+                    img.width() as usize,
+                    img.height() as usize,
+                    ColorSpace::RGBA,
+                    BitDepth::Eight,
+                )
+                .set_effort(effort);
+
+                JxlSimpleEncoder::new(img.as_raw(), options)
+                    .encode()
+                    .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {:?}", e)))
+            }
+            (OutputFormat::Jxl { .. }, Media::Audio(_)) => Err(JsValue::from_str(
+                "Failed to encode image: audio cannot be encoded as JPEG XL",
+            )),
+            (OutputFormat::External { codec }, Media::Image(img)) => codecs::encode(codec, img),
+            (OutputFormat::External { .. }, Media::Audio(_)) => Err(JsValue::from_str(
+                "Failed to encode image: audio cannot be encoded by a registered encoder",
+            )),
+        }
+    }
+}
+
+fn encode_with_icc_profile(
+    img: &RgbaImage,
+    format: ImageFormat,
+    icc_profile: &[u8],
+) -> Result<Vec<u8>, JsValue> {
+    let mut buf = Vec::new();
+    match format {
+        ImageFormat::Png => write_with_icc_profile(PngEncoder::new(&mut buf), img, icc_profile)?,
+        ImageFormat::WebP => {
+            write_with_icc_profile(WebPEncoder::new_lossless(&mut buf), img, icc_profile)?
+        }
+        other => {
+            return Err(JsValue::from_str(&format!(
+                "Failed to encode image: {:?} cannot hold a color profile",
+                other
+            )))
+        }
+    }
+
+    Ok(buf)
+}
+
+fn write_with_icc_profile(
+    mut encoder: impl ImageEncoder,
+    img: &RgbaImage,
+    icc_profile: &[u8],
+) -> Result<(), JsValue> {
+    encoder
+        .set_icc_profile(icc_profile.to_vec())
+        .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
+    encoder
+        .write_image(
+            img.as_raw(),
+            img.width(),
+            img.height(),
+            ExtendedColorType::Rgba8,
+        )
+        .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_encode_jxl() {
+        let img = image::RgbaImage::from_pixel(16, 8, image::Rgba([1, 2, 3, 255]));
+
+        let jxl = OutputFormat::Jxl { effort: 4 }
+            .encode(&mut Media::from_image(img), None)
+            .unwrap();
+        assert_eq!(jxl[..2], [0xff, 0x0a], "it should be a JPEG XL codestream");
+    }
+
+    #[test]
+    fn should_write_back_the_format_of_the_stego_image() {
+        let img = image::RgbaImage::from_pixel(16, 8, image::Rgba([1, 2, 3, 255]));
+        let encoded = |format| {
+            let mut buf = std::io::Cursor::new(Vec::new());
+            img.write_to(&mut buf, format).unwrap();
+            buf.into_inner()
+        };
+        let jxl = OutputFormat::Jxl { effort: 4 }
+            .encode(&mut Media::from_image(img.clone()), None)
+            .unwrap();
+
+        assert_eq!(
+            OutputFormat::of(&encoded(ImageFormat::Bmp)),
+            Ok(OutputFormat::Image(ImageFormat::Bmp))
+        );
+        assert_eq!(
+            OutputFormat::of(&encoded(ImageFormat::WebP)),
+            Ok(OutputFormat::Image(ImageFormat::WebP))
+        );
+        assert!(matches!(
+            OutputFormat::of(&jxl),
+            Ok(OutputFormat::Jxl { .. })
+        ));
+        assert!(
+            OutputFormat::of(b"GIF89a").is_err(),
+            "it should not turn a GIF into a PNG"
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+
+/// the most words a passphrase can have
+const MAX_WORDS: u32 = 64;
+
+/// consonants and vowels of the generated words, 20 * 5 * 20 * 5 * 20 is about 17.6 bits per word,
+/// more than the 12.9 bits of a diceware word
+const CONSONANTS: &[u8] = b"bcdfghjklmnpqrstvwxz";
+const VOWELS: &[u8] = b"aeiou";
+
+/// Generates a passphrase of `words` words, picked uniformly with the CSPRNG of the browser and joined with `-`.
+/// The words are taken from `wordlist`, e.g. the EFF diceware list, duplicates and blank lines are ignored.
+/// Without a wordlist, pronounceable words of five letters are made up.
+#[wasm_bindgen]
+pub fn generate_passphrase(words: u32, wordlist: Option<Vec<String>>) -> Result<String, JsValue> {
+    if words == 0 || words > MAX_WORDS {
+        return Err(JsValue::from_str(&format!(
+            "Invalid number of words: {}, expected 1 to {}",
+            words, MAX_WORDS
+        )));
+    }
+    let wordlist = wordlist.map(unique_words);
+    if let Some(list) = &wordlist {
+        if list.len() < 2 {
+            return Err(JsValue::from_str(
+                "Invalid wordlist: expected at least 2 different words",
+            ));
+        }
+    }
+
+    let mut passphrase = Zeroizing::new(Vec::with_capacity(words as usize));
+    for _ in 0..words {
+        let word = match &wordlist {
+            Some(list) => list[random_below(list.len())?].clone(),
+            None => made_up_word()?,
+        };
+        passphrase.push(word);
+    }
+
+    Ok(passphrase.join("-"))
+}
+
+fn unique_words(mut wordlist: Vec<String>) -> Vec<String> {
+    wordlist
+        .iter_mut()
+        .for_each(|word| *word = word.trim().to_string());
+    wordlist.retain(|word| !word.is_empty());
+    wordlist.sort();
+    wordlist.dedup();
+    wordlist
+}
+
+/// alternates consonants and vowels, e.g. `bakod`
+fn made_up_word() -> Result<String, JsValue> {
+    (0..5)
+        .map(|i| {
+            let letters = if i % 2 == 0 { CONSONANTS } else { VOWELS };
+            Ok(letters[random_below(letters.len())?] as char)
+        })
+        .collect()
+}
+
+/// a uniform random number in `0..bound`, rejection sampling avoids the modulo bias
+fn random_below(bound: usize) -> Result<usize, JsValue> {
+    let bound = bound as u64;
+    let zone = u64::MAX - u64::MAX % bound;
+    loop {
+        let mut bytes = [0; 8];
+        getrandom::getrandom(&mut bytes)
+            .map_err(|e| JsValue::from_str(&format!("Failed to generate passphrase: {}", e)))?;
+        let n = u64::from_le_bytes(bytes);
+        if n < zone {
+            return Ok((n % bound) as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_pick_words_from_the_wordlist() {
+        let wordlist = vec![
+            "apple".to_string(),
+            " banana\r".to_string(),
+            "".to_string(),
+            "apple".to_string(),
+        ];
+
+        let passphrase = generate_passphrase(6, Some(wordlist)).unwrap();
+        let words: Vec<_> = passphrase.split('-').collect();
+        assert_eq!(words.len(), 6);
+        assert!(words.iter().all(|w| *w == "apple" || *w == "banana"));
+    }
+
+    #[test]
+    fn should_make_up_pronounceable_words() {
+        let passphrase = generate_passphrase(4, None).unwrap();
+        let words: Vec<_> = passphrase.split('-').collect();
+        assert_eq!(words.len(), 4);
+        for word in words {
+            assert_eq!(word.len(), 5);
+            assert!(VOWELS.contains(&word.as_bytes()[1]));
+        }
+    }
+
+    #[test]
+    fn should_stay_below_the_bound() {
+        assert!((0..1000).all(|_| random_below(7).unwrap() < 7));
+        assert_eq!(random_below(1).unwrap(), 0);
+    }
+}
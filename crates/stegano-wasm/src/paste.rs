@@ -0,0 +1,183 @@
+//! Browsers hand out pasted images in odd shapes: as data URI text, or as the `CF_DIB` of the Windows clipboard,
+//! a BMP without its file header that sometimes wraps a PNG. Those get normalized here, before decoding.
+
+use std::borrow::Cow;
+
+/// the compressions of a DIB that wrap a whole PNG or JPEG
+const BI_JPEG: u32 = 4;
+const BI_PNG: u32 = 5;
+/// the color masks follow a plain `BITMAPINFOHEADER`
+const BI_BITFIELDS: u32 = 3;
+
+/// the sizes of `BITMAPINFOHEADER`, `BITMAPV2INFOHEADER` up to `BITMAPV5HEADER`
+const DIB_HEADER_SIZES: [u32; 5] = [40, 52, 56, 108, 124];
+const FILE_HEADER_SIZE: u32 = 14;
+
+/// Turns pasted bytes into something the image decoders know, anything else is passed through untouched
+pub(crate) fn normalize(data: &[u8]) -> Cow<'_, [u8]> {
+    if let Some(decoded) = decode_data_uri(data) {
+        return Cow::Owned(normalize(&decoded).into_owned());
+    }
+    if let Some(bmp) = unwrap_dib(data) {
+        return bmp;
+    }
+
+    Cow::Borrowed(data)
+}
+
+/// the payload of `data:image/png;base64,...`
+fn decode_data_uri(data: &[u8]) -> Option<Vec<u8>> {
+    let uri = data.trim_ascii().strip_prefix(b"data:")?;
+    let comma = uri.iter().position(|&b| b == b',')?;
+    let (header, payload) = (&uri[..comma], &uri[comma + 1..]);
+    if !header.ends_with(b";base64") {
+        return None;
+    }
+
+    decode_base64(payload)
+}
+
+/// standard or URL safe base64, whitespace and padding are ignored
+fn decode_base64(text: &[u8]) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let (mut acc, mut bits) = (0u32, 0);
+    for &c in text {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        acc = acc << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+
+    Some(bytes)
+}
+
+/// A DIB as BMP with file header, or the PNG or JPEG it wraps
+fn unwrap_dib(data: &[u8]) -> Option<Cow<'_, [u8]>> {
+    let u32_at = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(
+            data.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    let header_size = u32_at(0)?;
+    let planes = u16::from_le_bytes(data.get(12..14)?.try_into().ok()?);
+    if !DIB_HEADER_SIZES.contains(&header_size) || planes != 1 {
+        return None;
+    }
+    let bit_count = u16::from_le_bytes(data.get(14..16)?.try_into().ok()?);
+    let compression = u32_at(16)?;
+
+    if compression == BI_PNG || compression == BI_JPEG {
+        return data.get(header_size as usize..).map(Cow::Borrowed);
+    }
+
+    let masks = if compression == BI_BITFIELDS && header_size == 40 {
+        12
+    } else {
+        0
+    };
+    let colors = match u32_at(32)? {
+        0 if bit_count <= 8 => 1 << bit_count,
+        used => used,
+    };
+    let pixels_offset = FILE_HEADER_SIZE
+        .checked_add(header_size)?
+        .checked_add(masks)?
+        .checked_add(colors.checked_mul(4)?)?;
+    let file_size = u32::try_from(data.len())
+        .ok()?
+        .checked_add(FILE_HEADER_SIZE)?;
+
+    let mut bmp = Vec::with_capacity(file_size as usize);
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&file_size.to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&pixels_offset.to_le_bytes());
+    bmp.extend_from_slice(data);
+
+    Some(Cow::Owned(bmp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png() -> Vec<u8> {
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::from_pixel(4, 3, image::Rgba([10, 20, 30, 255]))
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+        png.into_inner()
+    }
+
+    fn dib_header(compression: u32) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend_from_slice(&40u32.to_le_bytes());
+        header.extend_from_slice(&4i32.to_le_bytes());
+        header.extend_from_slice(&3i32.to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes());
+        header.extend_from_slice(&24u16.to_le_bytes());
+        header.extend_from_slice(&compression.to_le_bytes());
+        header.extend_from_slice(&[0; 20]);
+        header
+    }
+
+    #[test]
+    fn should_decode_a_data_uri() {
+        let png = png();
+        let uri = format!("data:image/png;base64,{}", encode_base64(&png));
+
+        assert_eq!(normalize(uri.as_bytes()).as_ref(), png.as_slice());
+        assert_eq!(
+            normalize(&png).as_ref(),
+            png.as_slice(),
+            "a PNG stays as is"
+        );
+    }
+
+    #[test]
+    fn should_add_the_file_header_to_a_dib() {
+        let mut dib = dib_header(0);
+        // 3 rows of 4 BGR pixels, each row padded to 12 bytes
+        dib.extend(std::iter::repeat_n([30, 20, 10], 12).flatten());
+
+        let bmp = normalize(&dib);
+        let img = image::load_from_memory(&bmp).unwrap().to_rgba8();
+        assert_eq!(img.dimensions(), (4, 3));
+        assert_eq!(img.get_pixel(3, 2).0, [10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn should_unwrap_a_png_in_a_dib() {
+        let png = png();
+        let mut dib = dib_header(BI_PNG);
+        dib.extend_from_slice(&png);
+
+        assert_eq!(normalize(&dib).as_ref(), png.as_slice());
+    }
+
+    fn encode_base64(data: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        data.chunks(3)
+            .flat_map(|chunk| {
+                let n =
+                    chunk.iter().fold(0u32, |n, b| n << 8 | *b as u32) << (8 * (3 - chunk.len()));
+                (0..4).map(move |i| match i {
+                    i if i > chunk.len() => '=',
+                    i => ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,281 @@
+use image::RgbaImage;
+use stegano_core::media::Media;
+use wasm_bindgen::prelude::*;
+use web_sys::ImageBitmap;
+use zeroize::Zeroizing;
+
+use crate::options::{HideOptions, UnveilOptions};
+use crate::{
+    canvas, carrier_from_image, decode, encoder_for, unveil_report, HideResult, UnveilResult,
+};
+
+/// The byte order of raw pixels, see `HideOptions.channel_order`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChannelOrder {
+    Rgba,
+    Bgra,
+    Rgb,
+    Bgr,
+}
+
+impl ChannelOrder {
+    pub(crate) fn parse(order: Option<&str>) -> Result<Self, JsValue> {
+        match order.map(str::to_lowercase).as_deref() {
+            None | Some("") | Some("rgba") => Ok(Self::Rgba),
+            Some("bgra") => Ok(Self::Bgra),
+            Some("rgb") => Ok(Self::Rgb),
+            Some("bgr") => Ok(Self::Bgr),
+            Some(other) => Err(JsValue::from_str(&format!(
+                "Invalid channel order: {}",
+                other
+            ))),
+        }
+    }
+
+    fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgba | Self::Bgra => 4,
+            Self::Rgb | Self::Bgr => 3,
+        }
+    }
+
+    fn is_bgr(self) -> bool {
+        matches!(self, Self::Bgra | Self::Bgr)
+    }
+
+    /// Reads raw pixels in this order, `None` if there are not exactly `width`x`height` of them.
+    /// Pixels without alpha become opaque
+    fn read_pixels(self, pixels: Vec<u8>, width: u32, height: u32) -> Option<RgbaImage> {
+        let pixel_count = width as usize * height as usize;
+        if pixels.len() != pixel_count * self.bytes_per_pixel() {
+            return None;
+        }
+        let mut rgba = match self {
+            Self::Rgba | Self::Bgra => pixels,
+            Self::Rgb | Self::Bgr => pixels
+                .chunks_exact(3)
+                .flat_map(|p| [p[0], p[1], p[2], u8::MAX])
+                .collect(),
+        };
+        if self.is_bgr() {
+            rgba.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+        }
+
+        RgbaImage::from_raw(width, height, rgba)
+    }
+
+    /// Writes the pixels in this order, alpha is dropped for `Rgb` and `Bgr`
+    fn write_pixels(self, img: RgbaImage) -> Vec<u8> {
+        let mut pixels = match self {
+            Self::Rgba | Self::Bgra => img.into_raw(),
+            Self::Rgb | Self::Bgr => img.pixels().flat_map(|p| [p[0], p[1], p[2]]).collect(),
+        };
+        if self.is_bgr() {
+            let bytes_per_pixel = self.bytes_per_pixel();
+            pixels
+                .chunks_exact_mut(bytes_per_pixel)
+                .for_each(|p| p.swap(0, 2));
+        }
+
+        pixels
+    }
+}
+
+/// reads the raw pixels, with a message that tells how many bytes were expected
+fn image_from_pixels(
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+    order: ChannelOrder,
+) -> Result<RgbaImage, JsValue> {
+    decode::check_dimensions(width, height)?;
+    order.read_pixels(pixels, width, height).ok_or_else(|| {
+        JsValue::from_str(&format!(
+            "Failed to load image: expected {} bytes of {:?} pixels for {}x{}",
+            width as u64 * height as u64 * order.bytes_per_pixel() as u64,
+            order,
+            width,
+            height
+        ))
+    })
+}
+
+/// Same as `hide_data_with_options`, but the carrier is given as RGBA pixels the browser decoded already,
+/// e.g. from `getImageData` or `VideoFrame.copyTo`, so that nothing needs to be decoded in wasm.
+/// Set `premultiplied` if the color channels are premultiplied with alpha,
+/// the byte order of the pixels is taken from `HideOptions.channel_order`.
+#[wasm_bindgen]
+pub fn hide_data_with_pixels(
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+    premultiplied: bool,
+    secret_name: &str,
+    secret_data: Vec<u8>,
+    options: &HideOptions,
+) -> Result<HideResult, JsValue> {
+    let mut img = image_from_pixels(pixels, width, height, options.pixel_channel_order()?)?;
+    if premultiplied {
+        unpremultiply(&mut img);
+    }
+
+    hide_in_image(img, secret_name, secret_data, options)
+}
+
+/// Same as `hide_data_with_options`, but the carrier is an `ImageBitmap`,
+/// e.g. from `createImageBitmap(blob)` that decodes off the main thread.
+#[wasm_bindgen]
+pub fn hide_data_with_bitmap(
+    bitmap: &ImageBitmap,
+    secret_name: &str,
+    secret_data: Vec<u8>,
+    options: &HideOptions,
+) -> Result<HideResult, JsValue> {
+    decode::check_dimensions(bitmap.width(), bitmap.height())?;
+    let img = canvas::pixels_of_bitmap(bitmap).ok_or_else(|| {
+        JsValue::from_str(
+            "Failed to load image: the ImageBitmap cannot be read without OffscreenCanvas",
+        )
+    })?;
+
+    hide_in_image(img, secret_name, secret_data, options)
+}
+
+/// Same as `hide_data_with_pixels`, but the stego image is handed back as raw pixels in `HideResult.data`,
+/// in the same channel order and size, instead of being encoded. The carrier is never upscaled.
+#[wasm_bindgen]
+pub fn hide_data_into_pixels(
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+    secret_name: &str,
+    secret_data: Vec<u8>,
+    options: &HideOptions,
+) -> Result<HideResult, JsValue> {
+    let order = options.pixel_channel_order()?;
+    let img = image_from_pixels(pixels, width, height, order)?;
+    let secret_data = Zeroizing::new(secret_data);
+    let mut encoder = encoder_for(secret_name, &secret_data, options)?;
+    encoder.use_media_from_media(Media::from_image(img));
+
+    match encoder
+        .hide_to_media()
+        .map_err(|e| JsValue::from_str(&format!("Failed to hide data: {}", e)))?
+    {
+        Media::Image(img) => Ok(HideResult::new(order.write_pixels(img), Vec::new())),
+        Media::Audio(_) => Err(JsValue::from_str(
+            "Failed to hide data: the carrier is not an image",
+        )),
+    }
+}
+
+/// Same as `unveil_data_with_options`, but the stego image is given as raw pixels,
+/// in the byte order of `UnveilOptions.channel_order`
+#[wasm_bindgen]
+pub fn unveil_data_with_pixels(
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+    options: &UnveilOptions,
+) -> Result<UnveilResult, JsValue> {
+    let img = image_from_pixels(pixels, width, height, options.pixel_channel_order()?)?;
+
+    unveil_report(options.unveil()?, Media::from_image(img))
+}
+
+fn hide_in_image(
+    img: RgbaImage,
+    secret_name: &str,
+    secret_data: Vec<u8>,
+    options: &HideOptions,
+) -> Result<HideResult, JsValue> {
+    let secret_data = Zeroizing::new(secret_data);
+    let mut encoder = encoder_for(secret_name, &secret_data, options)?;
+    encoder.use_media_from_media(carrier_from_image(
+        img,
+        options.payload_size(secret_name, secret_data.len())?,
+        options,
+    )?);
+
+    let mut media = encoder
+        .hide_to_media()
+        .map_err(|e| JsValue::from_str(&format!("Failed to hide data: {}", e)))?;
+
+    let data = options.output()?.encode(&mut media, None)?;
+
+    Ok(HideResult::new(data, Vec::new()))
+}
+
+/// turns premultiplied into straight alpha, fully transparent pixels stay black
+fn unpremultiply(img: &mut RgbaImage) {
+    for pixel in img.pixels_mut() {
+        let alpha = pixel[3] as u16;
+        if alpha == u8::MAX as u16 {
+            continue;
+        }
+        for c in &mut pixel.0[..3] {
+            *c = match alpha {
+                0 => 0,
+                _ => ((*c as u16 * 255 + alpha / 2) / alpha).min(255) as u8,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_unpremultiply_the_colors() {
+        let mut img =
+            RgbaImage::from_raw(3, 1, vec![100, 50, 0, 128, 10, 20, 30, 255, 9, 9, 9, 0]).unwrap();
+        unpremultiply(&mut img);
+
+        assert_eq!(img.get_pixel(0, 0).0, [199, 100, 0, 128]);
+        assert_eq!(img.get_pixel(1, 0).0, [10, 20, 30, 255]);
+        assert_eq!(img.get_pixel(2, 0).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn should_swap_the_channel_order_both_ways() {
+        let bgr = vec![30, 20, 10, 60, 50, 40];
+        let img = ChannelOrder::Bgr.read_pixels(bgr.clone(), 2, 1).unwrap();
+        assert_eq!(img.get_pixel(0, 0).0, [10, 20, 30, 255]);
+        assert_eq!(img.get_pixel(1, 0).0, [40, 50, 60, 255]);
+        assert_eq!(ChannelOrder::Bgr.write_pixels(img.clone()), bgr);
+        assert_eq!(
+            ChannelOrder::Bgra.write_pixels(img),
+            vec![30, 20, 10, 255, 60, 50, 40, 255]
+        );
+
+        assert!(ChannelOrder::Bgra.read_pixels(bgr, 2, 1).is_none());
+    }
+
+    #[test]
+    fn should_unveil_bgra_pixels_hidden_as_bgra() {
+        let carrier = RgbaImage::from_fn(64, 64, |x, y| image::Rgba([x as u8, y as u8, 200, 255]));
+        let mut hide_options = HideOptions::new();
+        hide_options.set_channel_order(Some("bgra".to_string()));
+        let bgra = ChannelOrder::Bgra.write_pixels(carrier);
+
+        let stego = hide_data_into_pixels(
+            bgra,
+            64,
+            64,
+            "secret.txt",
+            b"Hello World".to_vec(),
+            &hide_options,
+        )
+        .unwrap()
+        .into_data();
+        assert_eq!(stego.len(), 64 * 64 * 4);
+
+        let mut unveil_options = UnveilOptions::new();
+        unveil_options.set_channel_order(Some("bgra".to_string()));
+        let mut result = unveil_data_with_pixels(stego, 64, 64, &unveil_options).unwrap();
+        let files = result.take_files();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name(), "secret.txt");
+    }
+}
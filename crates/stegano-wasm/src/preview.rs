@@ -0,0 +1,60 @@
+use image::ImageFormat;
+use stegano_core::media::Media;
+use wasm_bindgen::prelude::*;
+
+use crate::options::HideOptions;
+use crate::resumable::ResumableHide;
+use crate::HideResult;
+
+/// how many previews are emitted while hiding
+const PREVIEW_STEPS: usize = 10;
+
+/// Same as `hide_data_with_options`, but calls `on_preview(png, progress)` after every step of the hide,
+/// with a PNG of the output that fits into `preview_max_side` pixels and the progress from 0 to 1.
+/// This way the UI can render the stego image gradually.
+#[wasm_bindgen]
+pub fn hide_data_with_preview(
+    carrier_data: &[u8],
+    secret_name: &str,
+    secret_data: Vec<u8>,
+    options: &HideOptions,
+    on_preview: &js_sys::Function,
+    preview_max_side: u32,
+) -> Result<HideResult, JsValue> {
+    let mut hide = ResumableHide::new(carrier_data, secret_name, secret_data, options)?;
+    let step = (hide.len() as usize).div_ceil(PREVIEW_STEPS);
+
+    loop {
+        let done = hide.step(step)?;
+        let preview = hide.preview(preview_max_side)?;
+        on_preview.call2(
+            &JsValue::NULL,
+            &js_sys::Uint8Array::from(&preview[..]),
+            &JsValue::from_f64(hide.offset() / hide.len().max(1.0)),
+        )?;
+        if done {
+            return hide.finish();
+        }
+    }
+}
+
+/// a downscaled PNG of the carrier, audio has no preview
+pub(crate) fn preview_png(media: &Media, max_side: u32) -> Result<Vec<u8>, JsValue> {
+    let Media::Image(img) = media else {
+        return Err(JsValue::from_str(
+            "Failed to preview: only images have a preview",
+        ));
+    };
+
+    let scale = (max_side.max(1) as f64 / img.width().max(img.height()) as f64).min(1.0);
+    let width = ((img.width() as f64 * scale).round() as u32).max(1);
+    let height = ((img.height() as f64 * scale).round() as u32).max(1);
+    let preview = image::imageops::thumbnail(img, width, height);
+
+    let mut buf = std::io::Cursor::new(Vec::new());
+    preview
+        .write_to(&mut buf, ImageFormat::Png)
+        .map_err(|e| JsValue::from_str(&format!("Failed to encode preview: {}", e)))?;
+
+    Ok(buf.into_inner())
+}
@@ -0,0 +1,152 @@
+use image::ImageFormat;
+use stegano_core::media::{HideProgress, Media};
+use stegano_core::CodecOptions;
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+
+use crate::color::ColorProfilePolicy;
+use crate::decode;
+use crate::options::HideOptions;
+use crate::output::OutputFormat;
+use crate::preview::preview_png;
+use crate::{carrier_for, encoder_for, load_secret_media, HideResult};
+
+/// A hide that runs in steps, so that an interrupted browser session can resume it later.
+///
+/// To resume, keep the `snapshot()` of the carrier, the `payload()` and the `progress_token`
+/// after a step, and hand them to `ResumableHide.resume` in the next session.
+/// The payload is encrypted if a password was set, otherwise it holds the secret as it is.
+#[wasm_bindgen]
+pub struct ResumableHide {
+    media: Media,
+    payload: Zeroizing<Vec<u8>>,
+    progress: HideProgress,
+    codec_options: CodecOptions,
+    output_format: OutputFormat,
+    icc_profile: Option<Vec<u8>>,
+    /// what happened to the carrier, a resumed hide has none, they were handed out by the first session
+    warnings: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl ResumableHide {
+    /// Prepares the hide, nothing is hidden until `step` is called
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        carrier_data: &[u8],
+        secret_name: &str,
+        secret_data: Vec<u8>,
+        options: &HideOptions,
+    ) -> Result<ResumableHide, JsValue> {
+        let secret_data = Zeroizing::new(secret_data);
+        let payload = encoder_for(secret_name, &secret_data, options)?
+            .encode_payload()
+            .map_err(|e| JsValue::from_str(&format!("Failed to encode payload: {}", e)))?;
+
+        let carrier = carrier_for(
+            carrier_data,
+            options.payload_size(secret_name, secret_data.len())?,
+            options,
+        )?;
+
+        Ok(Self {
+            media: carrier.media,
+            progress: HideProgress::new(&payload),
+            payload: Zeroizing::new(payload),
+            codec_options: options.codec_options()?,
+            output_format: options.output()?,
+            icc_profile: carrier.icc_profile,
+            warnings: carrier.warnings,
+        })
+    }
+
+    /// Continues a hide from a snapshot taken in an earlier session
+    pub fn resume(
+        snapshot: &[u8],
+        payload: Vec<u8>,
+        progress_token: &str,
+        options: &HideOptions,
+    ) -> Result<ResumableHide, JsValue> {
+        let progress = HideProgress::from_token(progress_token)
+            .map_err(|e| JsValue::from_str(&format!("Failed to resume: {}", e)))?;
+
+        Ok(Self {
+            media: load_secret_media(snapshot)?,
+            payload: Zeroizing::new(payload),
+            progress,
+            codec_options: options.codec_options()?,
+            output_format: options.output()?,
+            icc_profile: match options.color_profile_policy()? {
+                ColorProfilePolicy::Preserve => decode::icc_profile(snapshot),
+                _ => None,
+            },
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Hides the next `max_bytes` of the payload, returns `true` once all is hidden
+    pub fn step(&mut self, max_bytes: usize) -> Result<bool, JsValue> {
+        self.media
+            .hide_step(
+                &self.payload,
+                &mut self.progress,
+                max_bytes,
+                &self.codec_options,
+            )
+            .map_err(|e| JsValue::from_str(&format!("Failed to hide data: {}", e)))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn is_done(&self) -> bool {
+        self.progress.is_done()
+    }
+
+    /// bytes hidden so far
+    #[wasm_bindgen(getter)]
+    pub fn offset(&self) -> f64 {
+        self.progress.offset() as f64
+    }
+
+    /// size of the whole payload in bytes
+    #[wasm_bindgen(getter)]
+    pub fn len(&self) -> f64 {
+        self.progress.len() as f64
+    }
+
+    /// Serialized progress, to be kept together with a `snapshot()`
+    #[wasm_bindgen(getter)]
+    pub fn progress_token(&self) -> String {
+        self.progress.to_token()
+    }
+
+    /// A PNG of everything hidden so far, downscaled to fit into `max_side` pixels
+    pub fn preview(&self, max_side: u32) -> Result<Vec<u8>, JsValue> {
+        preview_png(&self.media, max_side)
+    }
+
+    /// The encoded payload that is being hidden
+    pub fn payload(&self) -> Vec<u8> {
+        self.payload.to_vec()
+    }
+
+    /// The carrier as a PNG with everything hidden so far, it has to be lossless to be resumed from
+    pub fn snapshot(&mut self) -> Result<Vec<u8>, JsValue> {
+        OutputFormat::Image(ImageFormat::Png).encode(&mut self.media, self.icc_profile.as_deref())
+    }
+
+    /// The final image in the output format of the options, once all is hidden
+    pub fn finish(&mut self) -> Result<HideResult, JsValue> {
+        if !self.progress.is_done() {
+            return Err(JsValue::from_str(&format!(
+                "Failed to finish: only {} of {} bytes are hidden",
+                self.progress.offset(),
+                self.progress.len()
+            )));
+        }
+
+        let data = self
+            .output_format
+            .encode(&mut self.media, self.icc_profile.as_deref())?;
+        Ok(HideResult::new(data, self.warnings.clone()))
+    }
+}
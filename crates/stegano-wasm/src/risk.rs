@@ -0,0 +1,242 @@
+use image::ImageFormat;
+use wasm_bindgen::prelude::*;
+
+use crate::{decode, paste};
+
+/// bytes per pixel of a PNG of a photo, a rough estimate of the stego image size
+const PNG_BYTES_PER_PIXEL: u64 = 2;
+
+/// most mail servers refuse more than 25 MB, attachments grow by a third in base64
+const EMAIL_MAX_BYTES: u64 = 18 * 1024 * 1024;
+
+/// Where the stego image is going to be shared, see `assess_channel_risk`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SharingChannel {
+    /// a transfer that keeps the bytes, e.g. a cloud link or a USB stick
+    File,
+    Email,
+    /// messengers, that re-encode images sent as photos
+    Chat,
+    /// social networks, that re-encode every image
+    Social,
+}
+
+impl SharingChannel {
+    pub(crate) fn parse(channel: Option<&str>) -> Result<Self, JsValue> {
+        match channel {
+            None | Some("") | Some("file") => Ok(Self::File),
+            Some("email") => Ok(Self::Email),
+            Some("chat") => Ok(Self::Chat),
+            Some("social") => Ok(Self::Social),
+            Some(other) => Err(JsValue::from_str(&format!(
+                "Invalid sharing channel: {}",
+                other
+            ))),
+        }
+    }
+
+    /// the longest side the channel keeps, larger images are scaled down
+    fn max_side(self) -> Option<u32> {
+        match self {
+            Self::File | Self::Email => None,
+            Self::Chat => Some(1600),
+            Self::Social => Some(2048),
+        }
+    }
+
+    fn recompresses(self) -> bool {
+        matches!(self, Self::Chat | Self::Social)
+    }
+}
+
+/// How likely the hidden data gets destroyed on the way, from `assess_channel_risk`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+/// The outcome of `assess_channel_risk`
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct ChannelRisk {
+    level: RiskLevel,
+    warnings: Vec<String>,
+    recommendations: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl ChannelRisk {
+    /// `"low"`, `"medium"` or `"high"`
+    #[wasm_bindgen(getter)]
+    pub fn level(&self) -> String {
+        match self.level {
+            RiskLevel::Low => "low",
+            RiskLevel::Medium => "medium",
+            RiskLevel::High => "high",
+        }
+        .to_string()
+    }
+
+    /// what is likely to happen to the stego image on the way
+    #[wasm_bindgen(getter)]
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.clone()
+    }
+
+    /// what to do instead, e.g. another way to send it
+    #[wasm_bindgen(getter)]
+    pub fn recommendations(&self) -> Vec<String> {
+        self.recommendations.clone()
+    }
+}
+
+impl ChannelRisk {
+    fn warn(&mut self, level: RiskLevel, warning: String, recommendation: String) {
+        self.level = self.level.max(level);
+        self.warnings.push(warning);
+        if !self.recommendations.contains(&recommendation) {
+            self.recommendations.push(recommendation);
+        }
+    }
+}
+
+/// Tells from the format, dimensions and color profile of the carrier if the hidden data will likely be destroyed
+/// on the intended sharing `channel`: `"file"` (default) for transfers that keep the bytes, `"email"`,
+/// `"chat"` for messengers or `"social"` for social networks. The data is hidden in the exact pixels,
+/// so any recompression or scaling on the way destroys it.
+#[wasm_bindgen]
+pub fn assess_channel_risk(
+    carrier_data: &[u8],
+    channel: Option<String>,
+) -> Result<ChannelRisk, JsValue> {
+    let channel = SharingChannel::parse(channel.as_deref())?;
+    let carrier_data = paste::normalize(carrier_data);
+    let format = image::guess_format(&carrier_data)
+        .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
+    // not every format that can be recognized can be decoded, e.g. JPEG
+    let dimensions = decode::dimensions(&carrier_data);
+
+    let mut risk = ChannelRisk {
+        level: RiskLevel::Low,
+        warnings: Vec::new(),
+        recommendations: Vec::new(),
+    };
+
+    if channel.recompresses() {
+        risk.warn(
+            RiskLevel::High,
+            "Images shared this way are re-encoded as JPEG, which destroys the hidden data"
+                .to_string(),
+            match channel {
+                SharingChannel::Chat => {
+                    "Send the stego image as a file or document, not as a photo"
+                }
+                _ => "Share a link to the unchanged file instead, e.g. from a cloud storage",
+            }
+            .to_string(),
+        );
+    }
+    if let (Some((width, height)), Some(max_side)) = (dimensions, channel.max_side()) {
+        if width.max(height) > max_side {
+            risk.warn(
+                RiskLevel::High,
+                format!(
+                    "The carrier of {}x{} pixels is larger than the {} pixels per side that are kept, \
+                     it will be scaled down",
+                    width, height, max_side
+                ),
+                format!("Scale the carrier down to at most {} pixels per side", max_side),
+            );
+        }
+    }
+
+    if format == ImageFormat::Jpeg {
+        risk.warn(
+            RiskLevel::Medium,
+            "The carrier is a JPEG, the stego image has to be a lossless PNG or WebP, \
+             which platforms tend to convert back to JPEG"
+                .to_string(),
+            "Use a screenshot or a PNG as carrier".to_string(),
+        );
+    }
+    let estimated_size = match (format, dimensions) {
+        (ImageFormat::Png, _) => carrier_data.len() as u64,
+        (_, Some((width, height))) => width as u64 * height as u64 * PNG_BYTES_PER_PIXEL,
+        (_, None) => 0,
+    };
+    if channel == SharingChannel::Email && estimated_size > EMAIL_MAX_BYTES {
+        risk.warn(
+            RiskLevel::Medium,
+            format!(
+                "The stego image will be about {} MB, more than most mail servers accept",
+                estimated_size / (1024 * 1024)
+            ),
+            "Use a smaller carrier or share a link to the file".to_string(),
+        );
+    }
+    if channel.recompresses() && decode::icc_profile(&carrier_data).is_some() {
+        risk.warn(
+            RiskLevel::Medium,
+            "The carrier has a color profile, platforms that convert it to sRGB change every pixel"
+                .to_string(),
+            "Set `HideOptions.color_profile` to \"srgb\"".to_string(),
+        );
+    }
+
+    Ok(risk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn carrier(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+        let mut data = std::io::Cursor::new(Vec::new());
+        image::RgbImage::new(width, height)
+            .write_to(&mut data, format)
+            .unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn should_rate_the_risk_by_channel() {
+        let png = carrier(400, 300, ImageFormat::Png);
+
+        let risk = assess_channel_risk(&png, None).unwrap();
+        assert_eq!(risk.level(), "low");
+        assert!(risk.warnings().is_empty());
+
+        let risk = assess_channel_risk(&png, Some("chat".to_string())).unwrap();
+        assert_eq!(risk.level(), "high");
+        assert_eq!(
+            risk.recommendations(),
+            ["Send the stego image as a file or document, not as a photo"]
+        );
+    }
+
+    #[test]
+    fn should_warn_about_scaling_on_social_networks() {
+        let png = carrier(3000, 100, ImageFormat::Png);
+
+        let risk = assess_channel_risk(&png, Some("social".to_string())).unwrap();
+        assert_eq!(risk.level(), "high");
+        assert_eq!(risk.warnings().len(), 2);
+        assert!(risk.warnings()[1].contains("3000x100"));
+    }
+
+    #[test]
+    fn should_warn_about_jpeg_carriers() {
+        let jpeg = [
+            0xff, 0xd8, 0xff, 0xe0, 0x00, 0x10, b'J', b'F', b'I', b'F', 0x00,
+        ];
+
+        let risk = assess_channel_risk(&jpeg, Some("email".to_string())).unwrap();
+        assert_eq!(risk.level(), "medium");
+        assert_eq!(
+            risk.recommendations(),
+            ["Use a screenshot or a PNG as carrier"]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+
+thread_local! {
+    /// all secrets that are alive in the wasm memory, so that `wipe_secrets` can reach them,
+    /// even if JS never frees the objects that own them
+    static SECRETS: RefCell<HashMap<u32, Zeroizing<Vec<u8>>>> = RefCell::new(HashMap::new());
+    static NEXT_ID: Cell<u32> = const { Cell::new(0) };
+}
+
+/// A handle to sensitive bytes kept in the registry, the bytes get zeroized on drop
+#[derive(Debug)]
+pub(crate) struct Secret(u32);
+
+impl Secret {
+    pub(crate) fn new(bytes: Vec<u8>) -> Self {
+        let id = NEXT_ID.with(|next| {
+            let id = next.get();
+            next.set(id.wrapping_add(1));
+            id
+        });
+        SECRETS.with(|secrets| secrets.borrow_mut().insert(id, Zeroizing::new(bytes)));
+
+        Self(id)
+    }
+
+    /// gives access to the bytes, or `None` if they have been wiped in the meantime
+    pub(crate) fn with_bytes<R>(&self, f: impl FnOnce(&[u8]) -> R) -> Option<R> {
+        SECRETS.with(|secrets| secrets.borrow().get(&self.0).map(|bytes| f(bytes)))
+    }
+
+    /// gives a copy of the bytes as string, e.g. for passwords
+    pub(crate) fn to_zeroizing_string(&self) -> Option<Zeroizing<String>> {
+        self.with_bytes(|b| Zeroizing::new(String::from_utf8_lossy(b).into_owned()))
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        SECRETS.with(|secrets| secrets.borrow_mut().remove(&self.0));
+    }
+}
+
+/// Zeroizes all passwords and unveiled file contents that are still held in the wasm memory.
+/// Any `KeyHandle` or `UnveiledFile` created before becomes unusable.
+#[wasm_bindgen]
+pub fn wipe_secrets() {
+    SECRETS.with(|secrets| secrets.borrow_mut().clear());
+}
+
+/// the number of secrets in the registry, or `None` if a trap left it in the middle of a change
+pub(crate) fn count() -> Option<usize> {
+    SECRETS.with(|secrets| secrets.try_borrow().ok().map(|secrets| secrets.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_wipe_all_secrets() {
+        let secret = Secret::new(b"hunter42".to_vec());
+        assert_eq!(
+            secret.with_bytes(|b| b.to_vec()),
+            Some(b"hunter42".to_vec())
+        );
+
+        wipe_secrets();
+        assert_eq!(secret.with_bytes(|b| b.to_vec()), None);
+    }
+
+    #[test]
+    fn should_remove_the_secret_on_drop() {
+        let id = {
+            let secret = Secret::new(b"hunter42".to_vec());
+            secret.0
+        };
+
+        SECRETS.with(|secrets| assert!(!secrets.borrow().contains_key(&id)));
+    }
+}
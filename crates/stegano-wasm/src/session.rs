@@ -0,0 +1,236 @@
+//! Hides and unveils that take their data in chunks and report the progress of each stage,
+//! so that a worker can feed a large secret from a stream and the page can show a progress bar.
+
+use stegano_core::media::HideProgress;
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+
+use crate::options::{HideOptions, UnveilOptions};
+use crate::{
+    carrier_from_decoded, decode, encoder_for, load_secret_media, unveil_report, wav, HideResult,
+    UnveilResult,
+};
+
+/// bytes of the payload that are hidden between two progress reports
+const EMBED_STEP: usize = 64 * 1024;
+
+/// The progress of a session, reported to the callback as `(stage, fraction)`
+#[derive(Debug)]
+struct Progress {
+    on_progress: Option<js_sys::Function>,
+    stage: &'static str,
+    fraction: f64,
+}
+
+impl Progress {
+    fn new(on_progress: Option<js_sys::Function>) -> Self {
+        Self {
+            on_progress,
+            stage: "pending",
+            fraction: 0.0,
+        }
+    }
+
+    /// a callback that throws cancels the session with its error
+    fn report(&mut self, stage: &'static str, fraction: f64) -> Result<(), JsValue> {
+        self.stage = stage;
+        self.fraction = fraction;
+        if let Some(on_progress) = &self.on_progress {
+            on_progress.call2(&JsValue::NULL, &JsValue::from_str(stage), &fraction.into())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Hides a secret that is handed over in chunks, created in JS with `new HideSession(carrier, name, onProgress)`.
+///
+/// `onProgress(stage, fraction)` is called with the stages `"decode"`, `"resize"`, `"embed"`, `"encode"`
+/// and `"done"`, the fraction goes from 0 to 1 within the embed stage. Throwing from it cancels the session.
+#[wasm_bindgen]
+pub struct HideSession {
+    carrier_data: Vec<u8>,
+    secret_name: String,
+    secret_data: Zeroizing<Vec<u8>>,
+    progress: Progress,
+}
+
+#[wasm_bindgen]
+impl HideSession {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        carrier_data: Vec<u8>,
+        secret_name: String,
+        on_progress: Option<js_sys::Function>,
+    ) -> HideSession {
+        Self {
+            carrier_data,
+            secret_name,
+            secret_data: Zeroizing::new(Vec::new()),
+            progress: Progress::new(on_progress),
+        }
+    }
+
+    /// Appends the next chunk of the secret
+    pub fn push_chunk(&mut self, chunk: &[u8]) {
+        self.secret_data.extend_from_slice(chunk);
+    }
+
+    /// bytes of the secret received so far
+    #[wasm_bindgen(getter)]
+    pub fn received(&self) -> f64 {
+        self.secret_data.len() as f64
+    }
+
+    /// the stage reported last, `"pending"` before `finish`
+    #[wasm_bindgen(getter)]
+    pub fn stage(&self) -> String {
+        self.progress.stage.to_string()
+    }
+
+    /// the fraction reported last, from 0 to 1
+    #[wasm_bindgen(getter)]
+    pub fn fraction(&self) -> f64 {
+        self.progress.fraction
+    }
+
+    /// Hides the secret with the settings of the options and returns the stego image, or WAV, with the carrier warnings.
+    /// The secret is zeroized afterwards, a session can only be finished once
+    pub fn finish(&mut self, options: &HideOptions) -> Result<HideResult, JsValue> {
+        let secret_data = std::mem::take(&mut self.secret_data);
+        let payload = encoder_for(&self.secret_name, &secret_data, options)?
+            .encode_payload()
+            .map_err(|e| JsValue::from_str(&format!("Failed to encode payload: {}", e)))?;
+        let payload = Zeroizing::new(payload);
+
+        self.progress.report("decode", 0.0)?;
+        let carrier = if wav::is_wav(&self.carrier_data) {
+            let carrier = wav::carrier(
+                &self.carrier_data,
+                options.payload_size(&self.secret_name, secret_data.len())?,
+                options,
+            )?;
+            self.progress.report("resize", 0.0)?;
+            carrier
+        } else {
+            let (img, icc_profile) = decode::load_image_with_profile(&self.carrier_data)?;
+            self.progress.report("resize", 0.0)?;
+            carrier_from_decoded(
+                img.to_rgba8(),
+                icc_profile,
+                options.payload_size(&self.secret_name, secret_data.len())?,
+                options,
+            )?
+        };
+
+        let mut media = carrier.media;
+        let codec_options = options.codec_options()?;
+        let mut hidden = HideProgress::new(&payload);
+        self.progress.report("embed", 0.0)?;
+        while !media
+            .hide_step(&payload, &mut hidden, EMBED_STEP, &codec_options)
+            .map_err(|e| JsValue::from_str(&format!("Failed to hide data: {}", e)))?
+        {
+            self.progress
+                .report("embed", hidden.offset() as f64 / payload.len() as f64)?;
+        }
+        self.progress.report("embed", 1.0)?;
+
+        self.progress.report("encode", 0.0)?;
+        let output = options
+            .output()?
+            .encode(&mut media, carrier.icc_profile.as_deref())?;
+        self.progress.report("done", 1.0)?;
+
+        Ok(HideResult::new(output, carrier.warnings))
+    }
+}
+
+/// Unveils from a stego image that is handed over in chunks, created in JS with `new UnveilSession(onProgress)`.
+///
+/// `onProgress(stage, fraction)` is called with the stages `"decode"`, `"unveil"` and `"done"`.
+/// Throwing from it cancels the session.
+#[wasm_bindgen]
+pub struct UnveilSession {
+    carrier_data: Vec<u8>,
+    progress: Progress,
+}
+
+#[wasm_bindgen]
+impl UnveilSession {
+    #[wasm_bindgen(constructor)]
+    pub fn new(on_progress: Option<js_sys::Function>) -> UnveilSession {
+        Self {
+            carrier_data: Vec::new(),
+            progress: Progress::new(on_progress),
+        }
+    }
+
+    /// Appends the next chunk of the stego image
+    pub fn push_chunk(&mut self, chunk: &[u8]) {
+        self.carrier_data.extend_from_slice(chunk);
+    }
+
+    /// bytes of the stego image received so far
+    #[wasm_bindgen(getter)]
+    pub fn received(&self) -> f64 {
+        self.carrier_data.len() as f64
+    }
+
+    /// the stage reported last, `"pending"` before `finish`
+    #[wasm_bindgen(getter)]
+    pub fn stage(&self) -> String {
+        self.progress.stage.to_string()
+    }
+
+    /// the fraction reported last, from 0 to 1
+    #[wasm_bindgen(getter)]
+    pub fn fraction(&self) -> f64 {
+        self.progress.fraction
+    }
+
+    /// Unveils with the given options, see `unveil_data_with_options`
+    pub fn finish(&mut self, options: &UnveilOptions) -> Result<UnveilResult, JsValue> {
+        self.progress.report("decode", 0.0)?;
+        let media = load_secret_media(&std::mem::take(&mut self.carrier_data))?;
+        self.progress.report("unveil", 0.0)?;
+        let result = unveil_report(options.unveil()?, media)?;
+        self.progress.report("done", 1.0)?;
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn carrier() -> Vec<u8> {
+        let mut carrier = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::from_pixel(400, 300, image::Rgba([90, 120, 150, 255]))
+            .write_to(&mut carrier, image::ImageFormat::Png)
+            .unwrap();
+        carrier.into_inner()
+    }
+
+    #[test]
+    fn should_hide_and_unveil_in_chunks() {
+        let secret: Vec<u8> = (0..40_000u32).map(|i| (i * 7 % 251) as u8).collect();
+
+        let mut hide = HideSession::new(carrier(), "large.bin".to_string(), None);
+        secret.chunks(4096).for_each(|chunk| hide.push_chunk(chunk));
+        assert_eq!(hide.received(), 40_000.0);
+        assert_eq!(hide.stage(), "pending");
+        let stego = hide.finish(&HideOptions::new()).unwrap().into_data();
+        assert_eq!((hide.stage().as_str(), hide.fraction()), ("done", 1.0));
+
+        let mut unveil = UnveilSession::new(None);
+        stego
+            .chunks(10_000)
+            .for_each(|chunk| unveil.push_chunk(chunk));
+        let files = unveil.finish(&UnveilOptions::new()).unwrap().take_files();
+        assert_eq!(unveil.stage(), "done");
+        assert_eq!(files[0].name(), "large.bin");
+        assert_eq!(files[0].data.with_bytes(<[u8]>::to_vec), Some(secret));
+    }
+}
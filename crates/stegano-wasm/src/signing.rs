@@ -0,0 +1,129 @@
+use stegano_core::{FileVerification, ManifestVerification};
+use wasm_bindgen::prelude::*;
+
+use crate::load_secret_media;
+use crate::options::UnveilOptions;
+
+/// The outcome of `verify_manifest`, one status per file
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct ManifestCheck {
+    verification: ManifestVerification,
+}
+
+#[wasm_bindgen]
+impl ManifestCheck {
+    /// `true` if the manifest was signed by the given public key, without it no status can be trusted
+    #[wasm_bindgen(getter)]
+    pub fn signature_valid(&self) -> bool {
+        self.verification.signature_valid
+    }
+
+    /// the files that were listed or unveiled, the listed ones first
+    #[wasm_bindgen(getter)]
+    pub fn file_names(&self) -> Vec<String> {
+        self.verification
+            .files
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// `"verified"`, `"modified"`, `"missing"` or `"unlisted"`, in the same order as `file_names`
+    #[wasm_bindgen(getter)]
+    pub fn statuses(&self) -> Vec<String> {
+        self.verification
+            .files
+            .iter()
+            .map(|(_, status)| {
+                match status {
+                    FileVerification::Verified => "verified",
+                    FileVerification::Modified => "modified",
+                    FileVerification::Missing => "missing",
+                    FileVerification::Unlisted => "unlisted",
+                }
+                .to_string()
+            })
+            .collect()
+    }
+
+    /// `true` if the signature is valid and every file is verified
+    #[wasm_bindgen(getter)]
+    pub fn authentic(&self) -> bool {
+        self.verification.is_authentic()
+    }
+}
+
+/// Checks the hidden files against the manifest that was signed with `HideOptions.set_signing_key`,
+/// the signature is checked with the 32 bytes Ed25519 `public_key` of the sender.
+/// The password is taken from `options`, so a `KeyHandle` or threshold keys work the same.
+/// The file contents are not handed out, use `unveil_data_with_options` for them
+#[wasm_bindgen]
+pub fn verify_manifest(
+    carrier_data: &[u8],
+    public_key: Vec<u8>,
+    options: &UnveilOptions,
+) -> Result<ManifestCheck, JsValue> {
+    let public_key = ed25519_key("public", &public_key).map_err(|e| JsValue::from_str(&e))?;
+
+    let verification = options
+        .unveil()?
+        .from_media(load_secret_media(carrier_data)?)
+        .execute_to_verification(&public_key)
+        .map_err(|e| JsValue::from_str(&format!("Failed to verify: {}", e)))?;
+
+    Ok(ManifestCheck { verification })
+}
+
+/// an Ed25519 `kind` key, it has to be exactly 32 bytes
+pub(crate) fn ed25519_key(kind: &str, key: &[u8]) -> Result<[u8; 32], String> {
+    key.try_into()
+        .map_err(|_| format!("Invalid {kind} key: expected 32 bytes, got {}", key.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hide_data_with_options, HideOptions};
+
+    /// the first test vector of RFC 8032
+    const SECRET_KEY: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    const PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn should_verify_a_signed_carrier() {
+        let mut carrier = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::from_pixel(64, 64, image::Rgba([90, 120, 150, 255]))
+            .write_to(&mut carrier, image::ImageFormat::Png)
+            .unwrap();
+        let mut options = HideOptions::new();
+        options.set_signing_key(Some(from_hex(SECRET_KEY))).unwrap();
+        assert!(ed25519_key("signing", &[0; 31]).is_err());
+
+        let stego = hide_data_with_options(
+            carrier.get_ref(),
+            "secret.txt",
+            b"Hello World".to_vec(),
+            &options,
+        )
+        .unwrap()
+        .into_data();
+
+        let check = verify_manifest(&stego, from_hex(PUBLIC_KEY), &UnveilOptions::new()).unwrap();
+        assert!(check.authentic());
+        assert_eq!(check.file_names(), ["secret.txt"]);
+        assert_eq!(check.statuses(), ["verified"]);
+
+        let mut other_key = from_hex(PUBLIC_KEY);
+        other_key[0] ^= 1;
+        let check = verify_manifest(&stego, other_key, &UnveilOptions::new()).unwrap();
+        assert!(!check.signature_valid());
+    }
+}
@@ -0,0 +1,132 @@
+use std::io::{Cursor, Write};
+
+use image::ImageFormat;
+use stegano_core::media::image::BandEncoder;
+use stegano_core::SteganoEncoder;
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+
+use crate::color::{ColorProfilePolicy, PROFILE_DROPPED};
+use crate::decode;
+use crate::options::HideOptions;
+use crate::output::OutputFormat;
+use crate::verify::HideResult;
+
+/// PNG carriers with more pixels than this are decoded strip by strip
+const STRIP_MIN_PIXELS: u64 = 4096 * 4096;
+
+/// rows that are decoded, hidden into and encoded at once
+const STRIP_ROWS: u32 = 64;
+
+/// Hides the payload of `encoder` in a huge PNG carrier, strip by strip, so that the decoded image
+/// is never fully in memory. Returns `None` for any other carrier, which is hidden the regular way,
+/// e.g. if it is small, interlaced, has to be upscaled or the output is not a PNG.
+pub(crate) fn hide_in_strips(
+    carrier_data: &[u8],
+    encoder: &SteganoEncoder,
+    options: &HideOptions,
+) -> Result<Option<HideResult>, JsValue> {
+    if options.output()? != OutputFormat::Image(ImageFormat::Png)
+        || options.color_profile_policy()? != ColorProfilePolicy::Ignore
+        || image::guess_format(carrier_data).ok() != Some(ImageFormat::Png)
+    {
+        return Ok(None);
+    }
+    decode::check_limits(carrier_data)?;
+
+    let mut decoder = png::Decoder::new(Cursor::new(carrier_data));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let Ok(mut reader) = decoder.read_info() else {
+        return Ok(None);
+    };
+    let (width, height, interlaced, has_profile) = {
+        let info = reader.info();
+        (
+            info.width,
+            info.height,
+            info.interlaced,
+            info.icc_profile.is_some(),
+        )
+    };
+    if interlaced || (width as u64 * height as u64) < STRIP_MIN_PIXELS {
+        return Ok(None);
+    }
+
+    let payload = Zeroizing::new(
+        encoder
+            .encode_payload()
+            .map_err(|e| JsValue::from_str(&format!("Failed to hide data: {}", e)))?,
+    );
+    let codec_options = options.codec_options()?;
+    let Ok(band_encoder) = BandEncoder::new(&payload, width, height, &codec_options) else {
+        return Ok(None);
+    };
+
+    let (color_type, _) = reader.output_color_type();
+    let line_size = reader.output_line_size(width).ok_or_else(|| {
+        JsValue::from_str("Failed to load image: the rows do not fit into memory")
+    })?;
+    let mut row = vec![0; line_size];
+    let mut band = Vec::with_capacity(width as usize * 4 * STRIP_ROWS as usize);
+
+    let mut output = Vec::new();
+    let mut png_encoder = png::Encoder::new(&mut output, width, height);
+    png_encoder.set_color(png::ColorType::Rgba);
+    png_encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = png_encoder.write_header().map_err(encoding_error)?;
+    let mut stream = writer.stream_writer().map_err(encoding_error)?;
+
+    let mut first_row = 0;
+    while first_row < height {
+        let rows = STRIP_ROWS.min(height - first_row);
+        band.clear();
+        for _ in 0..rows {
+            reader
+                .read_row(&mut row)
+                .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?
+                .ok_or_else(|| JsValue::from_str("Failed to load image: PNG ended early"))?;
+            push_rgba(&mut band, &row, color_type);
+        }
+
+        band_encoder
+            .hide_in_band(first_row, &mut band)
+            .map_err(|e| JsValue::from_str(&format!("Failed to hide data: {}", e)))?;
+        stream.write_all(&band).map_err(encoding_error)?;
+        first_row += rows;
+    }
+    stream.finish().map_err(encoding_error)?;
+    writer.finish().map_err(encoding_error)?;
+
+    let warnings = if has_profile {
+        vec![PROFILE_DROPPED.to_string()]
+    } else {
+        Vec::new()
+    };
+    Ok(Some(HideResult::new(output, warnings)))
+}
+
+/// appends a row of 8 bit samples as RGBA
+fn push_rgba(band: &mut Vec<u8>, row: &[u8], color_type: png::ColorType) {
+    match color_type {
+        png::ColorType::Grayscale => {
+            for &l in row {
+                band.extend_from_slice(&[l, l, l, u8::MAX]);
+            }
+        }
+        png::ColorType::GrayscaleAlpha => {
+            for la in row.chunks_exact(2) {
+                band.extend_from_slice(&[la[0], la[0], la[0], la[1]]);
+            }
+        }
+        png::ColorType::Rgb | png::ColorType::Indexed => {
+            for rgb in row.chunks_exact(3) {
+                band.extend_from_slice(&[rgb[0], rgb[1], rgb[2], u8::MAX]);
+            }
+        }
+        png::ColorType::Rgba => band.extend_from_slice(row),
+    }
+}
+
+fn encoding_error(e: impl std::fmt::Display) -> JsValue {
+    JsValue::from_str(&format!("Failed to encode image: {}", e))
+}
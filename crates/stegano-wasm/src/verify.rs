@@ -0,0 +1,180 @@
+use std::io::Read;
+
+use image::ImageFormat;
+use stegano_core::media::image::LsbCodec;
+use stegano_core::media::{audio, Media};
+use stegano_core::CodecOptions;
+use subtle::ConstantTimeEq;
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+
+use crate::options::HideOptions;
+use crate::output::OutputFormat;
+use crate::{carrier_for, encoder_for, load_secret_media, Carrier};
+
+/// The stego image of every hide, together with what happened to the carrier on the way
+/// and, for `hide_data_verified`, the outcome of the verification
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct HideResult {
+    data: Vec<u8>,
+    verified: Option<bool>,
+    warnings: Vec<String>,
+}
+
+impl HideResult {
+    /// the result of a hide that is not verified
+    pub(crate) fn new(data: Vec<u8>, warnings: Vec<String>) -> Self {
+        Self {
+            data,
+            verified: None,
+            warnings,
+        }
+    }
+
+    /// the stego image without copying it, for callers in Rust
+    pub(crate) fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+#[wasm_bindgen]
+impl HideResult {
+    /// the stego image in the output format, or WAV
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+
+    /// `true` if the payload could be extracted again from `data`,
+    /// `undefined` if the hide was not verified, see `hide_data_verified`
+    #[wasm_bindgen(getter)]
+    pub fn verified(&self) -> Option<bool> {
+        self.verified
+    }
+
+    /// what happened to the carrier on the way, e.g. to its color profile
+    #[wasm_bindgen(getter)]
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.clone()
+    }
+}
+
+/// Same as `hide_data_with_options`, but for WebP, AVIF, JPEG XL and WAV the produced bytes get decoded again,
+/// to confirm the payload survived the encoder. A PNG image is lossless and counts as verified right away.
+#[wasm_bindgen]
+pub fn hide_data_verified(
+    carrier_data: &[u8],
+    secret_name: &str,
+    secret_data: Vec<u8>,
+    options: &HideOptions,
+) -> Result<HideResult, JsValue> {
+    let secret_data = Zeroizing::new(secret_data);
+    let payload = encoder_for(secret_name, &secret_data, options)?
+        .encode_payload()
+        .map_err(|e| JsValue::from_str(&format!("Failed to encode payload: {}", e)))?;
+    let payload = Zeroizing::new(payload);
+    let codec_options = options.codec_options()?;
+
+    let Carrier {
+        mut media,
+        icc_profile,
+        warnings,
+    } = carrier_for(
+        carrier_data,
+        options.payload_size(secret_name, secret_data.len())?,
+        options,
+    )?;
+    media
+        .hide_data(payload.to_vec(), &codec_options)
+        .map_err(|e| JsValue::from_str(&format!("Failed to hide data: {}", e)))?;
+
+    let format = options.output()?;
+    let is_image = matches!(media, Media::Image(_));
+    let data = format.encode(&mut media, icc_profile.as_deref())?;
+    let verified = (is_image && format == OutputFormat::Image(ImageFormat::Png))
+        || payload_survived(&data, &payload, &codec_options);
+
+    Ok(HideResult {
+        data,
+        verified: Some(verified),
+        warnings,
+    })
+}
+
+/// decodes the stego image, or WAV, and compares the payload hidden inside with the expected one
+fn payload_survived(stego_bytes: &[u8], payload: &[u8], opts: &CodecOptions) -> bool {
+    let Ok(media) = load_secret_media(stego_bytes) else {
+        return false;
+    };
+    let mut decoder = match &media {
+        Media::Image(img) => LsbCodec::decoder(img, opts),
+        Media::Audio((_, samples)) => audio::LsbCodec::decoder(samples),
+    };
+    let mut extracted = Zeroizing::new(vec![0; payload.len()]);
+
+    decoder.read_exact(&mut extracted).is_ok() && bool::from(extracted.as_slice().ct_eq(payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_verify_a_lossless_webp() {
+        let mut carrier = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::from_pixel(64, 64, image::Rgba([90, 120, 150, 255]))
+            .write_to(&mut carrier, ImageFormat::Png)
+            .unwrap();
+        let mut options = HideOptions::new();
+        options.set_output_format(Some("webp".to_string()));
+
+        let result = hide_data_verified(
+            carrier.get_ref(),
+            "secret.txt",
+            b"Hello World".to_vec(),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(result.verified(), Some(true));
+        assert_eq!(
+            image::guess_format(&result.data()).unwrap(),
+            ImageFormat::WebP
+        );
+    }
+
+    #[test]
+    fn should_check_a_wav_instead_of_taking_it_as_png() {
+        let samples: Vec<i16> = (0..16_000).map(|i| (i % 512) as i16).collect();
+        let payload = b"the hidden payload";
+        let mut media = crate::wav::load(&crate::wav::tests::wav(&samples)).unwrap();
+        media
+            .hide_data(payload.to_vec(), &CodecOptions::default())
+            .unwrap();
+        let mut wav = std::io::Cursor::new(Vec::new());
+        media.save_to_writer(&mut wav, ImageFormat::Png).unwrap();
+
+        let opts = CodecOptions::default();
+        assert!(payload_survived(wav.get_ref(), payload, &opts));
+        assert!(!payload_survived(
+            wav.get_ref(),
+            b"another payload!!!",
+            &opts
+        ));
+    }
+
+    #[test]
+    fn should_verify_a_wav() {
+        let samples: Vec<i16> = (0..16_000).map(|i| (i % 512) as i16).collect();
+
+        let result = hide_data_verified(
+            &crate::wav::tests::wav(&samples),
+            "secret.txt",
+            b"Hello World".to_vec(),
+            &HideOptions::new(),
+        )
+        .unwrap();
+        assert_eq!(result.verified(), Some(true));
+        assert!(crate::wav::is_wav(&result.data()));
+    }
+}
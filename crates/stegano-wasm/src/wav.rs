@@ -0,0 +1,129 @@
+//! WAV carriers, the data is hidden in the least significant bit of each sample.
+//! The stego audio is always written as WAV, with the format of the carrier.
+
+use std::io::Cursor;
+
+use stegano_core::media::{Media, WavReader};
+use wasm_bindgen::prelude::*;
+
+use crate::options::HideOptions;
+use crate::Carrier;
+
+pub(crate) fn is_wav(data: &[u8]) -> bool {
+    data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WAVE"
+}
+
+/// Bytes that fit into the LSBs of the given number of samples, all channels together
+pub(crate) fn capacity(samples: usize) -> usize {
+    samples / 8
+}
+
+/// Reads the samples of a WAV with integer samples of up to 16 bits
+pub(crate) fn load(data: &[u8]) -> Result<Media, JsValue> {
+    let failed =
+        |e: &dyn std::fmt::Display| JsValue::from_str(&format!("Failed to load audio: {}", e));
+    let mut reader = WavReader::new(Cursor::new(data)).map_err(|e| failed(&e))?;
+    let spec = reader.spec();
+    let samples = reader
+        .samples::<i16>()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| failed(&e))?;
+
+    Ok(Media::from_audio((spec, samples)))
+}
+
+/// Loads the carrier audio and makes sure it can hold a payload of `payload_size` bytes,
+/// unlike images it cannot be upscaled
+pub(crate) fn carrier(
+    data: &[u8],
+    payload_size: usize,
+    options: &HideOptions,
+) -> Result<Carrier, JsValue> {
+    let media = load(data)?;
+    let Media::Audio((_, samples)) = &media else {
+        unreachable!("a WAV is loaded as audio");
+    };
+    let capacity = capacity(samples.len());
+    if payload_size > capacity {
+        return Err(JsValue::from_str(&format!(
+            "Audio too short! Capacity: {} bytes, Payload: {} bytes. Choose a longer audio file.",
+            capacity, payload_size
+        )));
+    }
+
+    let mut warnings = Vec::new();
+    if options.redundancy() > 1 {
+        warnings.push(
+            "The redundancy applies to images only, the audio carries each byte once".to_string(),
+        );
+    }
+
+    Ok(Carrier {
+        media,
+        icc_profile: None,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{hide_data, unveil_data};
+
+    /// a mono WAV of 16 bit samples at 8 kHz
+    pub(crate) fn wav(samples: &[i16]) -> Vec<u8> {
+        let data_len = samples.len() as u32 * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&16_000u32.to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        samples
+            .iter()
+            .for_each(|sample| wav.extend_from_slice(&sample.to_le_bytes()));
+        wav
+    }
+
+    fn carrier_audio() -> Vec<u8> {
+        let samples: Vec<i16> = (0..40_000)
+            .map(|i| ((i * 37) % 2000 - 1000) as i16)
+            .collect();
+        wav(&samples)
+    }
+
+    #[test]
+    fn should_hide_and_unveil_in_a_wav() {
+        let stego = hide_data(
+            &carrier_audio(),
+            "memo.txt",
+            b"hidden in a voice memo".to_vec(),
+            None,
+            false,
+            None,
+        )
+        .unwrap()
+        .into_data();
+        assert!(is_wav(&stego));
+
+        let files = unveil_data(&stego, None).unwrap();
+        assert_eq!(files[0].name(), "memo.txt");
+        assert_eq!(
+            files[0].data.with_bytes(<[u8]>::to_vec),
+            Some(b"hidden in a voice memo".to_vec())
+        );
+    }
+
+    #[test]
+    fn should_tell_the_capacity_of_a_wav() {
+        assert_eq!(capacity(40_000), 5000);
+        assert!(!is_wav(b"RIFF\0\0\0\0WEBPVP8L"));
+    }
+}
@@ -225,8 +225,10 @@ function App() {
       // hide_data signature: (carrier, name, secret, password, resize, format)
       const result = hide_data(carrierBytes, secretFile.name, secretBytes, passwordArg, autoResize, outputFormat);
 
+      result.warnings.forEach((warning: string) => console.warn(warning));
+
       const mimeType = outputFormat === 'png' ? 'image/png' : 'image/webp';
-      const blob = new Blob([result as any], { type: mimeType });
+      const blob = new Blob([result.data as any], { type: mimeType });
       const url = URL.createObjectURL(blob);
       setHiddenImageUrl(url);
     } catch (e: any) {